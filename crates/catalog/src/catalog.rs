@@ -1,6 +1,11 @@
+use crate::field::Field;
+use crate::predicate::Predicate;
 use crate::schema::{RecordId, Schema};
+use crate::serde::Serde;
 use crate::tuple::Tuple;
-use rustdb_error::Result;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rustdb_error::{errinput, Result};
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -14,6 +19,12 @@ pub struct TableInfo {
     schema: Schema,
 }
 
+impl TableInfo {
+    pub fn new(id: TableId, name: String, schema: Schema) -> Self {
+        Self { id, name, schema }
+    }
+}
+
 /// A catalog of relevant information and references to objects relevant to the query execution.
 /// Designed for use by executors in the execution engine of a DBMS, providing a centralized API
 /// for table creation and table lookup.
@@ -39,10 +50,11 @@ impl<S: StorageApi> Catalog<S> {
         }
     }
 
-    /// Creates a new table with the given name and schema.
+    /// Creates a new table with the given name and schema, in both the catalog and the storage
+    /// engine.
     ///
     /// NOTE: We do not allow more than one table to share the same table name!
-    pub fn create_table(&mut self, name: String, schema: Schema) -> &TableInfo {
+    pub fn create_table(&mut self, name: String, schema: Schema) -> Result<&TableInfo> {
         assert!(
             !self.table_names.contains_key(&name),
             "Table names must be unique."
@@ -53,6 +65,7 @@ impl<S: StorageApi> Catalog<S> {
             let id = self
                 .next_table_id
                 .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.storage.create_table(id, &name)?;
             self.table_names.insert(name.clone(), id);
 
             // Update the table metadata map.
@@ -60,7 +73,7 @@ impl<S: StorageApi> Catalog<S> {
             self.tables.insert(id, info);
             id
         };
-        self.tables.get(&new_table_id).unwrap()
+        Ok(self.tables.get(&new_table_id).unwrap())
     }
 
     /// Fetches the metadata for the table with given id, if one exists.
@@ -74,6 +87,42 @@ impl<S: StorageApi> Catalog<S> {
         self.tables.get(id)
     }
 
+    /// Drops the table with the given id, removing it from both the catalog and the storage
+    /// engine.
+    pub fn drop_table(&mut self, id: TableId) -> Result<()> {
+        let Some(info) = self.tables.get(&id) else {
+            return errinput!("Table {} does not exist", id);
+        };
+        self.storage.drop_table(id)?;
+        self.table_names.remove(&info.name);
+        self.tables.remove(&id);
+        Ok(())
+    }
+
+    /// Fetches an iterator over the rows of the table with the given id that satisfy `predicate`,
+    /// emitting only the given columns of each. The work is left to the storage engine (see
+    /// [`StorageApi::scan_filtered`]).
+    pub fn filtered_table_iter(
+        &self,
+        id: TableId,
+        columns: &[usize],
+        predicate: Option<Predicate>,
+    ) -> Result<Box<dyn ScanIterator>> {
+        let Some(info) = self.tables.get(&id) else {
+            return errinput!("Table {} does not exist", id);
+        };
+        self.storage
+            .scan_filtered(id, &info.schema, columns, predicate)
+    }
+
+    /// Fetches the statistics the storage engine keeps about the table with the given id.
+    pub fn table_stats(&self, id: TableId) -> Result<TableStats> {
+        if !self.tables.contains_key(&id) {
+            return errinput!("Table {} does not exist", id);
+        }
+        self.storage.table_stats(id)
+    }
+
     /// Fetches an iterator over table with the given id, if one exists.
     pub fn table_iter(&self, id: TableId) -> Option<S::ScanIterator> {
         self.storage.scan(id).map_or(None, |iter| Some(iter))
@@ -90,6 +139,61 @@ pub trait ScanIterator: Iterator<Item = Result<(RecordId, Tuple)>> {}
 /// Blanket implementation of ScanIterator for any `T` satisfying the trait bound.
 impl<T: Iterator<Item = Result<(RecordId, Tuple)>>> ScanIterator for T {}
 
+/// Statistics about the contents of a table, e.g. for an optimizer to estimate the cost of a plan
+/// with. Storage engines keep them up to date as the table is written to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TableStats {
+    /// Number of tuples in the table.
+    pub row_count: u64,
+    /// Number of pages the table's tuples are stored in.
+    pub page_count: u64,
+    /// Total size of the table's tuples, in bytes.
+    pub tuple_bytes: u64,
+    /// Number of deleted tuples whose space hasn't been reclaimed yet.
+    pub deleted_count: u64,
+}
+
+impl TableStats {
+    /// Returns the average size of the table's tuples in bytes, or 0 if it has none.
+    pub fn avg_tuple_size(&self) -> f64 {
+        if self.row_count == 0 {
+            return 0.0;
+        }
+        self.tuple_bytes as f64 / self.row_count as f64
+    }
+
+    /// Returns the fraction of the tuples the table stores that are deleted.
+    pub fn deleted_ratio(&self) -> f64 {
+        let stored = self.row_count + self.deleted_count;
+        if stored == 0 {
+            return 0.0;
+        }
+        self.deleted_count as f64 / stored as f64
+    }
+}
+
+/// How [`StorageApi::sample`] picks the tuples of a random sample of a table. Each holds the
+/// probability with which a page or tuple is part of the sample, between 0 and 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleMethod {
+    /// Pages are picked at random, and all of the tuples of a picked page are returned. This only
+    /// reads the picked pages, but tuples that are stored together are sampled together.
+    System(f64),
+    /// Tuples are picked at random, independently of each other. Every page has to be read.
+    Bernoulli(f64),
+}
+
+impl SampleMethod {
+    /// Returns the probability with which a page or tuple is sampled, checking it is valid.
+    pub fn probability(&self) -> Result<f64> {
+        let (SampleMethod::System(p) | SampleMethod::Bernoulli(p)) = *self;
+        if !(0.0..=1.0).contains(&p) {
+            return errinput!("Sampling probability {} is not between 0 and 1", p);
+        }
+        Ok(p)
+    }
+}
+
 /// Should be implemented by the storage engine we're using to enable an access interface between
 /// the execution engine using this Catalog and its corresponding storage engine.
 pub trait StorageApi {
@@ -103,7 +207,7 @@ pub trait StorageApi {
         Self: Sized;
 
     /// Creates a table with the given name and id.
-    fn create_table(&self, table_id: TableId, name: &str) -> Result<&TableInfo>;
+    fn create_table(&self, table_id: TableId, name: &str) -> Result<Arc<TableInfo>>;
 
     /// Retrieves a tuple, with record id `rid`, from the table with corresponding id `table_id`.
     fn get_tuple(&self, table_id: TableId, rid: RecordId) -> Result<Tuple>;
@@ -115,11 +219,96 @@ pub trait StorageApi {
     /// newly inserted tuple's record id.
     fn insert_tuple(&self, table_id: TableId, tuple: &Tuple) -> Result<RecordId>;
 
+    /// Inserts all the given tuples into the table with corresponding id `table_id`, returning
+    /// their record ids in order. Storage engines should override this with something faster
+    /// than inserting the tuples one by one, which is what the default implementation does.
+    fn insert_tuples(&self, table_id: TableId, tuples: &[Tuple]) -> Result<Vec<RecordId>> {
+        tuples
+            .iter()
+            .map(|tuple| self.insert_tuple(table_id, tuple))
+            .collect()
+    }
+
+    /// Drops the table with corresponding id `table_id`, reclaiming all of its pages.
+    fn drop_table(&self, table_id: TableId) -> Result<()>;
+
+    /// Removes every tuple from the table with corresponding id `table_id`, reclaiming its pages.
+    /// The table itself remains, empty.
+    fn truncate_table(&self, table_id: TableId) -> Result<()>;
+
+    /// Replaces the tuple with record id `rid` in the table with corresponding id `table_id`. The
+    /// tuple keeps its record id, even if the new tuple is larger than the old one.
+    fn update_tuple(&self, table_id: TableId, rid: RecordId, tuple: &Tuple) -> Result<()>;
+
     /// Retrieves an iterator that emits tuples from a table via sequential scan.
     fn scan(&self, table_id: TableId) -> Result<Self::ScanIterator>
     where
         Self: Sized;
 
+    /// Retrieves an iterator over the tuples of the table with corresponding id `table_id` that
+    /// satisfy `predicate` (or all of them, without one). Emitted tuples only hold the fields in
+    /// `columns`, in that order, serialized as if those were the only columns of `schema`.
+    ///
+    /// `schema` is the schema of the table's tuples. Storage engines should override this to
+    /// evaluate the predicate and projection as they read pages, instead of materializing every
+    /// tuple like the default implementation does.
+    fn scan_filtered(
+        &self,
+        table_id: TableId,
+        schema: &Schema,
+        columns: &[usize],
+        predicate: Option<Predicate>,
+    ) -> Result<Box<dyn ScanIterator>> {
+        if let Some(predicate) = &predicate {
+            predicate.validate(schema)?;
+        }
+        for &column in columns {
+            schema.column_at(column)?;
+        }
+        let columns = columns.to_vec();
+        let schema = schema.clone();
+        let iter = self.scan_dyn(table_id)?.filter_map(move |item| {
+            let (rid, tuple) = match item {
+                Ok(item) => item,
+                Err(e) => return Some(Err(e)),
+            };
+            let data = tuple.data();
+            let mut column = |i| Serde::deserialize_column(&data, &schema, i);
+            if let Some(predicate) = &predicate {
+                match predicate.evaluate(&mut column) {
+                    Ok(true) => {}
+                    Ok(false) => return None,
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+            let fields = columns.iter().map(|&i| column(i)).collect::<Result<Vec<Field>>>();
+            Some(fields.map(|fields| (rid, Tuple::new(Serde::serialize(&fields).into()))))
+        });
+        Ok(Box::new(iter))
+    }
+
+    /// Retrieves an iterator over a random sample of the tuples of the table with corresponding
+    /// id `table_id`, picked as `method` says. The same seed picks the same sample of the same
+    /// table, so samples are reproducible.
+    ///
+    /// The default implementation has no notion of pages, so it samples tuples for both methods.
+    fn sample(
+        &self,
+        table_id: TableId,
+        method: SampleMethod,
+        seed: u64,
+    ) -> Result<Box<dyn ScanIterator>> {
+        let probability = method.probability()?;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let iter = self
+            .scan_dyn(table_id)?
+            .filter(move |item| item.is_err() || rng.random_bool(probability));
+        Ok(Box::new(iter))
+    }
+
+    /// Returns statistics about the table with corresponding id `table_id`.
+    fn table_stats(&self, table_id: TableId) -> Result<TableStats>;
+
     /// [`StorageApi::scan`], but can be used from trait objects. This iterator uses dynamic
     /// dispatch, which incurs a runtime performance penalty.
     fn scan_dyn(&self, table_id: TableId) -> Result<Box<dyn ScanIterator>>;
@@ -5,6 +5,8 @@
 pub mod catalog;
 pub mod column;
 pub mod field;
+pub mod memory;
+pub mod predicate;
 pub mod schema;
 pub mod serde;
 pub mod tuple;
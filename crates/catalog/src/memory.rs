@@ -0,0 +1,244 @@
+use crate::catalog::{StorageApi, TableId, TableInfo, TableStats};
+use crate::schema::{RecordId, Schema};
+use crate::tuple::Tuple;
+use bytes::Bytes;
+use rustdb_error::{errinput, Result};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// A [`StorageApi`] that keeps every table in memory, for testing the catalog and the code
+/// executing queries without a buffer pool or a file on disk.
+///
+/// It follows the semantics of the disk-based storage engine: a tuple's record id never changes
+/// until its table is truncated or dropped, deleting a deleted tuple does nothing, and deleted
+/// tuples can't be read or updated. Record ids are the positions tuples were inserted at, so
+/// scans emit tuples in insertion order.
+#[derive(Default)]
+pub struct MemoryStorage {
+    /// Maps table id -> the table's metadata and tuples.
+    tables: RwLock<HashMap<TableId, MemoryTable>>,
+}
+
+struct MemoryTable {
+    info: Arc<TableInfo>,
+    /// The table's tuples, indexed by record id. Deleted tuples leave a `None` behind, so the
+    /// record ids of the tuples after them stay the same.
+    tuples: Vec<Option<Bytes>>,
+}
+
+/// The iterator returned by [`MemoryStorage::scan`]. It owns a snapshot of the table's tuples
+/// taken when the scan started, so writes made during the scan aren't seen by it.
+pub type MemoryScanIterator = std::vec::IntoIter<Result<(RecordId, Tuple)>>;
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty table whose metadata has the given schema. The storage doesn't look at
+    /// the schema, as tuples are stored as they are given.
+    pub fn create_table_with_schema(
+        &self,
+        table_id: TableId,
+        name: &str,
+        schema: Schema,
+    ) -> Result<Arc<TableInfo>> {
+        let mut tables = self.tables.write().unwrap();
+        if tables.contains_key(&table_id) {
+            return errinput!("Table already exists");
+        }
+        let info = Arc::new(TableInfo::new(table_id, name.to_string(), schema));
+        tables.insert(
+            table_id,
+            MemoryTable {
+                info: info.clone(),
+                tuples: Vec::new(),
+            },
+        );
+        Ok(info)
+    }
+
+    /// Returns the metadata the table with id `table_id` was created with.
+    pub fn table_info(&self, table_id: TableId) -> Result<Arc<TableInfo>> {
+        match self.tables.read().unwrap().get(&table_id) {
+            Some(table) => Ok(table.info.clone()),
+            None => errinput!("Table not found"),
+        }
+    }
+
+    /// Runs `f` on the tuples of the table with id `table_id`.
+    fn with_table<R>(
+        &self,
+        table_id: TableId,
+        f: impl FnOnce(&mut Vec<Option<Bytes>>) -> Result<R>,
+    ) -> Result<R> {
+        let mut tables = self.tables.write().unwrap();
+        let Some(table) = tables.get_mut(&table_id) else {
+            return errinput!("Table not found");
+        };
+        f(&mut table.tuples)
+    }
+}
+
+/// Returns the slot of the tuple with record id `rid`, failing if no tuple was inserted there.
+fn slot(tuples: &mut [Option<Bytes>], rid: RecordId) -> Result<&mut Option<Bytes>> {
+    match usize::try_from(rid).ok().and_then(|i| tuples.get_mut(i)) {
+        Some(slot) => Ok(slot),
+        None => errinput!("Tuple {} does not exist", rid),
+    }
+}
+
+impl StorageApi for MemoryStorage {
+    type ScanIterator = MemoryScanIterator;
+
+    /// Creates an empty table. The storage isn't told the table's schema, so the returned
+    /// metadata has one without columns; see [`MemoryStorage::create_table_with_schema`].
+    fn create_table(&self, table_id: TableId, name: &str) -> Result<Arc<TableInfo>> {
+        self.create_table_with_schema(table_id, name, Schema::new(&[]))
+    }
+
+    fn get_tuple(&self, table_id: TableId, rid: RecordId) -> Result<Tuple> {
+        self.with_table(table_id, |tuples| match slot(tuples, rid)? {
+            Some(data) => Ok(Tuple::new(data.clone())),
+            None => errinput!("Tuple {} was deleted", rid),
+        })
+    }
+
+    /// Deletes a tuple. Deleting it again does nothing.
+    fn delete_tuple(&self, table_id: TableId, rid: RecordId) -> Result<()> {
+        self.with_table(table_id, |tuples| {
+            slot(tuples, rid)?.take();
+            Ok(())
+        })
+    }
+
+    fn insert_tuple(&self, table_id: TableId, tuple: &Tuple) -> Result<RecordId> {
+        self.with_table(table_id, |tuples| {
+            tuples.push(Some(tuple.data()));
+            Ok(tuples.len() as RecordId - 1)
+        })
+    }
+
+    fn drop_table(&self, table_id: TableId) -> Result<()> {
+        match self.tables.write().unwrap().remove(&table_id) {
+            Some(_) => Ok(()),
+            None => errinput!("Table not found"),
+        }
+    }
+
+    /// Removes every tuple from a table. Record ids start over from 0.
+    fn truncate_table(&self, table_id: TableId) -> Result<()> {
+        self.with_table(table_id, |tuples| {
+            tuples.clear();
+            Ok(())
+        })
+    }
+
+    fn update_tuple(&self, table_id: TableId, rid: RecordId, tuple: &Tuple) -> Result<()> {
+        self.with_table(table_id, |tuples| match slot(tuples, rid)? {
+            Some(data) => {
+                *data = tuple.data();
+                Ok(())
+            }
+            None => errinput!("Cannot update deleted tuple {}", rid),
+        })
+    }
+
+    fn scan(&self, table_id: TableId) -> Result<Self::ScanIterator> {
+        self.with_table(table_id, |tuples| {
+            let tuples: Vec<_> = tuples
+                .iter()
+                .enumerate()
+                .filter_map(|(rid, data)| {
+                    let data = data.as_ref()?;
+                    Some(Ok((rid as RecordId, Tuple::new(data.clone()))))
+                })
+                .collect();
+            Ok(tuples.into_iter())
+        })
+    }
+
+    /// Counts the table's tuples. Tables have no pages, and deleted tuples take up no space.
+    fn table_stats(&self, table_id: TableId) -> Result<TableStats> {
+        self.with_table(table_id, |tuples| {
+            let live = tuples.iter().flatten();
+            Ok(TableStats {
+                row_count: live.clone().count() as u64,
+                tuple_bytes: live.map(|data| data.len() as u64).sum(),
+                ..TableStats::default()
+            })
+        })
+    }
+
+    fn scan_dyn(&self, table_id: TableId) -> Result<Box<dyn crate::catalog::ScanIterator>> {
+        Ok(Box::new(self.scan(table_id)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::catalog::{Catalog, StorageApi};
+    use crate::column::Column;
+    use crate::field::Field;
+    use crate::memory::MemoryStorage;
+    use crate::predicate::{CompareOp, Predicate};
+    use crate::schema::Schema;
+    use crate::serde::Serde;
+    use crate::tuple::Tuple;
+    use crate::types::Type;
+    use rustdb_error::Result;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_catalog_with_memory_storage() -> Result<()> {
+        let storage = Arc::new(MemoryStorage::new());
+        let mut catalog = Catalog::new(storage.clone());
+        let schema = Schema::new(&[
+            Column::new("a".to_string(), Type::Integer),
+            Column::new("b".to_string(), Type::Varchar),
+        ]);
+        // The catalog hands out ids from 0, and creates the table in the storage too.
+        catalog.create_table("t".to_string(), schema.clone())?;
+        let id = 0;
+        let info = storage.table_info(id)?;
+        assert!(storage.create_table(id, "t").is_err());
+
+        for i in 0..10 {
+            let fields = [Field::Integer(i), Field::Varchar(i.to_string())];
+            storage.insert_tuple(id, &Tuple::new(Serde::serialize(&fields).into()))?;
+        }
+        storage.delete_tuple(id, 3)?;
+
+        let predicate = Predicate::compare(0, CompareOp::Lt, 5);
+        let rows = catalog
+            .filtered_table_iter(id, &[1], Some(predicate.clone()))?
+            .map(|item| {
+                let (rid, tuple) = item?;
+                let project = Schema::new(&schema.columns()[1..]);
+                Ok((rid, Serde::deserialize(&tuple.data(), &project)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let expected: Vec<_> = [0, 1, 2, 4]
+            .into_iter()
+            .map(|i| (i, vec![Field::Varchar(i.to_string())]))
+            .collect();
+        assert_eq!(expected, rows);
+        assert_eq!(9, catalog.table_stats(id)?.row_count);
+
+        // A tuple that doesn't match the schema is an error, not a panic.
+        storage.insert_tuple(id, &Tuple::new(vec![1, 2].into()))?;
+        let mut rows = catalog.filtered_table_iter(id, &[1], Some(predicate))?;
+        assert!(rows.any(|item| item.is_err()));
+
+        catalog.drop_table(id)?;
+        assert!(storage.scan(id).is_err());
+        assert!(storage.table_info(id).is_err());
+        assert!(catalog.table_stats(id).is_err());
+        // The storage let go of the table's metadata, and the id can be used again.
+        assert_eq!(1, Arc::strong_count(&info));
+        storage.create_table(id, "t")?;
+        assert_eq!(0, storage.scan(id)?.count());
+
+        Ok(())
+    }
+}
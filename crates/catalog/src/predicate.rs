@@ -0,0 +1,203 @@
+use crate::field::Field;
+use crate::schema::Schema;
+use rustdb_error::{errdata, errinput, Result};
+use std::cmp::Ordering;
+
+/// An operator comparing a column's value with a constant.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+impl CompareOp {
+    fn holds(&self, ordering: Ordering) -> bool {
+        match self {
+            CompareOp::Eq => ordering.is_eq(),
+            CompareOp::NotEq => ordering.is_ne(),
+            CompareOp::Lt => ordering.is_lt(),
+            CompareOp::LtEq => ordering.is_le(),
+            CompareOp::Gt => ordering.is_gt(),
+            CompareOp::GtEq => ordering.is_ge(),
+        }
+    }
+}
+
+/// A condition on the fields of a tuple, simple enough for a storage engine to evaluate while
+/// scanning, before the tuple is materialized. Columns are referred to by their index in the
+/// table's schema.
+///
+/// Predicates use two-valued logic: comparing NULL with anything is false, so negating such a
+/// comparison is true.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Predicate {
+    /// Compares the value of `column` with `value`, which must have the column's type.
+    Compare {
+        column: usize,
+        op: CompareOp,
+        value: Field,
+    },
+    /// Whether the value of a column is NULL.
+    IsNull(usize),
+    And(Box<Predicate>, Box<Predicate>),
+    Or(Box<Predicate>, Box<Predicate>),
+    Not(Box<Predicate>),
+}
+
+impl Predicate {
+    pub fn compare(column: usize, op: CompareOp, value: impl Into<Field>) -> Self {
+        Predicate::Compare {
+            column,
+            op,
+            value: value.into(),
+        }
+    }
+
+    pub fn and(self, other: Predicate) -> Self {
+        Predicate::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Predicate) -> Self {
+        Predicate::Or(Box::new(self), Box::new(other))
+    }
+
+    /// Checks that every column the predicate refers to exists in `schema`, and that it is only
+    /// compared with values of its own type (or NULL).
+    pub fn validate(&self, schema: &Schema) -> Result<()> {
+        match self {
+            Predicate::Compare { column, value, .. } => {
+                let field_type = schema.column_at(*column)?.field_type();
+                if !matches!(value, Field::Null) && value.get_type() != field_type {
+                    return errinput!(
+                        "Cannot compare column {} of type {} with {}",
+                        column,
+                        field_type,
+                        value.get_type()
+                    );
+                }
+                Ok(())
+            }
+            Predicate::IsNull(column) => schema.column_at(*column).map(|_| ()),
+            Predicate::And(a, b) | Predicate::Or(a, b) => {
+                a.validate(schema)?;
+                b.validate(schema)
+            }
+            Predicate::Not(a) => a.validate(schema),
+        }
+    }
+
+    /// Evaluates the predicate, calling `column` to get the value of each column it needs. Columns
+    /// that don't affect the outcome, e.g. on the right of an `And` whose left side is false,
+    /// aren't requested.
+    pub fn evaluate<F: FnMut(usize) -> Result<Field>>(&self, column: &mut F) -> Result<bool> {
+        match self {
+            Predicate::Compare {
+                column: index,
+                op,
+                value,
+            } => {
+                let field = column(*index)?;
+                if matches!(field, Field::Null) || matches!(value, Field::Null) {
+                    return Ok(false);
+                }
+                if field.get_type() != value.get_type() {
+                    return errdata!(
+                        "Column {} holds a {}, not a {}",
+                        index,
+                        field.get_type(),
+                        value.get_type()
+                    );
+                }
+                Ok(op.holds(field.cmp(value)))
+            }
+            Predicate::IsNull(index) => Ok(matches!(column(*index)?, Field::Null)),
+            Predicate::And(a, b) => Ok(a.evaluate(column)? && b.evaluate(column)?),
+            Predicate::Or(a, b) => Ok(a.evaluate(column)? || b.evaluate(column)?),
+            Predicate::Not(a) => Ok(!a.evaluate(column)?),
+        }
+    }
+}
+
+impl std::ops::Not for Predicate {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Predicate::Not(Box::new(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::column::Column;
+    use crate::field::Field;
+    use crate::predicate::{CompareOp, Predicate};
+    use crate::schema::Schema;
+    use crate::types::Type;
+    use rustdb_error::Result;
+
+    #[test]
+    fn test_predicate_evaluate() -> Result<()> {
+        let row = [
+            Field::Integer(5),
+            Field::Varchar("hello".to_string()),
+            Field::Null,
+        ];
+        let mut requested = Vec::new();
+        let mut column = |i: usize| {
+            requested.push(i);
+            Ok(row[i].clone())
+        };
+
+        let predicate = Predicate::compare(0, CompareOp::GtEq, 5).and(Predicate::compare(
+            1,
+            CompareOp::NotEq,
+            "world",
+        ));
+        assert!(predicate.evaluate(&mut column)?);
+        assert!(!(!predicate.clone()).evaluate(&mut column)?);
+
+        // Comparisons with NULL are false, whichever side it is on.
+        assert!(!Predicate::compare(2, CompareOp::Eq, Field::Null).evaluate(&mut column)?);
+        assert!(!Predicate::compare(0, CompareOp::NotEq, Field::Null).evaluate(&mut column)?);
+        assert!(Predicate::IsNull(2).evaluate(&mut column)?);
+
+        // The right side of a short-circuited operator isn't evaluated.
+        requested.clear();
+        let mut column = |i: usize| {
+            requested.push(i);
+            Ok(row[i].clone())
+        };
+        Predicate::compare(0, CompareOp::Lt, 5)
+            .and(Predicate::IsNull(1))
+            .evaluate(&mut column)?;
+        Predicate::compare(0, CompareOp::Eq, 5)
+            .or(Predicate::IsNull(2))
+            .evaluate(&mut column)?;
+        assert_eq!(vec![0, 0], requested);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_predicate_validate() {
+        let schema = Schema::new(&[
+            Column::new("a".to_string(), Type::Integer),
+            Column::new("b".to_string(), Type::Varchar),
+        ]);
+        assert!(Predicate::compare(1, CompareOp::Eq, "x")
+            .or(Predicate::IsNull(0))
+            .validate(&schema)
+            .is_ok());
+        assert!(Predicate::compare(0, CompareOp::Eq, Field::Null)
+            .validate(&schema)
+            .is_ok());
+        assert!(Predicate::compare(0, CompareOp::Eq, "x")
+            .validate(&schema)
+            .is_err());
+        assert!((!Predicate::IsNull(2)).validate(&schema).is_err());
+    }
+}
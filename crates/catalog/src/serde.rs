@@ -1,3 +1,6 @@
+use rustdb_error::{errdata, errinput, Result};
+
+use crate::column::Column;
 use crate::field::Field;
 use crate::schema::Schema;
 use crate::types::Type;
@@ -108,6 +111,62 @@ impl Serde {
 
         fields
     }
+
+    /// Deserializes only the `column`-th field of a serialized tuple, without decoding any of the
+    /// others. Fails if the schema has no such column, or if `bytes` can't hold a tuple of the
+    /// schema, e.g. because one of its varchar offsets points outside of it.
+    pub fn deserialize_column(bytes: &[u8], schema: &Schema, column: usize) -> Result<Field> {
+        let columns = schema.columns();
+        let Some(field_type) = columns.get(column).map(Column::field_type) else {
+            return errinput!("Column {} is out of range for schema {}", column, schema);
+        };
+        let fixed_size: usize = columns.iter().map(|c| c.field_type().size()).sum();
+        if bytes.len() < fixed_size {
+            return errdata!(
+                "Tuple of {} bytes is too small for schema {}",
+                bytes.len(),
+                schema
+            );
+        }
+        let start: usize = columns[..column]
+            .iter()
+            .map(|c| c.field_type().size())
+            .sum();
+        // Offsets lie in the fixed-size part, which was checked to be there.
+        let read_offset =
+            |i: usize| usize::from_le_bytes(bytes[i..i + size_of::<usize>()].try_into().unwrap());
+
+        match field_type {
+            Type::Null => Ok(Field::Null),
+            Type::Varchar => {
+                // The field ends where the next variable-length field starts, if there is one.
+                let mut i = start + Type::Varchar.size();
+                let mut end = bytes.len();
+                for c in &columns[column + 1..] {
+                    if c.field_type() == Type::Varchar {
+                        end = read_offset(i);
+                        break;
+                    }
+                    i += c.field_type().size();
+                }
+                let offset = read_offset(start);
+                if offset < fixed_size || offset > end || end > bytes.len() {
+                    return errdata!(
+                        "Column {} spans bytes {}..{} of a tuple of {} bytes",
+                        column,
+                        offset,
+                        end,
+                        bytes.len()
+                    );
+                }
+                match String::from_utf8(bytes[offset..end].to_vec()) {
+                    Ok(value) => Ok(Field::Varchar(value)),
+                    Err(_) => errdata!("Column {} is not valid UTF-8", column),
+                }
+            }
+            ty => Ok(Field::from_bytes(&bytes[start..start + ty.size()], ty)),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -140,6 +199,44 @@ mod tests {
         assert_eq!(tuple, deserialized_tuple);
     }
 
+    #[test]
+    fn test_deserialize_column() {
+        let schema = Schema::new(&columns_from(vec![
+            Type::Varchar,
+            Type::Integer,
+            Type::Null,
+            Type::Varchar,
+            Type::Boolean,
+        ]));
+        let tuple = vec![
+            Field::Varchar("first".to_string()),
+            Field::Integer(7),
+            Field::Null,
+            Field::Varchar("second".to_string()),
+            Field::Boolean(true),
+        ];
+
+        let serialized_tuple = Serde::serialize(&tuple);
+        for (i, field) in tuple.iter().enumerate() {
+            assert_eq!(
+                *field,
+                Serde::deserialize_column(&serialized_tuple, &schema, i).unwrap()
+            );
+        }
+
+        // Bad columns and tuples that don't match the schema are errors, not panics.
+        assert!(Serde::deserialize_column(&serialized_tuple, &schema, 5).is_err());
+        assert!(Serde::deserialize_column(&serialized_tuple[..10], &schema, 1).is_err());
+        let mut corrupt = serialized_tuple.clone();
+        corrupt[..8].copy_from_slice(&1000_usize.to_le_bytes());
+        assert!(Serde::deserialize_column(&corrupt, &schema, 0).is_err());
+        assert!(Serde::deserialize_column(&corrupt, &schema, 1).is_ok());
+        let mut invalid = serialized_tuple;
+        let last = invalid.len() - 1;
+        invalid[last] = 0xff;
+        assert!(Serde::deserialize_column(&invalid, &schema, 3).is_err());
+    }
+
     fn columns_from(types: Vec<Type>) -> Vec<Column> {
         types
             .iter()
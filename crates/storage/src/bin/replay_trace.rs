@@ -0,0 +1,51 @@
+//! Replays a buffer pool access trace against replacement policies and reports hit ratios.
+//!
+//! Usage: `replay_trace <trace-file> <pool-sizes> <policy>...`, where pool sizes are comma
+//! separated and policies are named `lru-<k>`, `clock`, `arc` or `2q`. For example:
+//!
+//! ```text
+//! cargo run -p rustdb-storage --bin replay_trace -- trace.txt 16,64,256 lru-1 lru-2 arc
+//! ```
+use std::io::Write;
+
+use rustdb_storage::trace::access_trace::read_trace;
+use rustdb_storage::trace::replay::{replay_policy, Policy};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.len() < 3 {
+        return Err("usage: replay_trace <trace-file> <pool-sizes> <policy>...".into());
+    }
+
+    let trace = read_trace(&args[0])?;
+    let pool_sizes = args[1]
+        .split(',')
+        .map(str::parse)
+        .collect::<Result<Vec<usize>, _>>()?;
+    let policies = args[2..]
+        .iter()
+        .map(|name| name.parse())
+        .collect::<Result<Vec<Policy>, _>>()?;
+
+    let mut out = std::io::stdout().lock();
+    writeln!(
+        out,
+        "{:<8} {:>10} {:>10} {:>10} {:>10} {:>9}",
+        "policy", "pool_size", "hits", "misses", "failed", "hit_ratio"
+    )?;
+    for policy in policies {
+        for stats in replay_policy(&trace, policy, &pool_sizes) {
+            writeln!(
+                out,
+                "{:<8} {:>10} {:>10} {:>10} {:>10} {:>9.4}",
+                policy.to_string(),
+                stats.pool_size,
+                stats.hits,
+                stats.misses,
+                stats.failed_pins,
+                stats.hit_ratio()
+            )?;
+        }
+    }
+    Ok(())
+}
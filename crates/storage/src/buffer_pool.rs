@@ -3,15 +3,27 @@ use rustdb_error::Error;
 use crate::disk::disk_manager::DiskManager;
 use crate::frame::PageFrame;
 use crate::frame_handle::{PageFrameMutHandle, PageFrameRefHandle};
+use crate::page::PAGE_SIZE;
 use crate::typedef::{FrameId, PageId};
 use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
 use std::sync::{Arc, Mutex, RwLock};
 
 use crate::Result;
 
-use crate::replacer::replacer::Replacer;
+use crate::replacer::replacer::{AccessType, Replacer};
+use crate::trace::access_trace::{TraceEvent, TraceRecorder};
+use std::path::Path;
+
+/// Number of optimistic attempts made by [`BufferPoolManager::with_page_optimistic`] before it
+/// falls back to taking the page's read lock.
+const OPTIMISTIC_READ_ATTEMPTS: usize = 3;
 
 /// Manages page allocation, caching, and eviction in the buffer pool.
+///
+/// Pages written through the pool are only marked dirty. They reach disk when their frame is
+/// evicted or when they are flushed with [`BufferPoolManager::flush_page`] or
+/// [`BufferPoolManager::flush_all`], which `StorageEngine::flush` and dropping the engine call.
 #[derive(Debug)]
 pub struct BufferPoolManager {
     frames: Vec<PageFrame>, // Storage for all frames in the buffer pool
@@ -19,6 +31,7 @@ pub struct BufferPoolManager {
     replacer: Box<dyn Replacer>, // Handles page replacement policy (e.g., LRU)
     free_list: VecDeque<FrameId>, // List of free frames
     disk_manager: Arc<Mutex<DiskManager>>, // Manages reading/writing pages to disk
+    trace: Option<TraceRecorder>, // Records pins and unpins while tracing is enabled
 }
 
 impl BufferPoolManager {
@@ -37,6 +50,30 @@ impl BufferPoolManager {
             replacer,
             free_list: (0..pool_size).collect(),
             disk_manager,
+            trace: None,
+        }
+    }
+
+    /// Starts recording every pin and unpin served by the buffer pool to the trace file at
+    /// `path`, replacing any trace that was being recorded.
+    pub(crate) fn start_trace(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        self.stop_trace()?;
+        self.trace = Some(TraceRecorder::new(path)?);
+        Ok(())
+    }
+
+    /// Stops recording the access trace and flushes it to disk.
+    pub(crate) fn stop_trace(&mut self) -> Result<()> {
+        match self.trace.take() {
+            Some(trace) => trace.finish(),
+            None => Ok(()),
+        }
+    }
+
+    /// Appends an event to the access trace, if one is being recorded.
+    fn record_trace(&mut self, event: TraceEvent) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.record(event);
         }
     }
 
@@ -83,28 +120,48 @@ impl BufferPoolManager {
         self.frames[free_frame].increment_pin_count();
 
         self.page_table.insert(new_page, free_frame);
-        self.replacer.record_access(free_frame);
+        self.replacer.record_page(free_frame, new_page);
+        self.replacer.record_access(free_frame, AccessType::Unknown);
         self.replacer.pin(free_frame);
 
+        drop(disk);
+        self.record_trace(TraceEvent::Pin {
+            page_id: new_page,
+            access_type: AccessType::Unknown,
+        });
+
         Ok(&mut self.frames[free_frame])
         
     }
 
     /// Fetches a mutable reference to a page, loading it from disk if necessary.
-    fn fetch_page_mut(&mut self, page_id: PageId) -> Result<&mut PageFrame> {
+    fn fetch_page_mut(
+        &mut self,
+        page_id: PageId,
+        access_type: AccessType,
+    ) -> Result<&mut PageFrame> {
 
         if self.page_table.contains_key(&page_id) {
             let frame = self.page_table[&page_id];
             self.frames[frame].increment_pin_count();
-            self.replacer.record_access(frame);
+            self.replacer.record_access(frame, access_type);
             self.replacer.pin(frame);
+            self.record_trace(TraceEvent::Pin {
+                page_id,
+                access_type,
+            });
             return Ok(&mut self.frames[frame]);
         }
         
         let free_frame = self.get_free_frame()?;
         let mut disk = self.disk_manager.lock()?;
-        let page_bytes = disk.read(page_id)?;
-        let page_data: &[u8] = &page_bytes.as_ref().unwrap();
+        let Some(page_bytes) = disk.read(page_id)? else {
+            // The page was deleted, e.g. by a vacuum that ran while a scan was on it.
+            drop(disk);
+            self.free_list.push_back(free_frame);
+            return Err(Error::InvalidInput(format!("Page {} does not exist", page_id)));
+        };
+        let page_data: &[u8] = &page_bytes;
 
         //read from disk
         self.frames[free_frame].set_page_id(page_id);
@@ -113,28 +170,44 @@ impl BufferPoolManager {
         //self.frames[free_frame].set_dirty(true);
 
         self.page_table.insert(page_id, free_frame);
-        self.replacer.record_access(free_frame);
+        self.replacer.record_page(free_frame, page_id);
+        self.replacer.record_access(free_frame, access_type);
         self.replacer.pin(free_frame);
 
+        drop(disk);
+        self.record_trace(TraceEvent::Pin {
+            page_id,
+            access_type,
+        });
+
         Ok(&mut self.frames[free_frame])
     }
 
     /// Fetches an immutable reference to a page.
-    fn fetch_page(&mut self, page_id: PageId) -> Result<&PageFrame> {
+    fn fetch_page(&mut self, page_id: PageId, access_type: AccessType) -> Result<&PageFrame> {
         
         if self.page_table.contains_key(&page_id) {
             let frame = self.page_table[&page_id];
             self.frames[frame].increment_pin_count();
-            self.replacer.record_access(frame);
+            self.replacer.record_access(frame, access_type);
             self.replacer.pin(frame);
+            self.record_trace(TraceEvent::Pin {
+                page_id,
+                access_type,
+            });
             return Ok(&mut self.frames[frame]);
         }
 
         // read from disk
         let free_frame = self.get_free_frame()?;
         let mut disk = self.disk_manager.lock()?;
-        let page_bytes = disk.read(page_id)?;
-        let page_data: &[u8] = &page_bytes.as_ref().unwrap();
+        let Some(page_bytes) = disk.read(page_id)? else {
+            // The page was deleted, e.g. by a vacuum that ran while a scan was on it.
+            drop(disk);
+            self.free_list.push_back(free_frame);
+            return Err(Error::InvalidInput(format!("Page {} does not exist", page_id)));
+        };
+        let page_data: &[u8] = &page_bytes;
 
 
         self.frames[free_frame].set_page_id(page_id);
@@ -142,13 +215,21 @@ impl BufferPoolManager {
         self.frames[free_frame].increment_pin_count();
 
         self.page_table.insert(page_id, free_frame);
-        self.replacer.record_access(free_frame);
+        self.replacer.record_page(free_frame, page_id);
+        self.replacer.record_access(free_frame, access_type);
         self.replacer.pin(free_frame);
 
+        drop(disk);
+        self.record_trace(TraceEvent::Pin {
+            page_id,
+            access_type,
+        });
+
         Ok(&self.frames[free_frame])
     }
 
-    /// Unpins a page, allowing it to be evicted if necessary.
+    /// Unpins a page, allowing it to be evicted if necessary. A page unpinned as dirty is not
+    /// written back yet, only when it is evicted or flushed.
     pub(crate) fn unpin_page(&mut self, page_id: PageId, is_dirty: bool) {
         if self.page_table.contains_key(&page_id) {
             
@@ -161,10 +242,12 @@ impl BufferPoolManager {
             if self.frames[frame_id].pin_count() == 0 {
                 self.replacer.unpin(frame_id);
             }
+            self.record_trace(TraceEvent::Unpin { page_id });
 
-            // ??????
-            let flushed = self.flush_page(&page_id);
-            self.frames[frame_id].set_dirty(is_dirty);
+            // A dirty page stays dirty until it is written back, on eviction or when flushed.
+            if is_dirty {
+                self.frames[frame_id].set_dirty(true);
+            }
         }
     }
 
@@ -172,6 +255,11 @@ impl BufferPoolManager {
     pub(crate) fn delete_page(&mut self, page_id: PageId) -> Result<()> {
 
         if !self.page_table.contains_key(&page_id) {
+            // The page isn't cached, but it may still take up space on disk.
+            let mut disk = self.disk_manager.lock()?;
+            if disk.contains_page(page_id) {
+                disk.deallocate_page(page_id)?;
+            }
             return Ok(()) ;
         }
 
@@ -199,6 +287,20 @@ impl BufferPoolManager {
         Ok(())
     }
 
+    /// Deletes all the given pages from the buffer pool and disk. If any of them is pinned, fails
+    /// with `Error::PagePinned` before deleting anything.
+    pub(crate) fn delete_pages(&mut self, page_ids: &[PageId]) -> Result<()> {
+        let pinned = page_ids
+            .iter()
+            .find(|&&page_id| self.get_pin_count(page_id).is_some_and(|count| count > 0));
+        if let Some(&page_id) = pinned {
+            return Err(Error::PagePinned(page_id));
+        }
+        page_ids
+            .iter()
+            .try_for_each(|&page_id| self.delete_page(page_id))
+    }
+
     /// Flushes a specific page to disk.
     pub(crate) fn flush_page(&mut self, page_id: &PageId) -> Result<()> {
 
@@ -231,6 +333,14 @@ impl BufferPoolManager {
 
     }
 
+    /// Flushes every dirty page in the buffer pool to disk.
+    pub(crate) fn flush_all(&mut self) -> Result<()> {
+        let page_ids: Vec<PageId> = self.page_table.keys().copied().collect();
+        page_ids
+            .iter()
+            .try_for_each(|page_id| self.flush_page(page_id))
+    }
+
     /// Returns the total number of frames in the buffer pool.
     fn capacity(&self) -> usize {
         self.frames.len()
@@ -252,7 +362,7 @@ impl BufferPoolManager {
     /// Creates a new page and returns a handle for it.
     pub(crate) fn create_page_handle(
         bpm: &Arc<RwLock<BufferPoolManager>>,
-    ) -> Result<PageFrameMutHandle> {
+    ) -> Result<PageFrameMutHandle<'_>> {
         let page_frame = {
             let mut bpm_guard = bpm.write()?;
             // SAFETY:
@@ -280,35 +390,77 @@ impl BufferPoolManager {
         Ok(PageFrameMutHandle::new(&bpm, page_frame))
     }
 
-    /// Fetches a read-only handle to a page.
+    /// Fetches a read-only handle to a page, recording the access as `access_type`.
     pub(crate) fn fetch_page_handle(
         bpm: &Arc<RwLock<BufferPoolManager>>,
         page_id: PageId,
-    ) -> Result<PageFrameRefHandle> {
+        access_type: AccessType,
+    ) -> Result<PageFrameRefHandle<'_>> {
         let page_frame = {
             let mut bpm_guard = bpm.write()?;
             // SAFETY: see `create_page_handle`
             let bpm_ptr = &mut *bpm_guard as *mut BufferPoolManager;
-            unsafe { (*bpm_ptr).fetch_page(page_id)? }
+            unsafe { (*bpm_ptr).fetch_page(page_id, access_type)? }
         };
 
         Ok(PageFrameRefHandle::new(&bpm, page_frame))
     }
 
-    /// Fetches a mutable handle to a page.
+    /// Fetches a mutable handle to a page, recording the access as `access_type`.
     pub(crate) fn fetch_page_mut_handle(
         bpm: &Arc<RwLock<BufferPoolManager>>,
         page_id: PageId,
-    ) -> Result<PageFrameMutHandle> {
+        access_type: AccessType,
+    ) -> Result<PageFrameMutHandle<'_>> {
         let page_frame = {
             let mut bpm_guard = bpm.write()?;
             // SAFETY: see `create_page_handle`
             let bpm_ptr = &mut *bpm_guard as *mut BufferPoolManager;
-            unsafe { (*bpm_ptr).fetch_page_mut(page_id)? }
+            unsafe { (*bpm_ptr).fetch_page_mut(page_id, access_type)? }
         };
 
         Ok(PageFrameMutHandle::new(&bpm, page_frame))
     }
+
+    /// Runs `f` over the bytes in `range` of a page's data without taking the page's read lock,
+    /// returning its result.
+    ///
+    /// The page is pinned for the duration of the call. Only the bytes in `range` are copied,
+    /// optimistically, and the copy is only kept if no writer touched the page in the meantime
+    /// (see [`PageFrame::read_optimistic`]); after a few failed attempts we give up and take the
+    /// read lock instead. Either way, `f` runs exactly once, over consistent bytes. Callers
+    /// should keep `range` to what they look at, as copying the whole page would cost more than
+    /// taking the lock.
+    pub(crate) fn with_page_optimistic<R>(
+        bpm: &Arc<RwLock<BufferPoolManager>>,
+        page_id: PageId,
+        access_type: AccessType,
+        range: Range<usize>,
+        f: impl FnOnce(&[u8]) -> R,
+    ) -> Result<R> {
+        if range.start > range.end || range.end > PAGE_SIZE {
+            return Err(Error::OutOfBounds);
+        }
+        let page_frame = {
+            let mut bpm_guard = bpm.write()?;
+            // SAFETY: see `create_page_handle`
+            let bpm_ptr = &mut *bpm_guard as *mut BufferPoolManager;
+            unsafe { (*bpm_ptr).fetch_page(page_id, access_type)? }
+        };
+
+        let mut buf = vec![0; range.len()];
+        let copied = (0..OPTIMISTIC_READ_ATTEMPTS)
+            .any(|_| page_frame.read_optimistic(range.start, &mut buf));
+        let result = if copied {
+            f(&buf)
+        } else {
+            let _guard = page_frame.read_lock();
+            f(&page_frame.data()[range])
+        };
+
+        bpm.write()?.unpin_page(page_id, false);
+        Ok(result)
+    }
 }
 
 #[cfg(test)]
@@ -316,9 +468,11 @@ mod tests {
     use crate::disk::disk_manager::DiskManager;
     use crate::frame_handle::{PageFrameMutHandle, PageFrameRefHandle};
     use crate::page::PAGE_SIZE;
-    use crate::replacer::lru_k_replacer::LrukReplacer;
+    use crate::replacer::replacer::AccessType;
+    use crate::trace::access_trace::{read_trace, TraceEvent};
     use crate::{buffer_pool::BufferPoolManager, typedef::PageId};
     use rand::{rng, Rng};
+    use rustdb_error::Error;
     use serial_test::serial;
     use std::collections::HashMap;
     use std::sync::atomic::{AtomicBool, Ordering};
@@ -326,35 +480,9 @@ mod tests {
     use std::sync::{Arc, Condvar, Mutex};
     use std::thread;
     use std::time::Duration;
-
-    // Helper function to create a buffer pool manager with `n` pages.
-    fn get_bpm_arc_with_pool_size(pool_size: usize) -> Arc<RwLock<BufferPoolManager>> {
-        Arc::new(RwLock::new(get_bpm_with_pool_size(pool_size)))
-    }
-
-    fn get_bpm_arc_with_pool_size_and_file_name(
-        pool_size: usize,
-        file_name: &str,
-    ) -> Arc<RwLock<BufferPoolManager>> {
-        Arc::new(RwLock::new(get_bpm_with_pool_size_and_file_name(
-            pool_size, file_name,
-        )))
-    }
-
-    fn get_bpm_with_pool_size(pool_size: usize) -> BufferPoolManager {
-        let disk_manager = Arc::new(Mutex::new(DiskManager::new("test.db").unwrap()));
-        let replacer = Box::new(LrukReplacer::new(5));
-        BufferPoolManager::new(pool_size, disk_manager, replacer)
-    }
-
-    fn get_bpm_with_pool_size_and_file_name(
-        pool_size: usize,
-        file_name: &str,
-    ) -> BufferPoolManager {
-        let disk_manager = Arc::new(Mutex::new(DiskManager::new(file_name).unwrap()));
-        let replacer = Box::new(LrukReplacer::new(5));
-        BufferPoolManager::new(pool_size, disk_manager, replacer)
-    }
+    use crate::test_utils::{
+        get_bpm_arc, get_bpm_arc_with_pool_size, get_bpm_with_pool_size, get_disk_manager,
+    };
 
     // Helper function to create `n` pages in the buffer pool.
     fn create_n_pages(bpm: &Arc<RwLock<BufferPoolManager>>, n: usize) -> Vec<PageFrameMutHandle> {
@@ -421,10 +549,10 @@ mod tests {
         drop(page_handle1);
         drop(page_handle2);
 
-        let _read1 =
-            BufferPoolManager::fetch_page_handle(&bpm, page_id1).expect("Failed to fetch page 1");
-        let _read2 =
-            BufferPoolManager::fetch_page_handle(&bpm, page_id2).expect("Failed to fetch page 2");
+        let _read1 = BufferPoolManager::fetch_page_handle(&bpm, page_id1, AccessType::Unknown)
+            .expect("Failed to fetch page 1");
+        let _read2 = BufferPoolManager::fetch_page_handle(&bpm, page_id2, AccessType::Unknown)
+            .expect("Failed to fetch page 2");
 
         // All frames are now pinned, attempt to create another page.
         let result = BufferPoolManager::create_page_handle(&bpm);
@@ -479,7 +607,8 @@ mod tests {
         drop(pages);
         page_ids.iter().for_each(|&page_id| {
             let page_handle =
-                BufferPoolManager::fetch_page_handle(&bpm, page_id).expect("Failed to fetch page");
+                BufferPoolManager::fetch_page_handle(&bpm, page_id, AccessType::Unknown)
+                    .expect("Failed to fetch page");
             assert_eq!(page_handle.page_id(), page_id);
         });
     }
@@ -516,13 +645,14 @@ mod tests {
             .contains_key(&page_id_to_evict));
 
         // We should still be able to fetch that evicted page (from disk).
-        let fetched_page_handle = BufferPoolManager::fetch_page_handle(&bpm, page_id_to_evict)
-            .expect("Failed to fetch page");
+        let fetched_page_handle =
+            BufferPoolManager::fetch_page_handle(&bpm, page_id_to_evict, AccessType::Unknown)
+                .expect("Failed to fetch page");
         assert_eq!(fetched_page_handle.page_id(), page_id_to_evict);
 
         // Another fetch of that page (this time from the buffer pool!)
         let fetched_page_handle_again =
-            BufferPoolManager::fetch_page_handle(&bpm, page_id_to_evict)
+            BufferPoolManager::fetch_page_handle(&bpm, page_id_to_evict, AccessType::Unknown)
                 .expect("Failed to fetch page");
         assert_eq!(fetched_page_handle_again.page_id(), page_id_to_evict);
     }
@@ -580,7 +710,8 @@ mod tests {
         // Pin count: 25
         for _ in 0..25 {
             let page_handle =
-                BufferPoolManager::fetch_page_handle(&bpm, page_id).expect("Failed to fetch page");
+                BufferPoolManager::fetch_page_handle(&bpm, page_id, AccessType::Unknown)
+                    .expect("Failed to fetch page");
             page_handles.push(page_handle);
         }
         assert_eq!(bpm.read().unwrap().get_pin_count(page_id).unwrap(), 25);
@@ -597,7 +728,7 @@ mod tests {
     #[serial]
     fn test_bpm_flush_page() {
         let pool_size = 5;
-        let bpm = get_bpm_arc_with_pool_size_and_file_name(pool_size, "test.db");
+        let bpm = get_bpm_arc_with_pool_size(pool_size);
 
         // Create a new page and modify it
         let page_id = BufferPoolManager::create_page_handle(&bpm)
@@ -606,8 +737,9 @@ mod tests {
 
         let data = b"Test data";
         let page_data = {
-            let mut page_handle = BufferPoolManager::fetch_page_mut_handle(&bpm, page_id)
-                .expect("Failed to fetch page for writing");
+            let mut page_handle =
+                BufferPoolManager::fetch_page_mut_handle(&bpm, page_id, AccessType::Unknown)
+                    .expect("Failed to fetch page for writing");
             page_handle.write(0, data);
             page_handle.data().to_vec()
         };
@@ -629,11 +761,70 @@ mod tests {
 
         // Ensure the page is still in the buffer pool and is no longer dirty
         let mut binder = bpm.write().unwrap();
-        let frame = binder.fetch_page(page_id).expect("Failed to fetch page");
+        let frame = binder
+            .fetch_page(page_id, AccessType::Unknown)
+            .expect("Failed to fetch page");
         assert!(!frame.is_dirty(), "Page should not be dirty after flush");
         assert_eq!(frame.data(), page_data, "Page data should persist");
     }
 
+    #[test]
+    #[serial]
+    fn test_bpm_unpin_does_not_write() {
+        let disk_manager = get_disk_manager();
+        let bpm = get_bpm_arc(disk_manager.clone());
+        let mut page = BufferPoolManager::create_page_handle(&bpm).unwrap();
+        let page_id = page.page_id();
+        page.write(0, &[9; 8]);
+        drop(page);
+
+        // The written page is unpinned, but only reaches disk once it's flushed.
+        let on_disk = |disk_manager: &Mutex<DiskManager>| {
+            disk_manager.lock().unwrap().read(page_id).unwrap()
+        };
+        assert!(on_disk(&disk_manager).map_or(true, |data| data[..8] != [9; 8]));
+        bpm.write().unwrap().flush_page(&page_id).expect("Failed to flush page");
+        assert_eq!([9; 8], on_disk(&disk_manager).unwrap()[..8]);
+    }
+
+    #[test]
+    #[serial]
+    fn test_bpm_flush_all() {
+        let disk_manager = get_disk_manager();
+        let bpm = get_bpm_arc(disk_manager.clone());
+        let page_ids: Vec<PageId> = create_n_pages(&bpm, 3)
+            .into_iter()
+            .map(|page| page.page_id())
+            .collect();
+        for (i, &page_id) in page_ids.iter().enumerate() {
+            let mut page =
+                BufferPoolManager::fetch_page_mut_handle(&bpm, page_id, AccessType::Unknown)
+                    .unwrap();
+            page.write(0, &[i as u8 + 1; 8]);
+            drop(page);
+            drop(BufferPoolManager::fetch_page_handle(&bpm, page_id, AccessType::Unknown).unwrap());
+        }
+
+        // Unpinning a written page leaves it dirty, even once it's read again, until it's flushed.
+        let dirty = |bpm: &BufferPoolManager| {
+            page_ids
+                .iter()
+                .filter(|page_id| bpm.frames[bpm.page_table[page_id]].is_dirty())
+                .count()
+        };
+        assert_eq!(3, dirty(&bpm.read().unwrap()));
+        bpm.write().unwrap().flush_all().expect("Failed to flush pages");
+        assert_eq!(0, dirty(&bpm.read().unwrap()));
+
+        // A new pool over the same disk reads the pages back as they were written.
+        let bpm = get_bpm_arc(disk_manager);
+        for (i, &page_id) in page_ids.iter().enumerate() {
+            let page =
+                BufferPoolManager::fetch_page_handle(&bpm, page_id, AccessType::Unknown).unwrap();
+            assert_eq!(&[i as u8 + 1; 8], &page.data()[..8]);
+        }
+    }
+
     #[test]
     #[serial]
     fn test_bpm_evict_flush_page() {
@@ -647,8 +838,9 @@ mod tests {
 
         let data = b"Test data";
         let page_data = {
-            let mut page_handle = BufferPoolManager::fetch_page_mut_handle(&bpm, page_id)
-                .expect("Failed to fetch page for writing");
+            let mut page_handle =
+                BufferPoolManager::fetch_page_mut_handle(&bpm, page_id, AccessType::Unknown)
+                    .expect("Failed to fetch page for writing");
             page_handle.write(0, data);
             page_handle.data().to_vec()
         };
@@ -665,7 +857,9 @@ mod tests {
         // Ensure the page is still in the buffer pool and is no longer dirty
         let mut binder = bpm.write().unwrap();
         // Bring the page back into the buffer pool
-        let frame = binder.fetch_page(page_id).expect("Failed to fetch page");
+        let frame = binder
+            .fetch_page(page_id, AccessType::Unknown)
+            .expect("Failed to fetch page");
         assert!(!frame.is_dirty(), "Page should not be dirty after flush");
         assert_eq!(frame.data(), page_data, "Page data should persist");
     }
@@ -688,6 +882,37 @@ mod tests {
         assert!(bpm.delete_page(page_id).is_ok());
     }
 
+    #[test]
+    #[serial]
+    fn test_bpm_delete_pages() {
+        let mut bpm = get_bpm_with_pool_size(2);
+        let page_ids: Vec<PageId> = (0..3)
+            .map(|_| {
+                let page_id = bpm.create_page().unwrap().page_id();
+                bpm.unpin_page(page_id, false);
+                page_id
+            })
+            .collect();
+        // The first page was evicted to make room for the last one.
+        assert_eq!(None, bpm.get_pin_count(page_ids[0]));
+
+        // Nothing is deleted while one of the pages is pinned.
+        bpm.fetch_page(page_ids[2], AccessType::Unknown).unwrap();
+        assert_eq!(
+            Err(Error::PagePinned(page_ids[2])),
+            bpm.delete_pages(&page_ids)
+        );
+        assert_eq!(Some(0), bpm.get_pin_count(page_ids[1]));
+
+        bpm.unpin_page(page_ids[2], false);
+        assert!(bpm.delete_pages(&page_ids).is_ok());
+        // Deleted pages can't be fetched anymore.
+        assert!(bpm.fetch_page(page_ids[1], AccessType::Unknown).is_err());
+        assert_eq!(2, bpm.free_list.len());
+        let disk = bpm.disk_manager.lock().unwrap();
+        assert!(page_ids.iter().all(|&page_id| !disk.contains_page(page_id)));
+    }
+
     #[test]
     #[serial]
     fn test_bpm_very_basic_test() {
@@ -704,8 +929,9 @@ mod tests {
 
         // Check WritePageGuard basic functionality
         {
-            let mut write_guard = BufferPoolManager::fetch_page_mut_handle(&bpm, pid)
-                .expect("Failed to fetch page for writing");
+            let mut write_guard =
+                BufferPoolManager::fetch_page_mut_handle(&bpm, pid, AccessType::Unknown)
+                    .expect("Failed to fetch page for writing");
 
             write_guard.write(0, str_data);
             let stored_data = &write_guard.data()[..str_data.len()];
@@ -715,7 +941,7 @@ mod tests {
 
         // Check ReadPageGuard basic functionality
         {
-            let read_guard = BufferPoolManager::fetch_page_handle(&bpm, pid)
+            let read_guard = BufferPoolManager::fetch_page_handle(&bpm, pid, AccessType::Unknown)
                 .expect("Failed to fetch page for reading");
 
             let stored_data = &read_guard.data()[..str_data.len()];
@@ -724,7 +950,7 @@ mod tests {
 
         // Check ReadPageGuard functionality again
         {
-            let read_guard = BufferPoolManager::fetch_page_handle(&bpm, pid)
+            let read_guard = BufferPoolManager::fetch_page_handle(&bpm, pid, AccessType::Unknown)
                 .expect("Failed to fetch page for reading again");
 
             let stored_data = &read_guard.data()[..str_data.len()];
@@ -764,12 +990,14 @@ mod tests {
         let str1 = b"page1";
 
         {
-            let mut page0_write = BufferPoolManager::fetch_page_mut_handle(&bpm, pageid0)
-                .expect("Failed to fetch page 0 for writing");
+            let mut page0_write =
+                BufferPoolManager::fetch_page_mut_handle(&bpm, pageid0, AccessType::Unknown)
+                    .expect("Failed to fetch page 0 for writing");
             page0_write.write(0, str0);
 
-            let mut page1_write = BufferPoolManager::fetch_page_mut_handle(&bpm, pageid1)
-                .expect("Failed to fetch page 1 for writing");
+            let mut page1_write =
+                BufferPoolManager::fetch_page_mut_handle(&bpm, pageid1, AccessType::Unknown)
+                    .expect("Failed to fetch page 1 for writing");
             page1_write.write(0, str1);
 
             // Ensure pin counts are still 1
@@ -802,7 +1030,6 @@ mod tests {
         );
     }
 
-
     #[test]
     #[serial]
     fn test_bpm_page_access() {
@@ -823,9 +1050,12 @@ mod tests {
 
                 {
                     // Use a scoped block to drop the write lock as soon as possible
-                    let mut page_handle =
-                        BufferPoolManager::fetch_page_mut_handle(&writer_bpm, pid)
-                            .expect("Failed to fetch page for writing");
+                    let mut page_handle = BufferPoolManager::fetch_page_mut_handle(
+                        &writer_bpm,
+                        pid,
+                        AccessType::Unknown,
+                    )
+                    .expect("Failed to fetch page for writing");
                     let data = i.to_string().into_bytes();
                     page_handle.write(0, &data);
                 } // Write lock is dropped here
@@ -837,8 +1067,9 @@ mod tests {
 
             // Read page while ensuring data consistency
             let page_data = {
-                let page_handle = BufferPoolManager::fetch_page_handle(&bpm, pid)
-                    .expect("Failed to fetch page for reading");
+                let page_handle =
+                    BufferPoolManager::fetch_page_handle(&bpm, pid, AccessType::Unknown)
+                        .expect("Failed to fetch page for reading");
                 let data = page_handle.data().to_vec();
                 data // Copy the data and drop the read lock early
             };
@@ -887,8 +1118,12 @@ mod tests {
             threads.push(thread::spawn(move || {
                 for i in 0..rounds {
                     // Acquire a mutable handle.
-                    let mut page_handle = BufferPoolManager::fetch_page_mut_handle(&local_bpm, pid)
-                        .expect("Failed to fetch page for writing");
+                    let mut page_handle = BufferPoolManager::fetch_page_mut_handle(
+                        &local_bpm,
+                        pid,
+                        AccessType::Unknown,
+                    )
+                    .expect("Failed to fetch page for writing");
 
                     // Write the iteration number as bytes.
                     let data = i.to_string().into_bytes();
@@ -1187,7 +1422,7 @@ mod tests {
         bpm: &Arc<RwLock<BufferPoolManager>>,
         pid: PageId,
     ) -> Option<PageFrameRefHandle> {
-        match BufferPoolManager::fetch_page_handle(bpm, pid) {
+        match BufferPoolManager::fetch_page_handle(bpm, pid, AccessType::Unknown) {
             Ok(h) => Some(h),
             Err(_) => None,
         }
@@ -1197,7 +1432,7 @@ mod tests {
         bpm: &Arc<RwLock<BufferPoolManager>>,
         pid: PageId,
     ) -> Option<PageFrameMutHandle> {
-        match BufferPoolManager::fetch_page_mut_handle(bpm, pid) {
+        match BufferPoolManager::fetch_page_mut_handle(bpm, pid, AccessType::Unknown) {
             Ok(h) => Some(h),
             Err(_) => None,
         }
@@ -1217,8 +1452,9 @@ mod tests {
         };
 
         // Pin the page for writing.
-        let mut page0_write = BufferPoolManager::fetch_page_mut_handle(&bpm, pid0)
-            .expect("Failed to fetch page0 for writing");
+        let mut page0_write =
+            BufferPoolManager::fetch_page_mut_handle(&bpm, pid0, AccessType::Unknown)
+                .expect("Failed to fetch page0 for writing");
 
         // Generate random data of PAGE_SIZE length.
         let mut rng = rng();
@@ -1245,8 +1481,9 @@ mod tests {
             let new_pid = BufferPoolManager::create_page_handle(&bpm)
                 .expect("Failed to create new page")
                 .page_id();
-            let page_handle = BufferPoolManager::fetch_page_mut_handle(&bpm, new_pid)
-                .expect("Failed to fetch newly created page for writing");
+            let page_handle =
+                BufferPoolManager::fetch_page_mut_handle(&bpm, new_pid, AccessType::Unknown)
+                    .expect("Failed to fetch newly created page for writing");
             pages.push(page_handle);
         }
 
@@ -1311,7 +1548,7 @@ mod tests {
 
         // 11) Fetch the original page (pid0) in read mode => compare random data.
         {
-            let page0_read = BufferPoolManager::fetch_page_handle(&bpm, pid0)
+            let page0_read = BufferPoolManager::fetch_page_handle(&bpm, pid0, AccessType::Unknown)
                 .expect("Failed to fetch original page0 for reading");
             assert_eq!(
                 &page0_read.data()[..],
@@ -1350,8 +1587,9 @@ mod tests {
             };
 
             // Pin the newly created page in write mode
-            let mut page_handle = BufferPoolManager::fetch_page_mut_handle(&bpm, page_id)
-                .expect("Failed to fetch newly created page for writing");
+            let mut page_handle =
+                BufferPoolManager::fetch_page_mut_handle(&bpm, page_id, AccessType::Unknown)
+                    .expect("Failed to fetch newly created page for writing");
 
             // Write data (e.g., the index `i`) for clarity
             let data = i.to_string().into_bytes();
@@ -1391,8 +1629,9 @@ mod tests {
                     BufferPoolManager::create_page_handle(&bpm).expect("Failed to create page");
                 handle.page_id()
             };
-            let mut page_handle = BufferPoolManager::fetch_page_mut_handle(&bpm, page_id)
-                .expect("Failed to fetch new page for writing");
+            let mut page_handle =
+                BufferPoolManager::fetch_page_mut_handle(&bpm, page_id, AccessType::Unknown)
+                    .expect("Failed to fetch new page for writing");
             // Optionally write some data
             let data = b"some new data";
             page_handle.write(0, data);
@@ -1420,8 +1659,9 @@ mod tests {
                     BufferPoolManager::create_page_handle(&bpm).expect("Failed to create page");
                 handle.page_id()
             };
-            let mut page_handle = BufferPoolManager::fetch_page_mut_handle(&bpm, page_id)
-                .expect("Failed to fetch page for writing");
+            let mut page_handle =
+                BufferPoolManager::fetch_page_mut_handle(&bpm, page_id, AccessType::Unknown)
+                    .expect("Failed to fetch page for writing");
             page_handle.write(0, b"some fresh data");
             pgs.push(page_handle);
         }
@@ -1455,7 +1695,7 @@ mod tests {
             page_handle.page_id()
         };
 
-        let guard0 = BufferPoolManager::fetch_page_mut_handle(&bpm, pid0)
+        let guard0 = BufferPoolManager::fetch_page_mut_handle(&bpm, pid0, AccessType::Unknown)
             .expect("Failed to fetch pid0 for writing in main thread");
 
         let start = Arc::new(AtomicBool::new(false));
@@ -1468,8 +1708,9 @@ mod tests {
             start_for_child.store(true, Ordering::Release);
 
             // Attempt to fetch pid0 in write mode – could deadlock if concurrency logic is wrong
-            let _guard0_child = BufferPoolManager::fetch_page_mut_handle(&bpm_for_child, pid0)
-                .expect("Child thread: fetch_page_mut_handle on pid0");
+            let _guard0_child =
+                BufferPoolManager::fetch_page_mut_handle(&bpm_for_child, pid0, AccessType::Unknown)
+                    .expect("Child thread: fetch_page_mut_handle on pid0");
             // If we get here, we successfully pinned pid0. We drop it at end of scope => unpin
         });
 
@@ -1482,7 +1723,7 @@ mod tests {
         thread::sleep(Duration::from_millis(1000));
 
         // Now we pin pid1 while still holding pid0
-        let _guard1 = BufferPoolManager::fetch_page_mut_handle(&bpm, pid1)
+        let _guard1 = BufferPoolManager::fetch_page_mut_handle(&bpm, pid1, AccessType::Unknown)
             .expect("Main thread: fetch_page_mut_handle on pid1");
 
         // We let go of pid0 now so the child can proceed
@@ -1536,12 +1777,21 @@ mod tests {
                     drop(guard);
 
                     // 1) Read-latch the winner page
-                    let _read_guard = BufferPoolManager::fetch_page_handle(&bpm_clone, winner_pid)
-                        .expect("Failed to read-latch the winner page");
+                    let _read_guard = BufferPoolManager::fetch_page_handle(
+                        &bpm_clone,
+                        winner_pid,
+                        AccessType::Unknown,
+                    )
+                    .expect("Failed to read-latch the winner page");
 
                     // 2) We expect no free frames, so loading the loser page should fail.
                     assert!(
-                        BufferPoolManager::fetch_page_handle(&bpm_clone, loser_pid).is_err(),
+                        BufferPoolManager::fetch_page_handle(
+                            &bpm_clone,
+                            loser_pid,
+                            AccessType::Unknown
+                        )
+                        .is_err(),
                         "Should not be able to read loser page when only 1 frame is pinned"
                     );
 
@@ -1554,8 +1804,9 @@ mod tests {
             let mut guard = lock.lock().unwrap();
             if i % 2 == 0 {
                 // Even iteration: read-latch winner page
-                let winner_read = BufferPoolManager::fetch_page_handle(&bpm, winner_pid)
-                    .expect("Failed to read-latch winner page");
+                let winner_read =
+                    BufferPoolManager::fetch_page_handle(&bpm, winner_pid, AccessType::Unknown)
+                        .expect("Failed to read-latch winner page");
 
                 // Wake up all readers
                 *guard = true;
@@ -1566,8 +1817,9 @@ mod tests {
                 drop(winner_read);
             } else {
                 // Odd iteration: write-latch winner page
-                let winner_write = BufferPoolManager::fetch_page_mut_handle(&bpm, winner_pid)
-                    .expect("Failed to write-latch winner page");
+                let winner_write =
+                    BufferPoolManager::fetch_page_mut_handle(&bpm, winner_pid, AccessType::Unknown)
+                        .expect("Failed to write-latch winner page");
 
                 *guard = true;
                 cv.notify_all();
@@ -1620,9 +1872,12 @@ mod tests {
                         page_ids.push(page_id);
 
                         // Now fetch the page for writing
-                        let mut page_handle =
-                            BufferPoolManager::fetch_page_mut_handle(&bpm_clone, page_id)
-                                .expect("Failed to fetch newly created page");
+                        let mut page_handle = BufferPoolManager::fetch_page_mut_handle(
+                            &bpm_clone,
+                            page_id,
+                            AccessType::Unknown,
+                        )
+                        .expect("Failed to fetch newly created page");
 
                         // Write the page_id as string
                         let data = page_id.to_string().into_bytes();
@@ -1656,8 +1911,12 @@ mod tests {
                     // c) Read them again to verify data
                     for &pid in &page_ids {
                         {
-                            let read_guard = BufferPoolManager::fetch_page_handle(&bpm_clone, pid)
-                                .expect("Failed to read-latch page");
+                            let read_guard = BufferPoolManager::fetch_page_handle(
+                                &bpm_clone,
+                                pid,
+                                AccessType::Unknown,
+                            )
+                            .expect("Failed to read-latch page");
                             let stored_data = read_guard.data();
                             let expected_str = pid.to_string();
                             assert_eq!(
@@ -1712,7 +1971,8 @@ mod tests {
         let bpm2 = Arc::clone(&bpm);
 
         let writer1 = thread::spawn(move || {
-            let mut page = BufferPoolManager::fetch_page_mut_handle(&bpm1, pid).unwrap();
+            let mut page =
+                BufferPoolManager::fetch_page_mut_handle(&bpm1, pid, AccessType::Unknown).unwrap();
             page.write(0, b"Writer1");
             thread::sleep(Duration::from_millis(50));
             assert!(std::str::from_utf8(page.data())
@@ -1722,7 +1982,8 @@ mod tests {
 
         let writer2 = thread::spawn(move || {
             thread::sleep(Duration::from_millis(10));
-            let mut page = BufferPoolManager::fetch_page_mut_handle(&bpm2, pid).unwrap();
+            let mut page =
+                BufferPoolManager::fetch_page_mut_handle(&bpm2, pid, AccessType::Unknown).unwrap();
             page.write(0, b"Writer2");
         });
 
@@ -1763,8 +2024,12 @@ mod tests {
                 threads.push(thread::spawn(move || {
                     for &pid in &page_ids_clone {
                         // Pin page in write mode
-                        let mut write_guard =
-                            BufferPoolManager::fetch_page_mut_handle(&bpm_clone, pid).unwrap();
+                        let mut write_guard = BufferPoolManager::fetch_page_mut_handle(
+                            &bpm_clone,
+                            pid,
+                            AccessType::Unknown,
+                        )
+                        .unwrap();
                         for i in 0..data_length {
                             write_guard.data_mut()[i] = tid;
                             thread::sleep(Duration::from_micros(1));
@@ -1780,8 +2045,12 @@ mod tests {
                 threads.push(thread::spawn(move || {
                     for &pid in &page_ids_clone {
                         // Pin page in read mode
-                        let read_guard = BufferPoolManager::fetch_page_handle(&bpm_clone, pid)
-                            .expect("Failed to fetch page for reading");
+                        let read_guard = BufferPoolManager::fetch_page_handle(
+                            &bpm_clone,
+                            pid,
+                            AccessType::Unknown,
+                        )
+                        .expect("Failed to fetch page for reading");
                         let page_current_tid = read_guard.data()[10];
                         for i in 0..data_length {
                             assert_eq!(read_guard.data()[i], page_current_tid);
@@ -1797,4 +2066,151 @@ mod tests {
         }
     }
 
+    #[test]
+    #[serial]
+    fn test_bpm_optimistic_read() {
+        let bpm = get_bpm_arc_with_pool_size(2);
+
+        let pid = {
+            let mut page = BufferPoolManager::create_page_handle(&bpm).unwrap();
+            page.write(0, b"optimistic");
+            page.page_id()
+        };
+
+        let data =
+            BufferPoolManager::with_page_optimistic(&bpm, pid, AccessType::Lookup, 0..10, |data| {
+                data.to_vec()
+            })
+            .expect("Failed to read page optimistically");
+        assert_eq!(data, b"optimistic");
+        let data =
+            BufferPoolManager::with_page_optimistic(&bpm, pid, AccessType::Lookup, 3..6, |data| {
+                data.to_vec()
+            })
+            .expect("Failed to read page optimistically");
+        assert_eq!(data, b"imi");
+
+        // Ranges past the end of the page are rejected.
+        let range = PAGE_SIZE - 1..PAGE_SIZE + 1;
+        assert!(
+            BufferPoolManager::with_page_optimistic(&bpm, pid, AccessType::Lookup, range, |_| ())
+                .is_err()
+        );
+
+        // The page is unpinned once the closure returns.
+        assert_eq!(bpm.read().unwrap().get_pin_count(pid), Some(0));
+
+        // Every write bumps the version twice, leaving it even once the handle is dropped.
+        let version = {
+            let frame_id = bpm.read().unwrap().page_table[&pid];
+            bpm.read().unwrap().frames[frame_id].version()
+        };
+        assert_eq!(version % 2, 0);
+        drop(BufferPoolManager::fetch_page_mut_handle(&bpm, pid, AccessType::Unknown).unwrap());
+        let frame_id = bpm.read().unwrap().page_table[&pid];
+        assert_eq!(bpm.read().unwrap().frames[frame_id].version(), version + 2);
+    }
+
+    #[test]
+    #[serial]
+    fn test_bpm_optimistic_read_with_concurrent_writers() {
+        let num_writers = 4;
+        let num_readers = 4;
+        let num_pages = 16;
+        let data_length = 100;
+
+        let bpm = get_bpm_arc_with_pool_size(num_pages);
+        let page_ids: Vec<PageId> = (0..num_pages)
+            .map(|_| {
+                BufferPoolManager::create_page_handle(&bpm)
+                    .expect("Failed to create page")
+                    .page_id()
+            })
+            .collect();
+
+        let mut threads = Vec::new();
+        for tid in 0..num_writers {
+            let bpm_clone = Arc::clone(&bpm);
+            let page_ids_clone = page_ids.clone();
+            threads.push(thread::spawn(move || {
+                for &pid in &page_ids_clone {
+                    let mut write_guard = BufferPoolManager::fetch_page_mut_handle(
+                        &bpm_clone,
+                        pid,
+                        AccessType::Unknown,
+                    )
+                    .unwrap();
+                    for i in 0..data_length {
+                        write_guard.data_mut()[i] = tid;
+                        thread::sleep(Duration::from_micros(1));
+                    }
+                }
+            }));
+        }
+
+        for _ in 0..num_readers {
+            let bpm_clone = Arc::clone(&bpm);
+            let page_ids_clone = page_ids.clone();
+            threads.push(thread::spawn(move || {
+                for &pid in &page_ids_clone {
+                    // Validation must never let a half-written page through.
+                    let data = BufferPoolManager::with_page_optimistic(
+                        &bpm_clone,
+                        pid,
+                        AccessType::Lookup,
+                        0..data_length,
+                        |data| data.to_vec(),
+                    )
+                    .expect("Failed to read page optimistically");
+                    assert!(data.iter().all(|&byte| byte == data[0]));
+                }
+            }));
+        }
+
+        for handle in threads {
+            handle.join().expect("Thread panicked");
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_bpm_access_trace() {
+        let bpm = get_bpm_arc_with_pool_size(2);
+        let trace_path = std::env::temp_dir().join("rustdb_test_bpm_access_trace.txt");
+        bpm.write().unwrap().start_trace(&trace_path).unwrap();
+
+        let pid = BufferPoolManager::create_page_handle(&bpm)
+            .unwrap()
+            .page_id();
+        drop(BufferPoolManager::fetch_page_handle(&bpm, pid, AccessType::Scan).unwrap());
+        BufferPoolManager::with_page_optimistic(&bpm, pid, AccessType::Lookup, 0..1, |_| ())
+            .unwrap();
+        bpm.write().unwrap().stop_trace().unwrap();
+
+        // Accesses after the trace is stopped aren't recorded.
+        drop(BufferPoolManager::fetch_page_handle(&bpm, pid, AccessType::Scan).unwrap());
+
+        let trace = read_trace(&trace_path).unwrap();
+        std::fs::remove_file(&trace_path).unwrap();
+        assert_eq!(
+            trace,
+            vec![
+                TraceEvent::Pin {
+                    page_id: pid,
+                    access_type: AccessType::Unknown
+                },
+                TraceEvent::Unpin { page_id: pid },
+                TraceEvent::Pin {
+                    page_id: pid,
+                    access_type: AccessType::Scan
+                },
+                TraceEvent::Unpin { page_id: pid },
+                TraceEvent::Pin {
+                    page_id: pid,
+                    access_type: AccessType::Lookup
+                },
+                TraceEvent::Unpin { page_id: pid },
+            ]
+        );
+    }
 }
@@ -0,0 +1,248 @@
+//! Checks every [`StorageApi`] implementation must pass, run against the in-memory storage and
+//! against [`StorageEngine`] with each table layout, so that code tested against one behaves the
+//! same on the others.
+//!
+//! Each check only relies on behavior the implementations share. E.g. the disk-based heaps don't
+//! say what reading a deleted tuple returns, so no check reads one.
+
+use std::collections::HashMap;
+
+use rustdb_catalog::catalog::{StorageApi, TableId};
+use rustdb_catalog::column::Column;
+use rustdb_catalog::field::Field;
+use rustdb_catalog::memory::MemoryStorage;
+use rustdb_catalog::schema::{RecordId, Schema};
+use rustdb_catalog::serde::Serde;
+use rustdb_catalog::tuple::Tuple;
+use rustdb_catalog::types::Type;
+use serial_test::serial;
+
+use crate::storage::{StorageEngine, TableLayout};
+use crate::test_utils::get_bpm_arc_with_pool_size;
+use crate::Result;
+
+/// Creates an empty table with the given id in `S`, with the [`schema`] the checks' tuples have.
+type CreateTable<S> = fn(&S, TableId) -> Result<()>;
+
+fn schema() -> Schema {
+    Schema::new(&[
+        Column::new("a".to_string(), Type::Integer),
+        Column::new("b".to_string(), Type::Varchar),
+    ])
+}
+
+fn tuple(a: i32, b: &str) -> Tuple {
+    let fields = [Field::Integer(a), Field::Varchar(b.to_string())];
+    Tuple::new(Serde::serialize(&fields).into())
+}
+
+/// Returns the tuples a scan of the table emits, by record id.
+fn scan<S: StorageApi>(storage: &S, table_id: TableId) -> Result<HashMap<RecordId, Vec<u8>>> {
+    let mut tuples = HashMap::new();
+    for item in storage.scan(table_id)? {
+        let (rid, tuple) = item?;
+        assert!(tuples.insert(rid, tuple.data().to_vec()).is_none());
+    }
+    let dyn_tuples = storage
+        .scan_dyn(table_id)?
+        .map(|item| item.map(|(rid, tuple)| (rid, tuple.data().to_vec())))
+        .collect::<Result<HashMap<_, _>>>()?;
+    assert_eq!(tuples, dyn_tuples);
+    Ok(tuples)
+}
+
+fn check_insert_get_scan<S: StorageApi>(storage: &S, create: CreateTable<S>) -> Result<()> {
+    create(storage, 0)?;
+    assert!(scan(storage, 0)?.is_empty());
+
+    let mut expected = HashMap::new();
+    for i in 0..500 {
+        let tuple = tuple(i, &"x".repeat(i as usize % 50));
+        let rid = storage.insert_tuple(0, &tuple)?;
+        assert!(expected.insert(rid, tuple.data().to_vec()).is_none());
+    }
+    let tuples: Vec<_> = (500..600).map(|i| tuple(i, "batch")).collect();
+    let rids = storage.insert_tuples(0, &tuples)?;
+    for (rid, tuple) in rids.into_iter().zip(&tuples) {
+        assert!(expected.insert(rid, tuple.data().to_vec()).is_none());
+    }
+
+    for (&rid, data) in &expected {
+        assert_eq!(*data, storage.get_tuple(0, rid)?.data().to_vec());
+    }
+    assert_eq!(expected, scan(storage, 0)?);
+    Ok(())
+}
+
+fn check_delete<S: StorageApi>(storage: &S, create: CreateTable<S>) -> Result<()> {
+    create(storage, 0)?;
+    let rids = (0..200)
+        .map(|i| storage.insert_tuple(0, &tuple(i, "x")))
+        .collect::<Result<Vec<_>>>()?;
+    for rid in rids.iter().step_by(2) {
+        storage.delete_tuple(0, *rid)?;
+    }
+    // Deleting a tuple again does nothing.
+    storage.delete_tuple(0, rids[0])?;
+
+    let tuples = scan(storage, 0)?;
+    assert_eq!(100, tuples.len());
+    for (i, rid) in rids.iter().enumerate() {
+        assert_eq!(i % 2 == 1, tuples.contains_key(rid));
+    }
+    assert!(storage.update_tuple(0, rids[0], &tuple(0, "y")).is_err());
+
+    // Later inserts don't disturb the tuples that are left.
+    let rid = storage.insert_tuple(0, &tuple(200, "new"))?;
+    assert_eq!(tuple(1, "x").data(), storage.get_tuple(0, rids[1])?.data());
+    assert_eq!(tuple(200, "new").data(), storage.get_tuple(0, rid)?.data());
+    assert_eq!(101, scan(storage, 0)?.len());
+    Ok(())
+}
+
+fn check_update<S: StorageApi>(storage: &S, create: CreateTable<S>) -> Result<()> {
+    create(storage, 0)?;
+    // Few enough tuples for their page to have room for them to grow, which PAX tables need.
+    let rids = (0..20)
+        .map(|i| storage.insert_tuple(0, &tuple(i, "x")))
+        .collect::<Result<Vec<_>>>()?;
+    // Tuples keep their record id, whether they grow or shrink.
+    for (i, &rid) in rids.iter().enumerate() {
+        let b = if i % 2 == 0 {
+            "y".repeat(100)
+        } else {
+            String::new()
+        };
+        storage.update_tuple(0, rid, &tuple(i as i32, &b))?;
+    }
+    let tuples = scan(storage, 0)?;
+    assert_eq!(20, tuples.len());
+    for (i, rid) in rids.iter().enumerate() {
+        let b = if i % 2 == 0 {
+            "y".repeat(100)
+        } else {
+            String::new()
+        };
+        assert_eq!(tuple(i as i32, &b).data().to_vec(), tuples[rid]);
+        assert_eq!(tuples[rid], storage.get_tuple(0, *rid)?.data().to_vec());
+    }
+    Ok(())
+}
+
+fn check_tables<S: StorageApi>(storage: &S, create: CreateTable<S>) -> Result<()> {
+    create(storage, 0)?;
+    create(storage, 1)?;
+    assert!(create(storage, 0).is_err());
+
+    // Tables don't see each other's tuples.
+    let rid = storage.insert_tuple(0, &tuple(0, "zero"))?;
+    storage.insert_tuple(1, &tuple(1, "one"))?;
+    assert_eq!(1, scan(storage, 0)?.len());
+    assert_eq!(tuple(0, "zero").data(), storage.get_tuple(0, rid)?.data());
+
+    storage.drop_table(0)?;
+    assert!(storage.drop_table(0).is_err());
+    assert!(storage.scan(0).is_err());
+    assert!(storage.scan_dyn(0).is_err());
+    assert!(storage.get_tuple(0, rid).is_err());
+    assert!(storage.insert_tuple(0, &tuple(0, "zero")).is_err());
+    assert!(storage.update_tuple(0, rid, &tuple(0, "zero")).is_err());
+    assert!(storage.delete_tuple(0, rid).is_err());
+    assert!(storage.truncate_table(0).is_err());
+    assert!(storage.table_stats(0).is_err());
+    assert_eq!(1, scan(storage, 1)?.len());
+
+    // A dropped table's id can be used again.
+    create(storage, 0)?;
+    assert!(scan(storage, 0)?.is_empty());
+    Ok(())
+}
+
+fn check_truncate<S: StorageApi>(storage: &S, create: CreateTable<S>) -> Result<()> {
+    create(storage, 0)?;
+    for i in 0..300 {
+        storage.insert_tuple(0, &tuple(i, "x"))?;
+    }
+    storage.truncate_table(0)?;
+    assert!(scan(storage, 0)?.is_empty());
+    assert_eq!(0, storage.table_stats(0)?.row_count);
+
+    let rid = storage.insert_tuple(0, &tuple(0, "again"))?;
+    assert_eq!(tuple(0, "again").data(), storage.get_tuple(0, rid)?.data());
+    assert_eq!(1, scan(storage, 0)?.len());
+    Ok(())
+}
+
+fn check_table_stats<S: StorageApi>(storage: &S, create: CreateTable<S>) -> Result<()> {
+    create(storage, 0)?;
+    assert_eq!(0, storage.table_stats(0)?.row_count);
+    let rids = (0..400)
+        .map(|i| storage.insert_tuple(0, &tuple(i, &"x".repeat(i as usize % 20))))
+        .collect::<Result<Vec<_>>>()?;
+    for &rid in &rids[..100] {
+        storage.delete_tuple(0, rid)?;
+    }
+    storage.update_tuple(0, rids[100], &tuple(0, &"y".repeat(200)))?;
+
+    let stats = storage.table_stats(0)?;
+    let tuples = scan(storage, 0)?;
+    assert_eq!(tuples.len() as u64, stats.row_count);
+    assert_eq!(
+        tuples.values().map(Vec::len).sum::<usize>() as u64,
+        stats.tuple_bytes
+    );
+    Ok(())
+}
+
+/// Runs every check against a fresh storage from `new`.
+fn check_all<S: StorageApi>(new: impl Fn() -> S, create: CreateTable<S>) -> Result<()> {
+    check_insert_get_scan(&new(), create)?;
+    check_delete(&new(), create)?;
+    check_update(&new(), create)?;
+    check_tables(&new(), create)?;
+    check_truncate(&new(), create)?;
+    check_table_stats(&new(), create)
+}
+
+fn new_engine() -> StorageEngine {
+    StorageEngine::new(get_bpm_arc_with_pool_size(10))
+}
+
+#[test]
+fn test_memory_storage_conformance() -> Result<()> {
+    check_all(MemoryStorage::new, |storage, table_id| {
+        storage.create_table(table_id, "table").map(|_| ())
+    })
+}
+
+#[test]
+#[serial]
+fn test_storage_engine_row_conformance() -> Result<()> {
+    check_all(new_engine, |engine, table_id| {
+        engine.create_table(table_id, "table").map(|_| ())
+    })
+}
+
+#[test]
+#[serial]
+fn test_storage_engine_pax_conformance() -> Result<()> {
+    check_all(new_engine, |engine, table_id| {
+        engine.create_table_with_layout(table_id, "table", TableLayout::Pax(schema()))
+    })
+}
+
+#[test]
+#[serial]
+fn test_storage_engine_lsm_conformance() -> Result<()> {
+    check_all(new_engine, |engine, table_id| {
+        engine.create_table_with_layout(table_id, "table", TableLayout::Lsm)
+    })
+}
+
+#[test]
+#[serial]
+fn test_storage_engine_columnar_conformance() -> Result<()> {
+    check_all(new_engine, |engine, table_id| {
+        engine.create_table_with_layout(table_id, "table", TableLayout::Columnar(schema()))
+    })
+}
@@ -0,0 +1,241 @@
+use std::sync::{Arc, RwLock};
+
+use bytes::{Buf, BufMut};
+use rustdb_catalog::catalog::TableId;
+use rustdb_catalog::column::Column;
+use rustdb_catalog::schema::Schema;
+use rustdb_catalog::tuple::Tuple;
+use rustdb_catalog::types::Type;
+use rustdb_error::errdata;
+
+use crate::buffer_pool::BufferPoolManager;
+use crate::heap::table_heap::TableHeap;
+use crate::heap::table_tuple_iterator::TableTupleIterator;
+use crate::record_id::RecordId;
+use crate::storage::TableLayout;
+use crate::typedef::PageId;
+use crate::Result;
+
+/// What the storage engine needs to know to reattach a table.
+#[derive(Debug, Clone)]
+pub(crate) struct TableEntry {
+    pub(crate) table_id: TableId,
+    pub(crate) name: String,
+    pub(crate) layout: TableLayout,
+    /// The header page of the table's heap.
+    pub(crate) header_page_id: PageId,
+}
+
+const ROW_LAYOUT: u8 = 0;
+const PAX_LAYOUT: u8 = 1;
+const LSM_LAYOUT: u8 = 2;
+const COLUMNAR_LAYOUT: u8 = 3;
+
+impl TableEntry {
+    fn to_tuple(&self) -> Tuple {
+        let mut data = Vec::new();
+        data.put_u32_le(self.table_id);
+        data.put_u32_le(self.header_page_id);
+        put_str(&mut data, &self.name);
+        match &self.layout {
+            TableLayout::Row => data.put_u8(ROW_LAYOUT),
+            TableLayout::Lsm => data.put_u8(LSM_LAYOUT),
+            TableLayout::Pax(schema) => {
+                data.put_u8(PAX_LAYOUT);
+                put_schema(&mut data, schema);
+            }
+            TableLayout::Columnar(schema) => {
+                data.put_u8(COLUMNAR_LAYOUT);
+                put_schema(&mut data, schema);
+            }
+        }
+        Tuple::new(data.into())
+    }
+
+    fn from_tuple(tuple: &Tuple) -> Result<Self> {
+        let mut data = tuple.data();
+        ensure_remaining(&data, 8)?;
+        let table_id = data.get_u32_le();
+        let header_page_id = data.get_u32_le();
+        let name = get_str(&mut data)?;
+        ensure_remaining(&data, 1)?;
+        let layout = match data.get_u8() {
+            ROW_LAYOUT => TableLayout::Row,
+            PAX_LAYOUT => TableLayout::Pax(get_schema(&mut data)?),
+            LSM_LAYOUT => TableLayout::Lsm,
+            COLUMNAR_LAYOUT => TableLayout::Columnar(get_schema(&mut data)?),
+            tag => return errdata!("Invalid table layout: {}", tag),
+        };
+        Ok(Self {
+            table_id,
+            name,
+            layout,
+            header_page_id,
+        })
+    }
+}
+
+fn ensure_remaining(data: &impl Buf, len: usize) -> Result<()> {
+    if data.remaining() < len {
+        return errdata!("Truncated table directory entry");
+    }
+    Ok(())
+}
+
+fn put_str(data: &mut Vec<u8>, s: &str) {
+    data.put_u16_le(s.len() as u16);
+    data.put_slice(s.as_bytes());
+}
+
+fn get_str(data: &mut impl Buf) -> Result<String> {
+    ensure_remaining(data, 2)?;
+    let len = data.get_u16_le() as usize;
+    ensure_remaining(data, len)?;
+    let bytes = data.copy_to_bytes(len);
+    String::from_utf8(bytes.to_vec()).or_else(|_| errdata!("Invalid table name"))
+}
+
+fn put_schema(data: &mut Vec<u8>, schema: &Schema) {
+    data.put_u16_le(schema.num_columns() as u16);
+    for column in schema.columns() {
+        data.put_u8(type_to_tag(column.field_type()));
+        put_str(data, column.name());
+    }
+}
+
+fn get_schema(data: &mut impl Buf) -> Result<Schema> {
+    ensure_remaining(data, 2)?;
+    let column_cnt = data.get_u16_le();
+    let columns = (0..column_cnt)
+        .map(|_| {
+            ensure_remaining(data, 1)?;
+            let field_type = tag_to_type(data.get_u8())?;
+            Ok(Column::new(get_str(data)?, field_type))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Schema::new(&columns))
+}
+
+fn type_to_tag(field_type: Type) -> u8 {
+    match field_type {
+        Type::Null => 0,
+        Type::Boolean => 1,
+        Type::Integer => 2,
+        Type::Float => 3,
+        Type::Varchar => 4,
+    }
+}
+
+fn tag_to_type(tag: u8) -> Result<Type> {
+    match tag {
+        0 => Ok(Type::Null),
+        1 => Ok(Type::Boolean),
+        2 => Ok(Type::Integer),
+        3 => Ok(Type::Float),
+        4 => Ok(Type::Varchar),
+        _ => errdata!("Invalid column type: {}", tag),
+    }
+}
+
+/// The list of tables a storage engine holds, kept in a table heap of its own so that it is
+/// persisted like any other table. Reopening the directory from its header page is all the
+/// engine needs to find its tables again.
+pub(crate) struct TableDirectory {
+    bpm: Arc<RwLock<BufferPoolManager>>,
+    heap: Arc<RwLock<TableHeap>>,
+}
+
+impl TableDirectory {
+    /// Creates an empty directory.
+    pub(crate) fn new(bpm: Arc<RwLock<BufferPoolManager>>) -> Self {
+        let heap = TableHeap::new("__directory", bpm.clone());
+        Self {
+            bpm,
+            heap: Arc::new(RwLock::new(heap)),
+        }
+    }
+
+    /// Opens the directory whose heap has header page `header_page_id`.
+    pub(crate) fn open(
+        header_page_id: PageId,
+        bpm: Arc<RwLock<BufferPoolManager>>,
+    ) -> Result<Self> {
+        let heap = TableHeap::open(header_page_id, bpm.clone())?;
+        Ok(Self {
+            bpm,
+            heap: Arc::new(RwLock::new(heap)),
+        })
+    }
+
+    pub(crate) fn header_page_id(&self) -> PageId {
+        self.heap.read().unwrap().header_page_id()
+    }
+
+    /// Records a new table, returning the record id of its entry.
+    pub(crate) fn insert(&self, entry: &TableEntry) -> Result<RecordId> {
+        self.heap.write().unwrap().insert_tuple(&entry.to_tuple())
+    }
+
+    /// Removes the entry of the table with id `table_id`, if there is one.
+    pub(crate) fn remove(&self, table_id: TableId) -> Result<()> {
+        let entry = self
+            .entries()?
+            .into_iter()
+            .find(|(_, entry)| entry.table_id == table_id);
+        if let Some((rid, _)) = entry {
+            self.heap.write().unwrap().delete_tuple(&rid)?;
+        }
+        Ok(())
+    }
+
+    /// Returns every table in the directory, along with the record id of its entry.
+    pub(crate) fn entries(&self) -> Result<Vec<(RecordId, TableEntry)>> {
+        TableTupleIterator::new(self.bpm.clone(), self.heap.clone())
+            .map(|item| {
+                let (rid, tuple) = item?;
+                Ok((RecordId::from(rid), TableEntry::from_tuple(&tuple)?))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_entry_round_trip() -> Result<()> {
+        let schema = Schema::new(&[
+            Column::new("id".to_string(), Type::Integer),
+            Column::new("name".to_string(), Type::Varchar),
+        ]);
+        let layouts = [
+            TableLayout::Row,
+            TableLayout::Pax(schema.clone()),
+            TableLayout::Lsm,
+            TableLayout::Columnar(schema.clone()),
+        ];
+        for layout in layouts {
+            let entry = TableEntry {
+                table_id: 3,
+                name: "users".to_string(),
+                layout,
+                header_page_id: 42,
+            };
+            let decoded = TableEntry::from_tuple(&entry.to_tuple())?;
+            assert_eq!(entry.table_id, decoded.table_id);
+            assert_eq!(entry.name, decoded.name);
+            assert_eq!(entry.header_page_id, decoded.header_page_id);
+            match (entry.layout, decoded.layout) {
+                (TableLayout::Row, TableLayout::Row) => {}
+                (TableLayout::Pax(a), TableLayout::Pax(b)) => assert_eq!(a, b),
+                (TableLayout::Lsm, TableLayout::Lsm) => {}
+                (TableLayout::Columnar(a), TableLayout::Columnar(b)) => assert_eq!(a, b),
+                _ => panic!("layout changed"),
+            }
+        }
+
+        assert!(TableEntry::from_tuple(&Tuple::new(vec![1, 2, 3].into())).is_err());
+        Ok(())
+    }
+}
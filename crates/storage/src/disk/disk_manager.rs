@@ -89,6 +89,11 @@ impl DiskManager {
         }
     }
 
+    /// Returns whether `page_id` is allocated, i.e. has an offset in the file.
+    pub(crate) fn contains_page(&self, page_id: PageId) -> bool {
+        self.pages.contains_key(&page_id)
+    }
+
     /// Read a page if it exists. If not found, returns None or an error.
     pub(crate) fn read(&mut self, page_id: PageId) -> Result<Option<Bytes>> {
         let offset = match self.pages.get(&page_id) {
@@ -1,6 +1,7 @@
 use core::fmt;
+use std::ptr;
 use std::sync::{
-    atomic::{AtomicU16, Ordering},
+    atomic::{fence, AtomicU16, AtomicU64, Ordering},
     RwLock,
 };
 
@@ -14,6 +15,7 @@ pub struct PageFrame {
     page_id: PageId,       // Unique identifier for the page
     is_dirty: bool,        // Tracks whether the page has been modified
     pin_cnt: AtomicU16,    // Pin count indicating active users (now atomic)
+    version: AtomicU64,    // Bumped by writers; odd while a writer holds the page
     lock: RwLock<()>,      // Read-Write lock for thread safety
     data: [u8; PAGE_SIZE], // Page data storage
 }
@@ -24,6 +26,7 @@ impl fmt::Debug for PageFrame {
             .field("page_id", &self.page_id)
             .field("is_dirty", &self.is_dirty)
             .field("pin_cnt", &self.pin_cnt.load(Ordering::SeqCst))
+            .field("version", &self.version.load(Ordering::SeqCst))
             .finish()
     }
 }
@@ -35,6 +38,7 @@ impl PageFrame {
             page_id: INVALID_PAGE_ID,
             is_dirty: false,
             pin_cnt: AtomicU16::new(0),
+            version: AtomicU64::new(0),
             lock: RwLock::new(()),
             data: [0; PAGE_SIZE],
         }
@@ -55,6 +59,12 @@ impl PageFrame {
         self.pin_cnt.load(Ordering::Acquire)
     }
 
+    /// Returns the current version of the page data. The version is odd while a writer holds the
+    /// page and is bumped again once the writer releases it.
+    pub(crate) fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
     /// Provides read-only access to page data.
     pub fn data(&self) -> &[u8] {
         &self.data
@@ -112,6 +122,44 @@ impl PageFrame {
         self.data[offset..offset + data.len()].copy_from_slice(data);
     }
 
+    /// Marks the start of a write, making the version odd until [`PageFrame::end_write`].
+    pub(crate) fn begin_write(&self) {
+        self.version.fetch_add(1, Ordering::SeqCst);
+        fence(Ordering::Release);
+    }
+
+    /// Marks the end of a write, making the version even again.
+    pub(crate) fn end_write(&self) {
+        self.version.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Copies the `buf.len()` bytes of page data at `offset` into `buf` without taking the read
+    /// lock.
+    ///
+    /// Returns `false` if a writer held the page when the copy started or the version changed
+    /// while it was being made, in which case `buf` may hold partially written bytes and must be
+    /// discarded. The bytes are copied with volatile reads, so the compiler can't assume they
+    /// don't change under it, and nothing but the copy is ever looked at unvalidated.
+    pub(crate) fn read_optimistic(&self, offset: usize, buf: &mut [u8]) -> bool {
+        if offset + buf.len() > PAGE_SIZE {
+            panic!("Read out of bounds");
+        }
+        let start = self.version();
+        if start % 2 == 1 {
+            return false;
+        }
+
+        let data = ptr::addr_of!(self.data).cast::<u8>();
+        for (i, byte) in buf.iter_mut().enumerate() {
+            // SAFETY: the byte is within `data`, which is always initialized, and the pointer is
+            // derived without creating a reference that a concurrent writer could invalidate.
+            *byte = unsafe { ptr::read_volatile(data.add(offset + i)) };
+        }
+
+        fence(Ordering::Acquire);
+        self.version.load(Ordering::Relaxed) == start
+    }
+
     /// Acquires a read lock on the page.
     pub(crate) fn read_lock(&self) -> std::sync::RwLockReadGuard<'_, ()> {
         self.lock.read().unwrap()
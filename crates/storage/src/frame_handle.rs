@@ -70,6 +70,8 @@ impl<'a> PageFrameMutHandle<'a> {
         // SAFETY:
         // Obtains a read lock on the `PageFrame` using an **unsafe** block.
         let lock_guard = unsafe { (*fp_ptr).write_lock() };
+        // Optimistic readers don't take the latch, so let them know a write is in progress.
+        page_frame.begin_write();
         PageFrameMutHandle {
             bpm,
             page_frame,
@@ -80,6 +82,7 @@ impl<'a> PageFrameMutHandle<'a> {
 
 impl<'a> Drop for PageFrameMutHandle<'a> {
     fn drop(&mut self) {
+        self.page_frame.end_write();
         self.bpm
             .write()
             .unwrap()
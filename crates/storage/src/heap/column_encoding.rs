@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+
+use rustdb_catalog::types::Type;
+use rustdb_error::errdata;
+
+use crate::Result;
+
+/// The bytes of a single column value, as [`rustdb_catalog::field::Field::to_bytes`] returns
+/// them.
+pub(crate) type Value = Vec<u8>;
+
+/// How the values of a column page are encoded. Each page uses whichever encoding stores its
+/// values in the fewest bytes.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    /// The values one after the other. Varchars are prefixed with their length.
+    Plain = 0,
+    /// Runs of equal values, each stored once after the length of the run.
+    RunLength = 1,
+    /// The distinct values stored once, followed by the bit-packed index of each value among
+    /// them.
+    Dictionary = 2,
+    /// Integers and booleans stored as their difference to the smallest of them, bit-packed.
+    BitPacked = 3,
+}
+
+impl TryFrom<u8> for Encoding {
+    type Error = rustdb_error::Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Encoding::Plain),
+            1 => Ok(Encoding::RunLength),
+            2 => Ok(Encoding::Dictionary),
+            3 => Ok(Encoding::BitPacked),
+            _ => errdata!("Invalid column encoding: {}", value),
+        }
+    }
+}
+
+/// Encodes values of type `ty` with every encoding that applies to them, and returns the smallest
+/// result.
+pub(crate) fn encode(ty: Type, values: &[Value]) -> (Encoding, Vec<u8>) {
+    let candidates = [
+        Some((Encoding::Plain, encode_plain(ty, values))),
+        Some((Encoding::RunLength, encode_run_length(ty, values))),
+        Some((Encoding::Dictionary, encode_dictionary(ty, values))),
+        encode_bit_packed(ty, values).map(|data| (Encoding::BitPacked, data)),
+    ];
+    candidates
+        .into_iter()
+        .flatten()
+        .min_by_key(|(_, data)| data.len())
+        .unwrap()
+}
+
+/// Decodes the `n` values of type `ty` that `data` holds in the given encoding.
+pub(crate) fn decode(ty: Type, encoding: Encoding, data: &[u8], n: usize) -> Result<Vec<Value>> {
+    let mut reader = Reader { data };
+    let values = match encoding {
+        Encoding::Plain => (0..n).map(|_| reader.value(ty)).collect::<Result<_>>()?,
+        Encoding::RunLength => {
+            let mut values = Vec::with_capacity(n);
+            while values.len() < n {
+                let len = reader.u32()? as usize;
+                let value = reader.value(ty)?;
+                if len > n - values.len() {
+                    return errdata!("Run of {} values is longer than its column page", len);
+                }
+                values.resize(values.len() + len, value);
+            }
+            values
+        }
+        Encoding::Dictionary => {
+            let dictionary = (0..reader.u32()?)
+                .map(|_| reader.value(ty))
+                .collect::<Result<Vec<_>>>()?;
+            let width = reader.u8()?;
+            unpack_bits(&mut reader, n, width)?
+                .into_iter()
+                .map(|i| match dictionary.get(i as usize) {
+                    Some(value) => Ok(value.clone()),
+                    None => errdata!("Dictionary index {} is out of bounds", i),
+                })
+                .collect::<Result<_>>()?
+        }
+        Encoding::BitPacked => {
+            let min = i64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+            let width = reader.u8()?;
+            unpack_bits(&mut reader, n, width)?
+                .into_iter()
+                .map(|delta| from_integer(ty, min.wrapping_add(delta as i64)))
+                .collect::<Result<_>>()?
+        }
+    };
+    Ok(values)
+}
+
+/// Number of bytes the plain encoding of `value` takes up.
+pub(crate) fn plain_size(ty: Type, value: &[u8]) -> usize {
+    match ty {
+        Type::Varchar => VARCHAR_LEN_SIZE + value.len(),
+        _ => value.len(),
+    }
+}
+
+/// Size of the length stored in front of a varchar.
+const VARCHAR_LEN_SIZE: usize = 2;
+
+fn put_value(data: &mut Vec<u8>, ty: Type, value: &[u8]) {
+    if ty == Type::Varchar {
+        data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    }
+    data.extend_from_slice(value);
+}
+
+fn encode_plain(ty: Type, values: &[Value]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for value in values {
+        put_value(&mut data, ty, value);
+    }
+    data
+}
+
+fn encode_run_length(ty: Type, values: &[Value]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for run in values.chunk_by(|a, b| a == b) {
+        data.extend_from_slice(&(run.len() as u32).to_le_bytes());
+        put_value(&mut data, ty, &run[0]);
+    }
+    data
+}
+
+fn encode_dictionary(ty: Type, values: &[Value]) -> Vec<u8> {
+    let mut dictionary: Vec<&[u8]> = Vec::new();
+    let mut indexes = HashMap::new();
+    let positions: Vec<u64> = values
+        .iter()
+        .map(|value| {
+            *indexes.entry(value.as_slice()).or_insert_with(|| {
+                dictionary.push(value);
+                dictionary.len() as u64 - 1
+            })
+        })
+        .collect();
+
+    let mut data = Vec::new();
+    data.extend_from_slice(&(dictionary.len() as u32).to_le_bytes());
+    for value in &dictionary {
+        put_value(&mut data, ty, value);
+    }
+    let width = bit_width(dictionary.len().saturating_sub(1) as u64);
+    data.push(width);
+    pack_bits(&mut data, positions.into_iter(), width);
+    data
+}
+
+/// Returns `None` for types that aren't integers.
+fn encode_bit_packed(ty: Type, values: &[Value]) -> Option<Vec<u8>> {
+    let integers = values
+        .iter()
+        .map(|value| to_integer(ty, value))
+        .collect::<Option<Vec<_>>>()?;
+    let min = integers.iter().copied().min().unwrap_or(0);
+    let max = integers.iter().copied().max().unwrap_or(0);
+    let width = bit_width(max.abs_diff(min));
+
+    let mut data = Vec::new();
+    data.extend_from_slice(&min.to_le_bytes());
+    data.push(width);
+    let deltas = integers.into_iter().map(|i| i.abs_diff(min));
+    pack_bits(&mut data, deltas, width);
+    Some(data)
+}
+
+/// Reads a value of a type bit-packing applies to as an integer.
+fn to_integer(ty: Type, value: &[u8]) -> Option<i64> {
+    match ty {
+        Type::Integer => Some(i32::from_le_bytes(value.try_into().ok()?).into()),
+        Type::Boolean => Some(value.first().copied()?.into()),
+        _ => None,
+    }
+}
+
+fn from_integer(ty: Type, i: i64) -> Result<Value> {
+    match ty {
+        Type::Integer => Ok((i as i32).to_le_bytes().to_vec()),
+        Type::Boolean => Ok(vec![i as u8]),
+        _ => errdata!("Column of type {} can't be bit-packed", ty),
+    }
+}
+
+/// Number of bits needed to store integers up to `max`.
+fn bit_width(max: u64) -> u8 {
+    (u64::BITS - max.leading_zeros()) as u8
+}
+
+/// Appends the low `width` bits of each integer to `data`, least significant bits first.
+fn pack_bits(data: &mut Vec<u8>, integers: impl Iterator<Item = u64>, width: u8) {
+    let (mut buffer, mut bits) = (0u128, 0);
+    for i in integers {
+        buffer |= u128::from(i) << bits;
+        bits += width;
+        while bits >= 8 {
+            data.push(buffer as u8);
+            buffer >>= 8;
+            bits -= 8;
+        }
+    }
+    if bits > 0 {
+        data.push(buffer as u8);
+    }
+}
+
+fn unpack_bits(reader: &mut Reader<'_>, n: usize, width: u8) -> Result<Vec<u64>> {
+    if width > 64 {
+        return errdata!("Invalid bit width: {}", width);
+    }
+    let mut bytes = reader.take((n * width as usize).div_ceil(8))?.iter();
+    let mask = match width {
+        0 => 0,
+        _ => u64::MAX >> (64 - width),
+    };
+    let (mut buffer, mut bits) = (0u128, 0);
+    let mut integers = Vec::with_capacity(n);
+    for _ in 0..n {
+        while bits < width {
+            buffer |= u128::from(*bytes.next().unwrap()) << bits;
+            bits += 8;
+        }
+        integers.push(buffer as u64 & mask);
+        buffer >>= width;
+        bits -= width;
+    }
+    Ok(integers)
+}
+
+/// Reads encoded data front to back, failing on data that ends too early.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let Some((taken, rest)) = self.data.split_at_checked(len) else {
+            return errdata!("Truncated column page data");
+        };
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn value(&mut self, ty: Type) -> Result<Value> {
+        let len = match ty {
+            Type::Varchar => {
+                u16::from_le_bytes(self.take(VARCHAR_LEN_SIZE)?.try_into().unwrap()) as usize
+            }
+            _ => ty.size(),
+        };
+        Ok(self.take(len)?.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustdb_catalog::field::Field;
+
+    use super::*;
+
+    fn values(fields: impl IntoIterator<Item = Field>) -> Vec<Value> {
+        fields.into_iter().map(|field| field.to_bytes()).collect()
+    }
+
+    #[test]
+    fn test_column_encodings() -> Result<()> {
+        let columns = [
+            (
+                Type::Integer,
+                values((0..1000).map(|i| Field::Integer(i * 7 % 1000 - 500))),
+            ),
+            (
+                Type::Integer,
+                values((0..1000).map(|i| Field::Integer([i32::MIN, 0, i32::MAX][i % 3]))),
+            ),
+            (
+                Type::Boolean,
+                values((0..999).map(|i| Field::Boolean(i % 3 == 0))),
+            ),
+            (
+                Type::Float,
+                values((0..300).map(|i| Field::Float(i as f64 / 3.0))),
+            ),
+            (
+                Type::Varchar,
+                values((0..500).map(|i| Field::Varchar("v".repeat(i % 13)))),
+            ),
+            (Type::Null, values((0..10).map(|_| Field::Null))),
+            (Type::Varchar, Vec::new()),
+        ];
+        // Every encoding that applies to a column gives its values back.
+        for (ty, values) in &columns {
+            let mut encodings = vec![Encoding::Plain, Encoding::RunLength, Encoding::Dictionary];
+            let mut encoded = vec![
+                encode_plain(*ty, values),
+                encode_run_length(*ty, values),
+                encode_dictionary(*ty, values),
+            ];
+            if let Some(data) = encode_bit_packed(*ty, values) {
+                encodings.push(Encoding::BitPacked);
+                encoded.push(data);
+            }
+            for (encoding, data) in encodings.into_iter().zip(encoded) {
+                assert_eq!(*values, decode(*ty, encoding, &data, values.len())?);
+                // Null values take up no space in the plain encoding, so there's nothing to cut.
+                if !data.is_empty() {
+                    assert!(decode(*ty, encoding, &data[..data.len() - 1], values.len()).is_err());
+                }
+            }
+        }
+        assert!(encode_bit_packed(Type::Varchar, &columns[4].1).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_column_encoding_choice() -> Result<()> {
+        let runs = values((0..1000).map(|i| Field::Integer(i / 100 * 1_000_000)));
+        let categories = values((0..1000).map(|i| Field::Varchar(format!("category {}", i % 5))));
+        let small_integers = values((0..1000).map(|i| Field::Integer(1000 + i * 37 % 50)));
+        let floats = values((0..100).map(|i| Field::Float((i as f64).sqrt())));
+        for (ty, values, expected) in [
+            (Type::Integer, runs, Encoding::RunLength),
+            (Type::Varchar, categories, Encoding::Dictionary),
+            (Type::Integer, small_integers, Encoding::BitPacked),
+            (Type::Float, floats, Encoding::Plain),
+        ] {
+            let (encoding, data) = encode(ty, &values);
+            assert_eq!(expected, encoding);
+            assert!(data.len() <= encode_plain(ty, &values).len());
+            assert_eq!(values, decode(ty, encoding, &data, values.len())?);
+        }
+        assert!(Encoding::try_from(4).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use rustdb_catalog::field::Field;
+use rustdb_catalog::predicate::Predicate;
+use rustdb_catalog::schema::{self, Schema};
+use rustdb_catalog::serde::Serde;
+use rustdb_catalog::tuple::Tuple;
+
+use crate::heap::column_encoding::Value;
+use crate::heap::column_store::{self, is_deleted, Chunk, ColumnStore};
+use crate::heap::sampler::Sampler;
+use crate::heap::scan_lock::{ScanGuard, ScanLocked};
+use crate::record_id::{RecordId, INVALID_RECORD_ID};
+use crate::Result;
+
+/// Most rows a [`ColumnBatch`] covers, deleted ones included.
+const BATCH_SIZE: u64 = 1024;
+
+/// The values of some columns of a column store for a range of consecutive rows, column by
+/// column. Deleted rows, and rows a filter or sample left out, aren't part of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnBatch {
+    record_ids: Vec<schema::RecordId>,
+    /// The values of each column the batch was read for, in the order they were requested.
+    columns: Vec<Vec<Field>>,
+}
+
+impl ColumnBatch {
+    /// Number of rows in the batch.
+    pub fn len(&self) -> usize {
+        self.record_ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.record_ids.is_empty()
+    }
+
+    /// The record ids of the batch's rows, in increasing order.
+    pub fn record_ids(&self) -> &[schema::RecordId] {
+        &self.record_ids
+    }
+
+    /// The values of the `i`-th column the batch was read for, one per row.
+    pub fn column(&self, i: usize) -> &[Field] {
+        &self.columns[i]
+    }
+
+    /// Serializes the batch's `i`-th row, as if its schema only had the batch's columns.
+    fn row(&self, i: usize) -> Tuple {
+        let fields: Vec<Field> = self
+            .columns
+            .iter()
+            .map(|column| column[i].clone())
+            .collect();
+        Tuple::new(Serde::serialize(&fields).into())
+    }
+}
+
+/// An iterator over the rows of a column store in batches of consecutive rows, reading only the
+/// requested columns' pages, along with those of the columns its predicate refers to.
+///
+/// Each page read stays cached in the iterator until the scan moves past its rows, so a page is
+/// read once per scan however many batches it spans. As with the PAX heap, writers aren't blocked
+/// unless the scan is stable; a concurrent scan sees writes to rows it hasn't reached yet, unless
+/// it already read the page they were written to.
+pub struct ColumnBatchIterator {
+    store: Arc<RwLock<ColumnStore>>,
+    schema: Schema,
+    columns: Vec<usize>,
+    /// The last page read of each chain, by chain.
+    chunks: HashMap<usize, Chunk>,
+    next_row: u64,
+    /// The row the scan stops before, if it doesn't go to the end of the store.
+    stop_row: Option<u64>,
+    done: bool,
+    predicate: Option<Predicate>,
+    /// Picks which batches are read and which rows are emitted, for a sampling scan.
+    sampler: Option<Sampler>,
+    /// Keeps writers out of the store while a stable scan runs.
+    guard: Option<ScanGuard>,
+}
+
+impl ColumnBatchIterator {
+    /// Creates an iterator over the given columns of every row. Fails if any of them isn't a
+    /// column of the store.
+    pub fn new(store: Arc<RwLock<ColumnStore>>, columns: Vec<usize>) -> Result<Self> {
+        Self::with_range(store, columns, 0, None)
+    }
+
+    /// Like [`ColumnBatchIterator::new`], but only scans the rows from `start_row` up to, but
+    /// excluding, `stop_row`.
+    pub(crate) fn with_range(
+        store: Arc<RwLock<ColumnStore>>,
+        columns: Vec<usize>,
+        start_row: u64,
+        stop_row: Option<u64>,
+    ) -> Result<Self> {
+        let schema = store.read().unwrap().schema().clone();
+        for &column in &columns {
+            schema.column_at(column)?;
+        }
+        Ok(Self {
+            store,
+            schema,
+            columns,
+            chunks: HashMap::new(),
+            next_row: start_row,
+            stop_row,
+            done: false,
+            predicate: None,
+            sampler: None,
+            guard: None,
+        })
+    }
+
+    /// Creates an iterator over every column of every row, which blocks the storage engine's
+    /// writes to the store until it is exhausted or dropped.
+    pub(crate) fn stable(store: Arc<RwLock<ColumnStore>>) -> Self {
+        let guard = {
+            let store = store.read().unwrap();
+            store.scan_lock().acquire()
+        };
+        let columns = (0..store.read().unwrap().schema().num_columns()).collect();
+        let mut iter = Self::new(store, columns).unwrap();
+        iter.guard = Some(guard);
+        iter
+    }
+
+    /// Makes the iterator only emit rows satisfying `predicate`. Fails if it doesn't fit the
+    /// store's schema.
+    pub(crate) fn with_predicate(mut self, predicate: Predicate) -> Result<Self> {
+        predicate.validate(&self.schema)?;
+        self.predicate = Some(predicate);
+        Ok(self)
+    }
+
+    /// Makes the iterator only emit a random sample of its rows. Page-level sampling picks whole
+    /// batches, as the pages of different columns don't hold the same rows.
+    pub(crate) fn with_sampler(mut self, sampler: Sampler) -> Self {
+        self.sampler = Some(sampler);
+        self
+    }
+
+    /// Returns the record id of the next row the iterator looks at, or [`INVALID_RECORD_ID`] once
+    /// it is exhausted.
+    pub(crate) fn position(&self) -> RecordId {
+        match self.done {
+            true => INVALID_RECORD_ID,
+            false => RecordId::from(column_store::record_id(self.next_row)),
+        }
+    }
+
+    fn next_batch(&mut self) -> Result<Option<ColumnBatch>> {
+        let store = self.store.read().unwrap();
+        let deleted_chain = self.schema.num_columns();
+        loop {
+            let end = store.row_count().min(self.stop_row.unwrap_or(u64::MAX));
+            if self.next_row >= end {
+                return Ok(None);
+            }
+            let rows = self.next_row..end.min(self.next_row + BATCH_SIZE);
+            self.next_row = rows.end;
+            if !self.sampler.as_mut().is_none_or(Sampler::sample_page) {
+                continue;
+            }
+
+            let mut batch = ColumnBatch {
+                record_ids: Vec::new(),
+                columns: vec![Vec::new(); self.columns.len()],
+            };
+            for row in rows {
+                if is_deleted(value(&mut self.chunks, &store, deleted_chain, row)?) {
+                    continue;
+                }
+                if !self.sampler.as_mut().is_none_or(Sampler::sample_tuple) {
+                    continue;
+                }
+                if let Some(predicate) = &self.predicate {
+                    let mut column = |i: usize| {
+                        let field_type = self.schema.column_at(i)?.field_type();
+                        let value = value(&mut self.chunks, &store, i, row)?;
+                        Ok(Field::from_bytes(value, field_type))
+                    };
+                    if !predicate.evaluate(&mut column)? {
+                        continue;
+                    }
+                }
+                batch.record_ids.push(column_store::record_id(row));
+                for (values, &column) in batch.columns.iter_mut().zip(&self.columns) {
+                    let field_type = self.schema.column_at(column)?.field_type();
+                    let value = value(&mut self.chunks, &store, column, row)?;
+                    values.push(Field::from_bytes(value, field_type));
+                }
+            }
+            // Pages the scan has moved past won't be read from again.
+            self.chunks.retain(|_, chunk| chunk.contains(self.next_row));
+            if !batch.is_empty() {
+                return Ok(Some(batch));
+            }
+        }
+    }
+}
+
+/// Returns the value of chain `chain` for `row`, reading the page holding it unless it's the page
+/// of that chain read last.
+fn value<'a>(
+    chunks: &'a mut HashMap<usize, Chunk>,
+    store: &ColumnStore,
+    chain: usize,
+    row: u64,
+) -> Result<&'a Value> {
+    if !chunks.get(&chain).is_some_and(|chunk| chunk.contains(row)) {
+        chunks.insert(chain, store.read_chunk(chain, row)?);
+    }
+    Ok(chunks[&chain].get(row))
+}
+
+impl Iterator for ColumnBatchIterator {
+    type Item = Result<ColumnBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let item = self.next_batch().transpose();
+        if !matches!(item, Some(Ok(_))) {
+            self.done = true;
+            self.chunks.clear();
+            self.guard.take();
+        }
+        item
+    }
+}
+
+/// An iterator over the rows of a column store, rebuilt from the batches of a
+/// [`ColumnBatchIterator`] and serialized with the schema of the columns it reads.
+pub struct ColumnTupleIterator {
+    batches: ColumnBatchIterator,
+    batch: Option<ColumnBatch>,
+    /// The row of `batch` emitted next.
+    index: usize,
+}
+
+impl ColumnTupleIterator {
+    pub(crate) fn new(batches: ColumnBatchIterator) -> Self {
+        Self {
+            batches,
+            batch: None,
+            index: 0,
+        }
+    }
+
+    /// Returns the record id of the next row the iterator looks at, or [`INVALID_RECORD_ID`] once
+    /// it is exhausted.
+    pub(crate) fn position(&self) -> RecordId {
+        match &self.batch {
+            Some(batch) if self.index < batch.len() => RecordId::from(batch.record_ids[self.index]),
+            _ => self.batches.position(),
+        }
+    }
+}
+
+impl Iterator for ColumnTupleIterator {
+    type Item = Result<(schema::RecordId, Tuple)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(batch) = &self.batch {
+                if self.index < batch.len() {
+                    let item = (batch.record_ids[self.index], batch.row(self.index));
+                    self.index += 1;
+                    return Some(Ok(item));
+                }
+            }
+            self.batch = match self.batches.next()? {
+                Ok(batch) => Some(batch),
+                Err(e) => return Some(Err(e)),
+            };
+            self.index = 0;
+        }
+    }
+}
@@ -0,0 +1,672 @@
+use std::sync::{Arc, RwLock};
+
+use rustdb_catalog::catalog::TableStats;
+use rustdb_catalog::field::Field;
+use rustdb_catalog::schema::Schema;
+use rustdb_catalog::serde::Serde;
+use rustdb_catalog::tuple::Tuple;
+use rustdb_catalog::types::Type;
+use rustdb_error::{errdata, errinput};
+
+use crate::buffer_pool::BufferPoolManager;
+use crate::heap::column_encoding::{self, plain_size, Encoding, Value};
+use crate::heap::heap_stats::HeapStats;
+use crate::heap::scan_lock::{ScanLock, ScanLocked};
+use crate::page::column_header_page::{ColumnHeaderPageMut, ColumnHeaderPageRef};
+use crate::page::column_page::{ColumnPageMut, ColumnPageRef, COLUMN_PAGE_CAPACITY};
+use crate::page::INVALID_PAGE_ID;
+use crate::replacer::replacer::AccessType;
+use crate::typedef::PageId;
+use crate::Result;
+
+/// The record id of a column store's first row. Rows are numbered from 0 in insertion order, and
+/// their record ids count up from here, so that their page id part is never `INVALID_PAGE_ID`,
+/// which marks the end of a scan in a [`crate::storage::ScanCursor`].
+const FIRST_RECORD_ID: u64 = 1 << 32;
+
+/// Returns the record id of the row with ordinal `row`.
+pub(crate) fn record_id(row: u64) -> u64 {
+    FIRST_RECORD_ID + row
+}
+
+/// Returns the ordinal of the row with record id `rid`, or 0 for record ids below the first one.
+pub(crate) fn row_of(rid: u64) -> u64 {
+    rid.saturating_sub(FIRST_RECORD_ID)
+}
+
+/// The values of a page chain for a range of consecutive rows, as read from one of its pages.
+pub(crate) struct Chunk {
+    first_row: u64,
+    values: Vec<Value>,
+}
+
+impl Chunk {
+    pub(crate) fn contains(&self, row: u64) -> bool {
+        (self.first_row..self.first_row + self.values.len() as u64).contains(&row)
+    }
+
+    /// Returns the value of `row`, which the chunk must contain.
+    pub(crate) fn get(&self, row: u64) -> &Value {
+        &self.values[(row - self.first_row) as usize]
+    }
+}
+
+/// The pages holding one column's values, in row order.
+struct ColumnChain {
+    ty: Type,
+    page_ids: Vec<PageId>,
+    /// The number of rows of each page.
+    row_cnts: Vec<u32>,
+    /// The first row of each page, followed by the number of rows of the chain.
+    bounds: Vec<u64>,
+}
+
+impl ColumnChain {
+    fn new(ty: Type) -> Self {
+        Self {
+            ty,
+            page_ids: Vec::new(),
+            row_cnts: Vec::new(),
+            bounds: vec![0],
+        }
+    }
+
+    /// Reads the page ids and row counts of the chain starting at `first_page_id`.
+    fn open(bpm: &Arc<RwLock<BufferPoolManager>>, ty: Type, first_page_id: PageId) -> Result<Self> {
+        let mut chain = Self::new(ty);
+        let mut page_id = first_page_id;
+        while page_id != INVALID_PAGE_ID {
+            let handle = BufferPoolManager::fetch_page_handle(bpm, page_id, AccessType::Unknown)?;
+            let page = ColumnPageRef::try_from(handle)?;
+            chain.page_ids.push(page_id);
+            chain.row_cnts.push(page.row_count());
+            page_id = page.next_page_id();
+        }
+        chain.update_bounds();
+        Ok(chain)
+    }
+
+    fn row_count(&self) -> u64 {
+        *self.bounds.last().unwrap()
+    }
+
+    fn first_page_id(&self) -> PageId {
+        self.page_ids.first().copied().unwrap_or(INVALID_PAGE_ID)
+    }
+
+    fn update_bounds(&mut self) {
+        self.bounds.truncate(1);
+        let mut row = 0;
+        for &row_cnt in &self.row_cnts {
+            row += u64::from(row_cnt);
+            self.bounds.push(row);
+        }
+    }
+
+    /// Returns the index of the page holding `row`, which must be one of the chain's rows.
+    fn page_for(&self, row: u64) -> usize {
+        self.bounds.partition_point(|&first_row| first_row <= row) - 1
+    }
+
+    fn read(
+        &self,
+        bpm: &Arc<RwLock<BufferPoolManager>>,
+        i: usize,
+        access_type: AccessType,
+    ) -> Result<Chunk> {
+        let handle = BufferPoolManager::fetch_page_handle(bpm, self.page_ids[i], access_type)?;
+        let page = ColumnPageRef::try_from(handle)?;
+        if page.row_count() != self.row_cnts[i] {
+            return errdata!(
+                "Column page {} holds {} rows instead of {}",
+                page.page_id(),
+                page.row_count(),
+                self.row_cnts[i]
+            );
+        }
+        Ok(Chunk {
+            first_row: self.bounds[i],
+            values: page.values(self.ty)?,
+        })
+    }
+
+    /// Appends values to the chain, after those of its last page.
+    fn append(&mut self, bpm: &Arc<RwLock<BufferPoolManager>>, values: Vec<Value>) -> Result<()> {
+        let Some(last) = self.page_ids.len().checked_sub(1) else {
+            return self.store(bpm, 0, &values);
+        };
+        let mut chunk = self.read(bpm, last, AccessType::Unknown)?;
+        chunk.values.extend(values);
+        self.store(bpm, last, &chunk.values)
+    }
+
+    /// Sets the value of `row`, which must be one of the chain's rows.
+    fn set(&mut self, bpm: &Arc<RwLock<BufferPoolManager>>, row: u64, value: Value) -> Result<()> {
+        let i = self.page_for(row);
+        let mut chunk = self.read(bpm, i, AccessType::Unknown)?;
+        let index = (row - chunk.first_row) as usize;
+        if chunk.values[index] == value {
+            return Ok(());
+        }
+        chunk.values[index] = value;
+        self.store(bpm, i, &chunk.values)
+    }
+
+    /// Stores `values`, of which there must be at least one, in place of the values of page `i`,
+    /// or after the last page if `i` is the number of pages. They are re-encoded, and spread over
+    /// new pages following page `i` if they don't fit in a single page anymore.
+    fn store(
+        &mut self,
+        bpm: &Arc<RwLock<BufferPoolManager>>,
+        i: usize,
+        values: &[Value],
+    ) -> Result<()> {
+        let mut pieces = Vec::new();
+        let mut rest = values;
+        while !rest.is_empty() {
+            let (row_cnt, encoding, data) = encode_prefix(self.ty, rest);
+            rest = &rest[row_cnt..];
+            pieces.push((row_cnt as u32, encoding, data));
+        }
+        let replaced = usize::from(i < self.page_ids.len());
+
+        // Write the new pages back to front, so that each can point to the next one.
+        let mut next_page_id = self.page_ids.get(i + 1).copied().unwrap_or(INVALID_PAGE_ID);
+        let mut new_page_ids = Vec::new();
+        for (row_cnt, encoding, data) in pieces[replaced..].iter().rev() {
+            let mut page = ColumnPageMut::from(BufferPoolManager::create_page_handle(bpm)?);
+            page.init(next_page_id, *row_cnt, *encoding, data);
+            next_page_id = page.page_id();
+            new_page_ids.push(next_page_id);
+        }
+        new_page_ids.reverse();
+        if replaced == 1 {
+            let (row_cnt, encoding, data) = &pieces[0];
+            let handle = BufferPoolManager::fetch_page_mut_handle(
+                bpm,
+                self.page_ids[i],
+                AccessType::Unknown,
+            )?;
+            ColumnPageMut::from(handle).init(next_page_id, *row_cnt, *encoding, data);
+        } else if let Some(&last_page_id) = self.page_ids.last() {
+            let handle =
+                BufferPoolManager::fetch_page_mut_handle(bpm, last_page_id, AccessType::Unknown)?;
+            ColumnPageMut::from(handle).set_next_page_id(next_page_id);
+        }
+
+        let page_ids: Vec<_> = self.page_ids[i..i + replaced]
+            .iter()
+            .copied()
+            .chain(new_page_ids)
+            .collect();
+        self.page_ids.splice(i..i + replaced, page_ids);
+        let row_cnts = pieces.iter().map(|(row_cnt, ..)| *row_cnt);
+        self.row_cnts.splice(i..i + replaced, row_cnts);
+        self.update_bounds();
+        Ok(())
+    }
+}
+
+/// Encodes as many of `values` as fit in a column page, and at least one. Returns how many it
+/// encoded along with their encoding.
+fn encode_prefix(ty: Type, values: &[Value]) -> (usize, Encoding, Vec<u8>) {
+    let (encoding, data) = column_encoding::encode(ty, values);
+    if data.len() <= COLUMN_PAGE_CAPACITY {
+        return (values.len(), encoding, data);
+    }
+    // Adding values never makes their encoding smaller, so the values that fit are a prefix.
+    let (mut fit, mut too_many) = (1, values.len());
+    while too_many - fit > 1 {
+        let mid = (fit + too_many) / 2;
+        if column_encoding::encode(ty, &values[..mid]).1.len() <= COLUMN_PAGE_CAPACITY {
+            fit = mid;
+        } else {
+            too_many = mid;
+        }
+    }
+    let (encoding, data) = column_encoding::encode(ty, &values[..fit]);
+    (fit, encoding, data)
+}
+
+fn deleted_flag(deleted: bool) -> Value {
+    Field::Boolean(deleted).to_bytes()
+}
+
+/// Whether a value of a column store's deletion flags chain marks its row as deleted.
+pub(crate) fn is_deleted(flag: &[u8]) -> bool {
+    flag == deleted_flag(true).as_slice()
+}
+
+/// A table stored column by column, for analytic tables that are mostly appended to and read a
+/// few columns at a time.
+///
+/// Each column has a chain of pages of its own, holding its values in row order, and each page
+/// encodes its values in whichever of the encodings of [`Encoding`] is the most compact for
+/// them. A last chain holds a flag per row telling whether it was deleted, which run-length
+/// encoding makes next to free. Rows are never moved or reclaimed, so a row's ordinal in
+/// insertion order gives its record id (see [`record_id`]).
+///
+/// Tuples go in and come out in the row-major [`Serde`] format, so the store needs the table's
+/// schema, like a PAX heap. Appending a row re-encodes the last page of each chain, and updating
+/// one re-encodes the pages holding it, splitting them when their values don't fit anymore.
+pub struct ColumnStore {
+    bpm: Arc<RwLock<BufferPoolManager>>,
+    schema: Schema,
+    header_page_id: PageId,
+    /// A chain per column of the schema, in order, followed by the chain of deletion flags.
+    chains: Vec<ColumnChain>,
+    stats: HeapStats,
+    scan_lock: Arc<ScanLock>,
+}
+
+impl ColumnStore {
+    /// Creates an empty store for tuples with the given schema, allocating its header page from
+    /// the buffer pool. Chains get their first page once the first row is inserted.
+    pub fn new(schema: Schema, bpm: Arc<RwLock<BufferPoolManager>>) -> Self {
+        let page_handle =
+            BufferPoolManager::create_page_handle(&bpm).expect("Failed to create page handle");
+        let mut header_page = ColumnHeaderPageMut::from(page_handle);
+        header_page
+            .init(&vec![INVALID_PAGE_ID; schema.num_columns() + 1])
+            .expect("Failed to initialize column header page");
+        let header_page_id = header_page.page_id();
+        drop(header_page);
+
+        Self {
+            chains: chain_types(&schema).map(ColumnChain::new).collect(),
+            bpm,
+            schema,
+            header_page_id,
+            stats: HeapStats::default(),
+            scan_lock: Arc::default(),
+        }
+    }
+
+    /// Reopens the store whose header page is `header_page_id`, which must have been created with
+    /// `schema`. Its statistics are counted again from its pages.
+    pub fn open(
+        header_page_id: PageId,
+        schema: Schema,
+        bpm: Arc<RwLock<BufferPoolManager>>,
+    ) -> Result<Self> {
+        let first_page_ids = {
+            let handle =
+                BufferPoolManager::fetch_page_handle(&bpm, header_page_id, AccessType::Unknown)?;
+            ColumnHeaderPageRef::try_from(handle)?.first_page_ids()?
+        };
+        if first_page_ids.len() != schema.num_columns() + 1 {
+            return errdata!(
+                "Column store {} has {} chains, but its schema has {} columns",
+                header_page_id,
+                first_page_ids.len(),
+                schema.num_columns()
+            );
+        }
+        let chains = chain_types(&schema)
+            .zip(first_page_ids)
+            .map(|(ty, first_page_id)| ColumnChain::open(&bpm, ty, first_page_id))
+            .collect::<Result<Vec<_>>>()?;
+        let row_cnt = chains[0].row_count();
+        if chains.iter().any(|chain| chain.row_count() != row_cnt) {
+            return errdata!(
+                "Columns of column store {} differ in length",
+                header_page_id
+            );
+        }
+
+        let mut store = Self {
+            bpm,
+            schema,
+            header_page_id,
+            chains,
+            stats: HeapStats::default(),
+            scan_lock: Arc::default(),
+        };
+        store.stats = store.count_stats()?;
+        Ok(store)
+    }
+
+    /// Counts the store's rows and deleted rows, and the size of its tuples: the size of each
+    /// column's type (a varchar's being that of its offset), plus the lengths of the varchars.
+    fn count_stats(&self) -> Result<HeapStats> {
+        let mut deleted = Vec::with_capacity(self.row_count() as usize);
+        let flags = self.chains.last().unwrap();
+        for i in 0..flags.page_ids.len() {
+            let chunk = flags.read(&self.bpm, i, AccessType::Unknown)?;
+            deleted.extend(chunk.values.iter().map(|flag| is_deleted(flag)));
+        }
+        let deleted_cnt = deleted.iter().filter(|&&deleted| deleted).count() as u64;
+        let tuple_cnt = deleted.len() as u64 - deleted_cnt;
+
+        let columns = &self.chains[..self.schema.num_columns()];
+        let fixed_size: usize = columns.iter().map(|chain| chain.ty.size()).sum();
+        let mut tuple_bytes = tuple_cnt * fixed_size as u64;
+        for chain in columns {
+            if chain.ty != Type::Varchar {
+                continue;
+            }
+            for i in 0..chain.page_ids.len() {
+                let chunk = chain.read(&self.bpm, i, AccessType::Unknown)?;
+                let rows = chunk.first_row as usize..;
+                for (value, &deleted) in chunk.values.iter().zip(&deleted[rows]) {
+                    if !deleted {
+                        tuple_bytes += value.len() as u64;
+                    }
+                }
+            }
+        }
+        Ok(HeapStats::new(tuple_cnt, tuple_bytes, deleted_cnt))
+    }
+
+    /// Returns the store's statistics. Its pages are those of all of its chains, and deleted rows
+    /// keep counting as they are never reclaimed.
+    pub(crate) fn stats(&self) -> TableStats {
+        let page_cnt: usize = self.chains.iter().map(|chain| chain.page_ids.len()).sum();
+        self.stats.to_table_stats(page_cnt as u32)
+    }
+
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    pub fn header_page_id(&self) -> PageId {
+        self.header_page_id
+    }
+
+    /// Number of rows ever inserted, deleted ones included.
+    pub(crate) fn row_count(&self) -> u64 {
+        self.chains[0].row_count()
+    }
+
+    /// Reads the page of chain `chain` holding `row`, which must be one of the store's rows.
+    /// Chain `schema.num_columns()` is the chain of deletion flags (see [`is_deleted`]).
+    pub(crate) fn read_chunk(&self, chain: usize, row: u64) -> Result<Chunk> {
+        let chain = &self.chains[chain];
+        chain.read(&self.bpm, chain.page_for(row), AccessType::Scan)
+    }
+
+    fn value(&self, chain: usize, row: u64) -> Result<Value> {
+        let chain = &self.chains[chain];
+        let chunk = chain.read(&self.bpm, chain.page_for(row), AccessType::Lookup)?;
+        Ok(chunk.get(row).clone())
+    }
+
+    /// Returns the row with record id `rid`, or `None` if it was deleted. Fails if there is no
+    /// such row.
+    fn row(&self, rid: u64) -> Result<Option<u64>> {
+        let row = row_of(rid);
+        if rid < FIRST_RECORD_ID || row >= self.row_count() {
+            return errinput!("Tuple {} does not exist", rid);
+        }
+        let deleted = is_deleted(&self.value(self.schema.num_columns(), row)?);
+        Ok((!deleted).then_some(row))
+    }
+
+    pub fn get_tuple(&self, rid: u64) -> Result<Tuple> {
+        let Some(row) = self.row(rid)? else {
+            return errinput!("Tuple {} was deleted", rid);
+        };
+        self.read_tuple(row)
+    }
+
+    fn read_tuple(&self, row: u64) -> Result<Tuple> {
+        let fields = (0..self.schema.num_columns())
+            .map(|column| {
+                let value = self.value(column, row)?;
+                Ok(Field::from_bytes(&value, self.chains[column].ty))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Tuple::new(Serde::serialize(&fields).into()))
+    }
+
+    /// Inserts a tuple, returning its record id.
+    pub fn insert_tuple(&mut self, tuple: &Tuple) -> Result<u64> {
+        Ok(self.insert_tuples(std::slice::from_ref(tuple))?[0])
+    }
+
+    /// Inserts tuples, returning their record ids. Each chain's last page is re-encoded once for
+    /// all of them, so inserting in batches is much cheaper than one tuple at a time. Nothing is
+    /// inserted if any of the tuples doesn't fit the schema.
+    pub fn insert_tuples(&mut self, tuples: &[Tuple]) -> Result<Vec<u64>> {
+        let rows = tuples
+            .iter()
+            .map(|tuple| self.split_tuple(tuple))
+            .collect::<Result<Vec<_>>>()?;
+        let first_row = self.row_count();
+
+        let mut columns = vec![Vec::with_capacity(rows.len()); self.schema.num_columns()];
+        for row in rows {
+            for (column, value) in columns.iter_mut().zip(row) {
+                column.push(value);
+            }
+        }
+        columns.push(vec![deleted_flag(false); tuples.len()]);
+        for (chain, values) in self.chains.iter_mut().zip(columns) {
+            chain.append(&self.bpm, values)?;
+        }
+        // The chains just got their first page.
+        if first_row == 0 && !tuples.is_empty() {
+            self.save_header()?;
+        }
+
+        for tuple in tuples {
+            self.stats.inserted(tuple.tuple_size());
+        }
+        Ok((first_row..self.row_count()).map(record_id).collect())
+    }
+
+    /// Overwrites the row with record id `rid`, keeping its record id. Only the pages of the
+    /// columns whose value changed are rewritten.
+    pub fn update_tuple(&mut self, rid: u64, tuple: &Tuple) -> Result<()> {
+        let values = self.split_tuple(tuple)?;
+        let Some(row) = self.row(rid)? else {
+            return errinput!("Cannot update deleted tuple {}", rid);
+        };
+        let old_size = self.read_tuple(row)?.tuple_size();
+        for (chain, value) in self.chains.iter_mut().zip(values) {
+            chain.set(&self.bpm, row, value)?;
+        }
+        self.stats.resized(old_size, tuple.tuple_size());
+        Ok(())
+    }
+
+    /// Deletes a row by flagging it as deleted. Deleting it again does nothing.
+    pub fn delete_tuple(&mut self, rid: u64) -> Result<()> {
+        let Some(row) = self.row(rid)? else {
+            return Ok(());
+        };
+        let size = self.read_tuple(row)?.tuple_size();
+        self.chains
+            .last_mut()
+            .unwrap()
+            .set(&self.bpm, row, deleted_flag(true))?;
+        self.stats.deleted(size);
+        self.stats.slot_deleted();
+        Ok(())
+    }
+
+    /// Removes every row, deleting the pages of every chain. Fails without changing the store if
+    /// any of them is pinned. Row ordinals, and thus record ids, start over from the first one.
+    pub fn truncate(&mut self) -> Result<()> {
+        let page_ids = self.chain_page_ids();
+        self.bpm.write().unwrap().delete_pages(&page_ids)?;
+        self.chains = chain_types(&self.schema).map(ColumnChain::new).collect();
+        self.stats = HeapStats::default();
+        self.save_header()
+    }
+
+    fn save_header(&self) -> Result<()> {
+        let first_page_ids: Vec<_> = self.chains.iter().map(ColumnChain::first_page_id).collect();
+        let handle = BufferPoolManager::fetch_page_mut_handle(
+            &self.bpm,
+            self.header_page_id,
+            AccessType::Unknown,
+        )?;
+        ColumnHeaderPageMut::from(handle).init(&first_page_ids)
+    }
+
+    /// Returns the ids of every page of the store: its header page and the pages of its chains.
+    pub(crate) fn page_ids(&self) -> Vec<PageId> {
+        let mut page_ids = vec![self.header_page_id];
+        page_ids.extend(self.chain_page_ids());
+        page_ids
+    }
+
+    fn chain_page_ids(&self) -> Vec<PageId> {
+        let chains = self.chains.iter();
+        chains
+            .flat_map(|chain| chain.page_ids.iter().copied())
+            .collect()
+    }
+
+    /// Splits a serialized tuple into the bytes of each of its column values, checking that each
+    /// fits in a column page.
+    fn split_tuple(&self, tuple: &Tuple) -> Result<Vec<Value>> {
+        if tuple.tuple_size() < self.schema.size() {
+            return errinput!(
+                "Tuple of {} bytes is too small for schema {}",
+                tuple.tuple_size(),
+                self.schema
+            );
+        }
+        let values: Vec<Value> = Serde::deserialize(&tuple.data(), &self.schema)
+            .iter()
+            .map(Field::to_bytes)
+            .collect();
+        for (value, chain) in values.iter().zip(&self.chains) {
+            if plain_size(chain.ty, value) > COLUMN_PAGE_CAPACITY {
+                return errinput!(
+                    "Value of {} bytes is too large for a column page",
+                    value.len()
+                );
+            }
+        }
+        Ok(values)
+    }
+}
+
+/// Returns the type of each chain of a store with the given schema.
+fn chain_types(schema: &Schema) -> impl Iterator<Item = Type> + '_ {
+    let columns = schema.columns().iter().map(|column| column.field_type());
+    columns.chain([Type::Boolean])
+}
+
+impl ScanLocked for ColumnStore {
+    fn scan_lock(&self) -> &Arc<ScanLock> {
+        &self.scan_lock
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustdb_catalog::column::Column;
+    use serial_test::serial;
+
+    use super::*;
+    use crate::test_utils::get_bpm_arc_with_pool_size;
+
+    fn schema() -> Schema {
+        Schema::new(&[
+            Column::new("id".to_string(), Type::Integer),
+            Column::new("region".to_string(), Type::Varchar),
+            Column::new("amount".to_string(), Type::Float),
+            Column::new("flagged".to_string(), Type::Boolean),
+        ])
+    }
+
+    fn row(i: i32) -> Vec<Field> {
+        vec![
+            Field::Integer(i),
+            Field::Varchar(format!("region {}", i / 1000)),
+            Field::Float(i as f64 * 1.5),
+            Field::Boolean(i % 10 == 0),
+        ]
+    }
+
+    fn tuple(fields: &[Field]) -> Tuple {
+        Tuple::new(Serde::serialize(fields).into())
+    }
+
+    #[test]
+    #[serial]
+    fn test_column_store() -> Result<()> {
+        let bpm = get_bpm_arc_with_pool_size(10);
+        let mut store = ColumnStore::new(schema(), bpm.clone());
+        let mut rids =
+            store.insert_tuples(&(0..3000).map(|i| tuple(&row(i))).collect::<Vec<_>>())?;
+        for i in 3000..3100 {
+            rids.push(store.insert_tuple(&tuple(&row(i)))?);
+        }
+        assert_eq!(
+            (0..3100).map(record_id).collect::<Vec<_>>(),
+            rids,
+            "record ids are row ordinals"
+        );
+        for i in [0, 999, 1000, 2500, 3099] {
+            let data = store.get_tuple(rids[i])?.data();
+            assert_eq!(row(i as i32), Serde::deserialize(&data, &schema()));
+        }
+        assert!(store.get_tuple(record_id(3100)).is_err());
+        assert!(store.get_tuple(0).is_err());
+
+        // Each column is encoded on its own: the few regions and flags take up a page each, while
+        // the floats need many.
+        let page_cnts: Vec<usize> = store.chains.iter().map(|c| c.page_ids.len()).collect();
+        assert_eq!(1, page_cnts[1]);
+        assert_eq!(1, page_cnts[3]);
+        assert_eq!(1, page_cnts[4]);
+        assert!(page_cnts[2] >= 3100 * 8 / COLUMN_PAGE_CAPACITY);
+        assert!(page_cnts[0] < page_cnts[2]);
+
+        // Growing values splits their page once they no longer fit.
+        let long = [
+            Field::Integer(-1),
+            Field::Varchar("x".repeat(3000)),
+            Field::Float(0.0),
+            Field::Boolean(true),
+        ];
+        store.update_tuple(rids[5], &tuple(&long))?;
+        store.update_tuple(rids[2000], &tuple(&long))?;
+        assert!(store.chains[1].page_ids.len() > 1);
+        for i in [5, 2000] {
+            let data = store.get_tuple(rids[i])?.data();
+            assert_eq!(long.to_vec(), Serde::deserialize(&data, &schema()));
+        }
+        for i in [4, 6, 1999, 2001, 3099] {
+            let data = store.get_tuple(rids[i])?.data();
+            assert_eq!(row(i as i32), Serde::deserialize(&data, &schema()));
+        }
+
+        store.delete_tuple(rids[7])?;
+        store.delete_tuple(rids[7])?;
+        assert!(store.get_tuple(rids[7]).is_err());
+        assert!(store.update_tuple(rids[7], &tuple(&row(7))).is_err());
+        let too_large = [
+            Field::Integer(0),
+            Field::Varchar("x".repeat(COLUMN_PAGE_CAPACITY)),
+            Field::Float(0.0),
+            Field::Boolean(false),
+        ];
+        assert!(store.insert_tuple(&tuple(&too_large)).is_err());
+        assert_eq!(3100, store.row_count());
+
+        // Reopening the store finds its pages and counts its statistics again.
+        let stats = store.stats();
+        assert_eq!(3099, stats.row_count);
+        assert_eq!(1, stats.deleted_count);
+        let header_page_id = store.header_page_id();
+        drop(store);
+        let mut store = ColumnStore::open(header_page_id, schema(), bpm.clone())?;
+        assert_eq!(stats, store.stats());
+        let data = store.get_tuple(rids[2000])?.data();
+        assert_eq!(long.to_vec(), Serde::deserialize(&data, &schema()));
+        assert!(ColumnStore::open(header_page_id, Schema::new(&[]), bpm.clone()).is_err());
+
+        store.truncate()?;
+        assert_eq!(0, store.row_count());
+        assert_eq!(TableStats::default(), store.stats());
+        assert_eq!(record_id(0), store.insert_tuple(&tuple(&row(0)))?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+use std::sync::{Arc, RwLock};
+
+use crate::buffer_pool::BufferPoolManager;
+use crate::page::free_space_page::{FreeSpacePageMut, FreeSpacePageRef, FREE_SPACE_PAGE_CAPACITY};
+use crate::page::{INVALID_PAGE_ID, PAGE_SIZE};
+use crate::replacer::replacer::AccessType;
+use crate::typedef::PageId;
+use crate::Result;
+
+/// Free space is tracked in steps of this many bytes, so that it fits in a byte per page.
+pub(crate) const FREE_SPACE_CATEGORY_SIZE: usize = PAGE_SIZE / 256;
+
+/// Returns the category of a page with `free_bytes` free. Rounds down, so a page is never
+/// recorded as having more room than it does.
+fn category_of(free_bytes: usize) -> u8 {
+    (free_bytes / FREE_SPACE_CATEGORY_SIZE).min(u8::MAX as usize) as u8
+}
+
+/// Returns the smallest category guaranteed to hold `bytes`.
+fn category_for(bytes: usize) -> usize {
+    bytes.div_ceil(FREE_SPACE_CATEGORY_SIZE)
+}
+
+/// Tracks the approximate free space of each page of a table heap, so inserts can find a page
+/// with enough room instead of always appending to the last one.
+///
+/// The map is stored in a chain of free space pages, so it lives in the buffer pool and on disk
+/// just like the heap it describes. Every heap page id has a fixed slot: the n-th page of the
+/// chain records the pages with ids from `n * FREE_SPACE_PAGE_CAPACITY` on, so looking a page up
+/// only fetches the map page holding its slot. Pages the map doesn't track, like those of other
+/// tables, are recorded as having no free space.
+///
+/// The map is only a hint: the heap still has to handle a page being fuller than the map says,
+/// and must report the page's actual free space back when it is.
+pub(crate) struct FreeSpaceMap {
+    bpm: Arc<RwLock<BufferPoolManager>>,
+    /// The ids of the map's pages, in chain order, so the n-th one can be found without walking
+    /// the chain.
+    page_ids: RwLock<Vec<PageId>>,
+}
+
+impl FreeSpaceMap {
+    /// Creates an empty free space map, allocating its first page.
+    pub(crate) fn new(bpm: Arc<RwLock<BufferPoolManager>>) -> Result<Self> {
+        let first_page_id = Self::create_page(&bpm)?;
+        Ok(Self {
+            bpm,
+            page_ids: RwLock::new(vec![first_page_id]),
+        })
+    }
+
+    /// Opens the free space map whose first page is `first_page_id`.
+    pub(crate) fn open(bpm: Arc<RwLock<BufferPoolManager>>, first_page_id: PageId) -> Result<Self> {
+        let mut page_ids = Vec::new();
+        let mut page_id = first_page_id;
+        while page_id != INVALID_PAGE_ID {
+            page_ids.push(page_id);
+            let handle = BufferPoolManager::fetch_page_handle(&bpm, page_id, AccessType::Index)?;
+            page_id = FreeSpacePageRef::try_from(handle)?.next_page_id();
+        }
+        Ok(Self {
+            bpm,
+            page_ids: RwLock::new(page_ids),
+        })
+    }
+
+    pub(crate) fn first_page_id(&self) -> PageId {
+        self.page_ids.read().unwrap()[0]
+    }
+
+    /// Returns the ids of the map's own pages.
+    pub(crate) fn page_ids(&self) -> Result<Vec<PageId>> {
+        Ok(self.page_ids.read()?.clone())
+    }
+
+    /// Returns the heap page with the lowest id recorded as having at least `bytes` free, if
+    /// there is one. Pages with less than a category free are never picked.
+    pub(crate) fn find(&self, bytes: usize) -> Result<Option<PageId>> {
+        let needed = category_for(bytes).max(1);
+        for (n, page_id) in self.page_ids()?.into_iter().enumerate() {
+            let page = self.fetch(page_id)?;
+            let found = page
+                .categories()
+                .iter()
+                .position(|&category| category as usize >= needed);
+            if let Some(offset) = found {
+                return Ok(Some((n * FREE_SPACE_PAGE_CAPACITY + offset) as PageId));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the free space recorded for `heap_page_id`, rounded down to its category.
+    pub(crate) fn get(&self, heap_page_id: PageId) -> Result<usize> {
+        let (n, offset) = slot_of(heap_page_id);
+        let Some(page_id) = self.page_ids.read()?.get(n).copied() else {
+            return Ok(0);
+        };
+        let category = self.fetch(page_id)?.categories()[offset];
+        Ok(category as usize * FREE_SPACE_CATEGORY_SIZE)
+    }
+
+    /// Records that `heap_page_id` has `free_bytes` free, growing the map if needed.
+    pub(crate) fn update(&self, heap_page_id: PageId, free_bytes: usize) -> Result<()> {
+        let (n, offset) = slot_of(heap_page_id);
+        let page_id = self.grow_to(n)?;
+        let category = category_of(free_bytes);
+        if self.fetch(page_id)?.categories()[offset] != category {
+            self.fetch_mut(page_id)?.categories_mut()[offset] = category;
+        }
+        Ok(())
+    }
+
+    /// Stops tracking `heap_page_id`, e.g. because it was freed.
+    pub(crate) fn remove(&self, heap_page_id: PageId) -> Result<()> {
+        let (n, offset) = slot_of(heap_page_id);
+        let page_id = self.page_ids.read()?.get(n).copied();
+        if let Some(page_id) = page_id {
+            self.fetch_mut(page_id)?.categories_mut()[offset] = 0;
+        }
+        Ok(())
+    }
+
+    /// Returns the id of the n-th map page, adding pages to the chain until there is one.
+    fn grow_to(&self, n: usize) -> Result<PageId> {
+        let mut page_ids = self.page_ids.write()?;
+        while page_ids.len() <= n {
+            let new_page_id = Self::create_page(&self.bpm)?;
+            self.fetch_mut(*page_ids.last().unwrap())?
+                .set_next_page_id(new_page_id);
+            page_ids.push(new_page_id);
+        }
+        Ok(page_ids[n])
+    }
+
+    fn create_page(bpm: &Arc<RwLock<BufferPoolManager>>) -> Result<PageId> {
+        let mut page = FreeSpacePageMut::from(BufferPoolManager::create_page_handle(bpm)?);
+        page.init(INVALID_PAGE_ID);
+        Ok(page.page_id())
+    }
+
+    fn fetch(&self, page_id: PageId) -> Result<FreeSpacePageRef<'_>> {
+        let handle = BufferPoolManager::fetch_page_handle(&self.bpm, page_id, AccessType::Index)?;
+        FreeSpacePageRef::try_from(handle)
+    }
+
+    fn fetch_mut(&self, page_id: PageId) -> Result<FreeSpacePageMut<'_>> {
+        let handle =
+            BufferPoolManager::fetch_page_mut_handle(&self.bpm, page_id, AccessType::Index)?;
+        Ok(FreeSpacePageMut::from(handle))
+    }
+}
+
+/// Returns the index of the map page holding the slot of `heap_page_id`, and the slot's offset.
+fn slot_of(heap_page_id: PageId) -> (usize, usize) {
+    let heap_page_id = heap_page_id as usize;
+    (
+        heap_page_id / FREE_SPACE_PAGE_CAPACITY,
+        heap_page_id % FREE_SPACE_PAGE_CAPACITY,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+    use crate::page::free_space_page::FREE_SPACE_PAGE_CAPACITY;
+    use crate::test_utils::get_bpm_arc_with_pool_size;
+
+    #[test]
+    #[serial]
+    fn test_free_space_map() -> Result<()> {
+        let bpm = get_bpm_arc_with_pool_size(4);
+        let fsm = FreeSpaceMap::new(bpm.clone())?;
+        assert_eq!(None, fsm.find(1)?);
+
+        fsm.update(10, 100)?;
+        fsm.update(11, 1000)?;
+        fsm.update(12, 4000)?;
+
+        // Free space is rounded down, so a page is only picked if it certainly has room.
+        assert_eq!(96, fsm.get(10)?);
+        assert_eq!(Some(10), fsm.find(96)?);
+        assert_eq!(Some(11), fsm.find(97)?);
+        assert_eq!(Some(12), fsm.find(1001)?);
+        assert_eq!(None, fsm.find(4001)?);
+
+        // Untracked pages have no free space, and removed pages are no longer picked.
+        assert_eq!(0, fsm.get(13)?);
+        assert_eq!(Some(10), fsm.find(0)?);
+        fsm.update(10, 0)?;
+        fsm.remove(11)?;
+        assert_eq!(0, fsm.get(11)?);
+        assert_eq!(Some(12), fsm.find(1)?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_free_space_map_spans_pages() -> Result<()> {
+        let bpm = get_bpm_arc_with_pool_size(4);
+        let fsm = FreeSpaceMap::new(bpm.clone())?;
+
+        // Each map page covers a fixed range of heap page ids, so tracking a page far into the
+        // third range adds the pages for the ranges before it.
+        let capacity = FREE_SPACE_PAGE_CAPACITY as PageId;
+        let heap_pages: Vec<PageId> = (1..capacity)
+            .chain(2 * capacity..2 * capacity + 10)
+            .collect();
+        for &heap_page_id in heap_pages.iter().rev() {
+            fsm.update(heap_page_id, 0)?;
+        }
+        assert_eq!(3, fsm.page_ids()?.len());
+        assert_eq!(None, fsm.find(1)?);
+
+        // The page with the lowest id wins, whichever page of the map holds it.
+        fsm.update(2 * capacity + 5, PAGE_SIZE)?;
+        assert_eq!(Some(2 * capacity + 5), fsm.find(PAGE_SIZE / 2)?);
+        fsm.update(capacity - 1, PAGE_SIZE / 2)?;
+        assert_eq!(Some(capacity - 1), fsm.find(PAGE_SIZE / 4)?);
+        fsm.remove(capacity - 1)?;
+        assert_eq!(Some(2 * capacity + 5), fsm.find(PAGE_SIZE / 4)?);
+
+        // The map can be reopened from its first page.
+        let reopened = FreeSpaceMap::open(bpm.clone(), fsm.first_page_id())?;
+        assert_eq!(fsm.page_ids()?, reopened.page_ids()?);
+        assert_eq!(Some(2 * capacity + 5), reopened.find(PAGE_SIZE / 2)?);
+        assert_eq!(0, reopened.get(1)?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rustdb_catalog::catalog::TableStats;
+
+use crate::frame::PageFrame;
+use crate::page::table_page::TablePage;
+
+/// Running totals behind a table heap's [`TableStats`], updated by each write as it happens
+/// instead of being recounted from the heap's pages.
+///
+/// Writes to a heap are already serialized by its lock, but some of them only take `&self`, so
+/// the counters are atomics.
+#[derive(Default)]
+pub(crate) struct HeapStats {
+    tuple_cnt: AtomicU64,
+    tuple_bytes: AtomicU64,
+    deleted_cnt: AtomicU64,
+}
+
+impl HeapStats {
+    /// Starts from totals counted from the heap's pages, e.g. when it is reopened.
+    pub(crate) fn new(tuple_cnt: u64, tuple_bytes: u64, deleted_cnt: u64) -> Self {
+        Self {
+            tuple_cnt: tuple_cnt.into(),
+            tuple_bytes: tuple_bytes.into(),
+            deleted_cnt: deleted_cnt.into(),
+        }
+    }
+
+    /// Records that a tuple of `size` bytes was inserted.
+    pub(crate) fn inserted(&self, size: usize) {
+        self.tuple_cnt.fetch_add(1, Ordering::Relaxed);
+        self.tuple_bytes.fetch_add(size as u64, Ordering::Relaxed);
+    }
+
+    /// Records that a tuple of `size` bytes was deleted. Its slot is counted as deleted separately,
+    /// by [`HeapStats::track`] or [`HeapStats::slot_deleted`].
+    pub(crate) fn deleted(&self, size: usize) {
+        self.tuple_cnt.fetch_sub(1, Ordering::Relaxed);
+        self.tuple_bytes.fetch_sub(size as u64, Ordering::Relaxed);
+    }
+
+    /// Records that a tuple of `old_size` bytes was replaced by one of `new_size` bytes.
+    pub(crate) fn resized(&self, old_size: usize, new_size: usize) {
+        self.tuple_bytes
+            .fetch_add(new_size as u64, Ordering::Relaxed);
+        self.tuple_bytes
+            .fetch_sub(old_size as u64, Ordering::Relaxed);
+    }
+
+    /// Runs `f` on a table page, recording how many of the page's slots it marked as deleted or
+    /// reclaimed by compacting the page.
+    pub(crate) fn track<T, R>(
+        &self,
+        page: &mut TablePage<T>,
+        f: impl FnOnce(&mut TablePage<T>) -> R,
+    ) -> R
+    where
+        T: DerefMut<Target = PageFrame> + Deref<Target = PageFrame>,
+    {
+        let before = page.deleted_count();
+        let result = f(page);
+        self.deleted_cnt
+            .fetch_add(page.deleted_count() as u64, Ordering::Relaxed);
+        self.deleted_cnt.fetch_sub(before as u64, Ordering::Relaxed);
+        result
+    }
+
+    /// Records that a slot was marked as deleted, for heaps whose pages don't count them.
+    pub(crate) fn slot_deleted(&self) {
+        self.deleted_cnt.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the statistics of a heap with these totals and `page_cnt` pages.
+    pub(crate) fn to_table_stats(&self, page_cnt: u32) -> TableStats {
+        TableStats {
+            row_count: self.tuple_cnt.load(Ordering::Relaxed),
+            page_count: page_cnt as u64,
+            tuple_bytes: self.tuple_bytes.load(Ordering::Relaxed),
+            deleted_count: self.deleted_cnt.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use std::ops::Bound;
+use std::sync::Arc;
+
+use rustdb_catalog::schema;
+use rustdb_catalog::tuple::Tuple;
+
+use crate::heap::lsm_tree::{Memtable, Run};
+use crate::heap::sampler::Sampler;
+use crate::heap::scan_filter::ScanFilter;
+use crate::page::run_page::RunEntry;
+use crate::record_id::{RecordId, INVALID_RECORD_ID};
+use crate::replacer::replacer::AccessType;
+use crate::Result;
+
+/// Where a merged iteration reads entries from.
+enum Source {
+    Memtable(Arc<Memtable>),
+    /// A run, read a page at a time. `entries` holds what is left of page `page`.
+    Run {
+        run: Arc<Run>,
+        page: usize,
+        entries: std::vec::IntoIter<RunEntry>,
+    },
+}
+
+/// A source along with the entry it is positioned on, if it has any left.
+struct Cursor {
+    source: Source,
+    head: Option<RunEntry>,
+}
+
+impl Cursor {
+    /// Positions a cursor on the first entry of `source` whose key is at least `start`.
+    fn seek(mut source: Source, start: u64) -> Result<Self> {
+        let head = match &mut source {
+            Source::Memtable(memtable) => first_after(memtable, Bound::Included(start)),
+            Source::Run { run, page, entries } => {
+                *page = run.page_for(start);
+                *entries = run.read_page(*page, AccessType::Scan)?.into_iter();
+                let mut head = next_in_run(run, page, entries)?;
+                while head.as_ref().is_some_and(|(key, _)| *key < start) {
+                    head = next_in_run(run, page, entries)?;
+                }
+                head
+            }
+        };
+        Ok(Self { source, head })
+    }
+
+    /// Moves the cursor past its head, whose key is `key`.
+    fn advance(&mut self, key: u64) -> Result<()> {
+        self.head = match &mut self.source {
+            Source::Memtable(memtable) => first_after(memtable, Bound::Excluded(key)),
+            Source::Run { run, page, entries } => next_in_run(run, page, entries)?,
+        };
+        Ok(())
+    }
+}
+
+fn first_after(memtable: &Memtable, bound: Bound<u64>) -> Option<RunEntry> {
+    let (key, value) = memtable.range((bound, Bound::Unbounded)).next()?;
+    Some((*key, value.clone()))
+}
+
+/// Returns the next entry of a run, reading its next page once `entries` runs out.
+fn next_in_run(
+    run: &Run,
+    page: &mut usize,
+    entries: &mut std::vec::IntoIter<RunEntry>,
+) -> Result<Option<RunEntry>> {
+    loop {
+        if let Some(entry) = entries.next() {
+            return Ok(Some(entry));
+        }
+        if *page + 1 >= run.page_count() {
+            return Ok(None);
+        }
+        *page += 1;
+        *entries = run.read_page(*page, AccessType::Scan)?.into_iter();
+    }
+}
+
+/// Merges the memtable and the runs of an LSM tree into a single sequence of entries sorted by
+/// key. Where several sources hold the same key, only the newest entry is kept, tombstones
+/// included, so the caller decides what a tombstone means.
+pub(crate) struct MergedEntries {
+    /// Ordered from newest to oldest source.
+    cursors: Vec<Cursor>,
+    /// Keys from here on are left out.
+    stop: Option<u64>,
+}
+
+impl MergedEntries {
+    /// Merges the entries with keys from `start` up to `stop` of the given memtable and runs,
+    /// which must be ordered from newest to oldest.
+    pub(crate) fn new(
+        memtable: Option<Arc<Memtable>>,
+        runs: Vec<Arc<Run>>,
+        start: u64,
+        stop: Option<u64>,
+    ) -> Result<Self> {
+        let sources = memtable
+            .map(Source::Memtable)
+            .into_iter()
+            .chain(runs.into_iter().map(|run| Source::Run {
+                run,
+                page: 0,
+                entries: Vec::new().into_iter(),
+            }));
+        let cursors = sources
+            .map(|source| Cursor::seek(source, start))
+            .collect::<Result<_>>()?;
+        Ok(Self { cursors, stop })
+    }
+
+    /// An empty sequence.
+    pub(crate) fn empty() -> Self {
+        Self {
+            cursors: Vec::new(),
+            stop: None,
+        }
+    }
+
+    /// Returns the entry with the next smallest key.
+    pub(crate) fn next_entry(&mut self) -> Result<Option<RunEntry>> {
+        let heads = self
+            .cursors
+            .iter()
+            .filter_map(|cursor| cursor.head.as_ref());
+        let Some(key) = heads.map(|(key, _)| *key).min() else {
+            return Ok(None);
+        };
+        if self.stop.is_some_and(|stop| key >= stop) {
+            self.cursors.clear();
+            return Ok(None);
+        }
+
+        let mut newest = None;
+        for cursor in &mut self.cursors {
+            if cursor.head.as_ref().is_some_and(|(k, _)| *k == key) {
+                let (_, value) = cursor.head.take().unwrap();
+                newest.get_or_insert(value);
+                cursor.advance(key)?;
+            }
+        }
+        Ok(newest.map(|value| (key, value)))
+    }
+}
+
+/// An iterator over the tuples of an LSM tree, in record id order.
+///
+/// It reads a snapshot of the tree taken when it was created: the memtable is shared with the
+/// tree until the tree's next write, and runs merged away while the scan is running are only
+/// freed once it is dropped. The scan thus returns exactly the tuples the tree held when it
+/// started, without keeping writers out.
+pub struct LsmIterator {
+    entries: MergedEntries,
+    /// The record id of the next tuple the scan looks at.
+    position: u64,
+    filter: Option<ScanFilter>,
+    sampler: Option<Sampler>,
+}
+
+impl LsmIterator {
+    pub(crate) fn new(entries: MergedEntries, start: u64) -> Self {
+        Self {
+            entries,
+            position: start,
+            filter: None,
+            sampler: None,
+        }
+    }
+
+    /// Only emits the tuples that pass `filter`, projected as it says.
+    pub(crate) fn with_filter(mut self, filter: ScanFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Only emits the tuples `sampler` picks. The runs of a tree overlap, so leaving out a page of
+    /// one could expose older versions of its tuples; every tuple is sampled on its own instead,
+    /// whichever method the sampler uses.
+    pub(crate) fn with_sampler(mut self, sampler: Sampler) -> Self {
+        self.sampler = Some(sampler);
+        self
+    }
+
+    /// Returns the record id the scan looks at next, or [`INVALID_RECORD_ID`] once it is over.
+    pub(crate) fn position(&self) -> RecordId {
+        RecordId::from(self.position)
+    }
+}
+
+impl Iterator for LsmIterator {
+    type Item = Result<(schema::RecordId, Tuple)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (key, value) = match self.entries.next_entry() {
+                Ok(Some(entry)) => entry,
+                Ok(None) => {
+                    self.position = INVALID_RECORD_ID.into();
+                    return None;
+                }
+                Err(e) => {
+                    self.entries = MergedEntries::empty();
+                    return Some(Err(e));
+                }
+            };
+            self.position = key + 1;
+            // Tombstones hide the tuples they delete.
+            let Some(data) = value else {
+                continue;
+            };
+            if let Some(sampler) = &mut self.sampler {
+                if !(sampler.sample_page() && sampler.sample_tuple()) {
+                    continue;
+                }
+            }
+            let tuple = match &self.filter {
+                Some(filter) => match filter.apply(&data) {
+                    Ok(Some(tuple)) => tuple,
+                    Ok(None) => continue,
+                    Err(e) => return Some(Err(e)),
+                },
+                None => Tuple::new(data),
+            };
+            return Some(Ok((key, tuple)));
+        }
+    }
+}
@@ -0,0 +1,655 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+use bytes::Bytes;
+use rustdb_catalog::catalog::TableStats;
+use rustdb_catalog::tuple::Tuple;
+use rustdb_error::{errdata, errinput};
+
+use crate::buffer_pool::BufferPoolManager;
+use crate::heap::heap_stats::HeapStats;
+use crate::heap::lsm_iterator::{LsmIterator, MergedEntries};
+use crate::heap::scan_lock::{ScanLock, ScanLocked};
+use crate::page::lsm_header_page::{LsmHeaderPageMut, LsmHeaderPageRef, RunLocation};
+use crate::page::run_page::{
+    encode_run_entry, run_entry_size, RunEntry, RunPageMut, RunPageRef, MAX_RUN_VALUE_SIZE,
+    RUN_PAGE_CAPACITY,
+};
+use crate::page::{INVALID_PAGE_ID, PAGE_SIZE};
+use crate::replacer::replacer::AccessType;
+use crate::typedef::PageId;
+use crate::Result;
+
+/// Size the memtable's entries may add up to, as stored in a run, before it is flushed.
+const MEMTABLE_CAPACITY: usize = 16 * PAGE_SIZE;
+/// Number of runs level 0 may hold before they are merged into level 1.
+const LEVEL0_RUN_LIMIT: usize = 4;
+/// Number of pages level 1 may hold before it is merged into level 2.
+const LEVEL1_PAGE_LIMIT: usize = 64;
+/// How many times more pages each level below level 1 may hold than the level above it.
+const LEVEL_SIZE_RATIO: usize = 10;
+
+/// The record id of the first tuple inserted into an LSM tree. Record ids are handed out in
+/// order from here. Starting past page id [`INVALID_PAGE_ID`] keeps them apart from
+/// [`crate::record_id::INVALID_RECORD_ID`], which scan cursors use to mark the end of a scan.
+pub(crate) const FIRST_RECORD_ID: u64 = 1 << 32;
+
+/// The newest versions of recently written tuples, by record id, with `None` for deleted ones.
+pub(crate) type Memtable = BTreeMap<u64, Option<Bytes>>;
+
+/// An immutable chain of run pages holding entries sorted by key, each key at most once.
+pub(crate) struct Run {
+    bpm: Arc<RwLock<BufferPoolManager>>,
+    page_ids: Vec<PageId>,
+    /// The smallest key of each page, to find the page a key would be in without reading others.
+    fences: Vec<u64>,
+    last_key: u64,
+    tombstone_cnt: u64,
+    /// Set once the run has been merged into another. Its pages are then freed as soon as the
+    /// last scan reading it is done with it.
+    obsolete: AtomicBool,
+}
+
+impl Run {
+    /// Reads the run whose chain starts at `first_page_id`.
+    fn open(bpm: Arc<RwLock<BufferPoolManager>>, first_page_id: PageId) -> Result<Self> {
+        let mut run = Self {
+            bpm,
+            page_ids: Vec::new(),
+            fences: Vec::new(),
+            last_key: 0,
+            tombstone_cnt: 0,
+            obsolete: AtomicBool::new(false),
+        };
+        let mut page_id = first_page_id;
+        while page_id != INVALID_PAGE_ID {
+            let handle = BufferPoolManager::fetch_page_handle(&run.bpm, page_id, AccessType::Scan)?;
+            let page = RunPageRef::try_from(handle)?;
+            let entries = page.entries()?;
+            let (Some((first_key, _)), Some((last_key, _))) = (entries.first(), entries.last())
+            else {
+                return errdata!("Run page {} is empty", page_id);
+            };
+            run.page_ids.push(page_id);
+            run.fences.push(*first_key);
+            run.last_key = *last_key;
+            run.tombstone_cnt += entries.iter().filter(|(_, value)| value.is_none()).count() as u64;
+            page_id = page.next_page_id();
+        }
+        if run.page_ids.is_empty() {
+            return errdata!("Run has no pages");
+        }
+        Ok(run)
+    }
+
+    pub(crate) fn page_count(&self) -> usize {
+        self.page_ids.len()
+    }
+
+    /// Returns the index of the page `key` is in, if the run holds it.
+    pub(crate) fn page_for(&self, key: u64) -> usize {
+        self.fences
+            .partition_point(|&fence| fence <= key)
+            .saturating_sub(1)
+    }
+
+    /// Reads the entries of the run's `i`th page.
+    pub(crate) fn read_page(&self, i: usize, access_type: AccessType) -> Result<Vec<RunEntry>> {
+        let handle =
+            BufferPoolManager::fetch_page_handle(&self.bpm, self.page_ids[i], access_type)?;
+        RunPageRef::try_from(handle)?.entries()
+    }
+
+    /// Looks `key` up, returning its entry if the run holds it. Only reads the page it would be in.
+    fn get(&self, key: u64) -> Result<Option<Option<Bytes>>> {
+        if key < self.fences[0] || key > self.last_key {
+            return Ok(None);
+        }
+        let mut entries = self.read_page(self.page_for(key), AccessType::Lookup)?;
+        Ok(entries
+            .binary_search_by_key(&key, |(k, _)| *k)
+            .ok()
+            .map(|i| entries.swap_remove(i).1))
+    }
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        if *self.obsolete.get_mut() {
+            // There's nobody to report an error to; pages that can't be freed are leaked.
+            let _ = self.bpm.write().unwrap().delete_pages(&self.page_ids);
+        }
+    }
+}
+
+/// Writes entries, which must come in increasing key order, to a new run.
+struct RunWriter<'a> {
+    bpm: &'a Arc<RwLock<BufferPoolManager>>,
+    page_ids: Vec<PageId>,
+    fences: Vec<u64>,
+    last_key: u64,
+    tombstone_cnt: u64,
+    /// The encoded entries of the page being filled.
+    data: Vec<u8>,
+    data_cnt: u32,
+}
+
+impl<'a> RunWriter<'a> {
+    fn new(bpm: &'a Arc<RwLock<BufferPoolManager>>) -> Self {
+        Self {
+            bpm,
+            page_ids: Vec::new(),
+            fences: Vec::new(),
+            last_key: 0,
+            tombstone_cnt: 0,
+            data: Vec::with_capacity(RUN_PAGE_CAPACITY),
+            data_cnt: 0,
+        }
+    }
+
+    fn push(&mut self, key: u64, value: Option<&[u8]>) -> Result<()> {
+        if self.data.len() + run_entry_size(value) > RUN_PAGE_CAPACITY {
+            self.write_page()?;
+        }
+        if self.data_cnt == 0 {
+            self.fences.push(key);
+        }
+        encode_run_entry(&mut self.data, key, value);
+        self.data_cnt += 1;
+        self.last_key = key;
+        self.tombstone_cnt += u64::from(value.is_none());
+        Ok(())
+    }
+
+    /// Writes the entries gathered so far to a new page, linking it to the previous one.
+    fn write_page(&mut self) -> Result<()> {
+        let mut page = RunPageMut::from(BufferPoolManager::create_page_handle(self.bpm)?);
+        page.init(self.data_cnt, &self.data);
+        let page_id = page.page_id();
+        drop(page);
+        if let Some(&prev_page_id) = self.page_ids.last() {
+            let handle = BufferPoolManager::fetch_page_mut_handle(
+                self.bpm,
+                prev_page_id,
+                AccessType::Unknown,
+            )?;
+            RunPageMut::from(handle).set_next_page_id(page_id);
+        }
+        self.page_ids.push(page_id);
+        self.data.clear();
+        self.data_cnt = 0;
+        Ok(())
+    }
+
+    /// Returns the written run, or `None` if no entry was pushed.
+    fn finish(mut self) -> Result<Option<Run>> {
+        if self.data_cnt > 0 {
+            self.write_page()?;
+        }
+        if self.page_ids.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Run {
+            bpm: self.bpm.clone(),
+            page_ids: self.page_ids,
+            fences: self.fences,
+            last_key: self.last_key,
+            tombstone_cnt: self.tombstone_cnt,
+            obsolete: AtomicBool::new(false),
+        }))
+    }
+}
+
+/// A table stored as a log-structured merge tree, for tables that are mostly appended to.
+///
+/// Writes go to an in-memory memtable, and deletes leave a tombstone there. Once the memtable is
+/// large enough it is flushed to a sorted run of pages in level 0, so writes never touch pages
+/// that are already written. Level 0 holds up to [`LEVEL0_RUN_LIMIT`] runs, which may overlap;
+/// when it has more they are all merged with the run of level 1. Every other level holds a single
+/// run, [`LEVEL_SIZE_RATIO`] times larger than the level above, and is merged into the level below
+/// once it outgrows that. Tombstones are dropped when they reach the lowest level, as no older
+/// version of their tuple is left to hide.
+///
+/// Tuples get increasing record ids, starting at [`FIRST_RECORD_ID`], and keep them for good.
+/// Reading a tuple checks the memtable and then the runs from newest to oldest; a scan merges
+/// them all (see [`LsmIterator`]).
+///
+/// The memtable is only written out when it is flushed, which includes when the tree is dropped.
+/// Tuples still in it are lost if the process stops before then.
+pub struct LsmTree {
+    bpm: Arc<RwLock<BufferPoolManager>>,
+    header_page_id: PageId,
+    /// Shared with the scans that started since it was last written to. Writers copy it first if
+    /// a scan still holds it.
+    memtable: Arc<Memtable>,
+    /// What the memtable's entries would take up in a run.
+    memtable_size: usize,
+    /// The runs of each level. Level 0 lists its runs from newest to oldest.
+    levels: Vec<Vec<Arc<Run>>>,
+    next_record_id: u64,
+    stats: HeapStats,
+    scan_lock: Arc<ScanLock>,
+}
+
+impl LsmTree {
+    /// Creates an empty tree, allocating its header page from the buffer pool.
+    pub fn new(bpm: Arc<RwLock<BufferPoolManager>>) -> Self {
+        let page_handle =
+            BufferPoolManager::create_page_handle(&bpm).expect("Failed to create page handle");
+        let mut header_page = LsmHeaderPageMut::from(page_handle);
+        header_page
+            .init(FIRST_RECORD_ID, &[])
+            .expect("Failed to initialize LSM header page");
+        let header_page_id = header_page.page_id();
+        drop(header_page);
+
+        Self {
+            bpm,
+            header_page_id,
+            memtable: Arc::default(),
+            memtable_size: 0,
+            levels: Vec::new(),
+            next_record_id: FIRST_RECORD_ID,
+            stats: HeapStats::default(),
+            scan_lock: Arc::default(),
+        }
+    }
+
+    /// Reopens the tree whose header page is `header_page_id`. Its statistics are counted again
+    /// by scanning it.
+    pub fn open(header_page_id: PageId, bpm: Arc<RwLock<BufferPoolManager>>) -> Result<Self> {
+        let (next_record_id, locations) = {
+            let handle =
+                BufferPoolManager::fetch_page_handle(&bpm, header_page_id, AccessType::Unknown)?;
+            let header_page = LsmHeaderPageRef::try_from(handle)?;
+            (header_page.next_record_id(), header_page.runs()?)
+        };
+        let mut levels: Vec<Vec<Arc<Run>>> = Vec::new();
+        for location in locations {
+            let level = location.level as usize;
+            if levels.len() <= level {
+                levels.resize_with(level + 1, Vec::new);
+            }
+            let run = Run::open(bpm.clone(), location.first_page_id)?;
+            levels[level].push(Arc::new(run));
+        }
+
+        let mut tree = Self {
+            bpm,
+            header_page_id,
+            memtable: Arc::default(),
+            memtable_size: 0,
+            levels,
+            next_record_id,
+            stats: HeapStats::default(),
+            scan_lock: Arc::default(),
+        };
+        let (mut tuple_cnt, mut tuple_bytes) = (0, 0);
+        for item in tree.scan()? {
+            let (_, tuple) = item?;
+            tuple_cnt += 1;
+            tuple_bytes += tuple.tuple_size() as u64;
+        }
+        tree.stats = HeapStats::new(tuple_cnt, tuple_bytes, 0);
+        Ok(tree)
+    }
+
+    pub fn header_page_id(&self) -> PageId {
+        self.header_page_id
+    }
+
+    /// The record id the next inserted tuple gets.
+    pub(crate) fn next_record_id(&self) -> u64 {
+        self.next_record_id
+    }
+
+    pub fn get_tuple(&self, rid: u64) -> Result<Tuple> {
+        match self.lookup(rid)? {
+            Some(data) => Ok(Tuple::new(data)),
+            None => errinput!("Tuple {} was deleted", rid),
+        }
+    }
+
+    /// Inserts a tuple, returning its record id. Tuples must fit in a run page.
+    pub fn insert_tuple(&mut self, tuple: &Tuple) -> Result<u64> {
+        check_tuple_size(tuple)?;
+        let rid = self.next_record_id;
+        self.next_record_id += 1;
+        self.put(rid, Some(tuple.data()))?;
+        self.stats.inserted(tuple.tuple_size());
+        Ok(rid)
+    }
+
+    pub fn update_tuple(&mut self, rid: u64, tuple: &Tuple) -> Result<()> {
+        check_tuple_size(tuple)?;
+        let Some(old) = self.lookup(rid)? else {
+            return errinput!("Cannot update deleted tuple {}", rid);
+        };
+        self.put(rid, Some(tuple.data()))?;
+        self.stats.resized(old.len(), tuple.tuple_size());
+        Ok(())
+    }
+
+    /// Deletes a tuple by writing a tombstone for it. Deleting it again does nothing.
+    pub fn delete_tuple(&mut self, rid: u64) -> Result<()> {
+        let Some(old) = self.lookup(rid)? else {
+            return Ok(());
+        };
+        self.put(rid, None)?;
+        self.stats.deleted(old.len());
+        Ok(())
+    }
+
+    /// Returns the newest version of the tuple with record id `rid`, or `None` if it was deleted.
+    /// Fails if no tuple ever had that record id.
+    fn lookup(&self, rid: u64) -> Result<Option<Bytes>> {
+        if !(FIRST_RECORD_ID..self.next_record_id).contains(&rid) {
+            return errinput!("Tuple {} does not exist", rid);
+        }
+        if let Some(value) = self.memtable.get(&rid) {
+            return Ok(value.clone());
+        }
+        for run in self.levels.iter().flatten() {
+            if let Some(value) = run.get(rid)? {
+                return Ok(value);
+            }
+        }
+        // The tuple's tombstone reached the lowest level and was dropped there.
+        Ok(None)
+    }
+
+    fn put(&mut self, rid: u64, value: Option<Bytes>) -> Result<()> {
+        self.memtable_size += run_entry_size(value.as_deref());
+        if let Some(old) = Arc::make_mut(&mut self.memtable).insert(rid, value) {
+            self.memtable_size -= run_entry_size(old.as_deref());
+        }
+        if self.memtable_size >= MEMTABLE_CAPACITY {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the memtable out as a new run of level 0, compacting levels that grew too large.
+    pub(crate) fn flush(&mut self) -> Result<()> {
+        if self.memtable.is_empty() {
+            return Ok(());
+        }
+        let lowest = self.levels.iter().all(Vec::is_empty);
+        let mut writer = RunWriter::new(&self.bpm);
+        for (&rid, value) in self.memtable.iter() {
+            if value.is_some() || !lowest {
+                writer.push(rid, value.as_deref())?;
+            }
+        }
+        if let Some(run) = writer.finish()? {
+            if self.levels.is_empty() {
+                self.levels.push(Vec::new());
+            }
+            self.levels[0].insert(0, Arc::new(run));
+        }
+        self.memtable = Arc::default();
+        self.memtable_size = 0;
+        self.compact()
+    }
+
+    /// Merges every level that holds too much into the level below it, and records the tree's
+    /// new shape in its header page.
+    fn compact(&mut self) -> Result<()> {
+        let mut replaced = Vec::new();
+        if self
+            .levels
+            .first()
+            .is_some_and(|runs| runs.len() > LEVEL0_RUN_LIMIT)
+        {
+            replaced.extend(self.merge_into(1)?);
+        }
+        let mut level = 1;
+        while level < self.levels.len() {
+            let page_cnt: usize = self.levels[level].iter().map(|run| run.page_count()).sum();
+            if page_cnt > LEVEL1_PAGE_LIMIT * LEVEL_SIZE_RATIO.pow(level as u32 - 1) {
+                replaced.extend(self.merge_into(level + 1)?);
+            }
+            level += 1;
+        }
+        self.save_header()?;
+        // Only give the merged runs' pages up once the header no longer refers to them.
+        for run in replaced {
+            run.obsolete.store(true, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Merges the runs of the level above `level` with those of `level`, into a single run of
+    /// `level`. Returns the runs that were merged.
+    fn merge_into(&mut self, level: usize) -> Result<Vec<Arc<Run>>> {
+        if self.levels.len() <= level {
+            self.levels.resize_with(level + 1, Vec::new);
+        }
+        let runs: Vec<Arc<Run>> = self.levels[level - 1]
+            .iter()
+            .chain(&self.levels[level])
+            .cloned()
+            .collect();
+        // Tombstones are only needed while an older version of their tuple may be further down.
+        let lowest = self.levels[level + 1..].iter().all(Vec::is_empty);
+
+        let mut entries = MergedEntries::new(None, runs.clone(), 0, None)?;
+        let mut writer = RunWriter::new(&self.bpm);
+        while let Some((rid, value)) = entries.next_entry()? {
+            if value.is_some() || !lowest {
+                writer.push(rid, value.as_deref())?;
+            }
+        }
+        let run = writer.finish()?;
+        self.levels[level - 1].clear();
+        self.levels[level] = run.into_iter().map(Arc::new).collect();
+        Ok(runs)
+    }
+
+    fn save_header(&self) -> Result<()> {
+        let runs: Vec<RunLocation> = self
+            .levels
+            .iter()
+            .enumerate()
+            .flat_map(|(level, runs)| {
+                runs.iter().map(move |run| RunLocation {
+                    level: level as u32,
+                    first_page_id: run.page_ids[0],
+                })
+            })
+            .collect();
+        let handle = BufferPoolManager::fetch_page_mut_handle(
+            &self.bpm,
+            self.header_page_id,
+            AccessType::Unknown,
+        )?;
+        LsmHeaderPageMut::from(handle).init(self.next_record_id, &runs)
+    }
+
+    /// Returns an iterator over every tuple of the tree.
+    pub(crate) fn scan(&self) -> Result<LsmIterator> {
+        self.scan_range(FIRST_RECORD_ID, None)
+    }
+
+    /// Returns an iterator over the tuples with record ids from `start` up to `stop`.
+    pub(crate) fn scan_range(&self, start: u64, stop: Option<u64>) -> Result<LsmIterator> {
+        let runs = self.levels.iter().flatten().cloned().collect();
+        let entries = MergedEntries::new(Some(self.memtable.clone()), runs, start, stop)?;
+        Ok(LsmIterator::new(entries, start))
+    }
+
+    /// Removes every tuple from the tree. The pages of its runs are freed once no scan reads them
+    /// anymore. Record ids aren't reused.
+    pub fn truncate(&mut self) -> Result<()> {
+        let runs: Vec<Arc<Run>> = self.levels.drain(..).flatten().collect();
+        self.memtable = Arc::default();
+        self.memtable_size = 0;
+        self.stats = HeapStats::default();
+        self.save_header()?;
+        for run in runs {
+            run.obsolete.store(true, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Forgets the tree's contents without touching its pages, once they have been deleted along
+    /// with the table, so that dropping the tree doesn't write to them.
+    pub(crate) fn discard(&mut self) {
+        self.memtable = Arc::default();
+        self.levels.clear();
+    }
+
+    /// Returns the ids of every page of the tree: its header page and the pages of its runs.
+    pub(crate) fn page_ids(&self) -> Vec<PageId> {
+        let mut page_ids = vec![self.header_page_id];
+        for run in self.levels.iter().flatten() {
+            page_ids.extend(&run.page_ids);
+        }
+        page_ids
+    }
+
+    /// Returns the tree's statistics. Its pages are those of its runs, and tombstones count as
+    /// deleted tuples until they are dropped.
+    pub(crate) fn stats(&self) -> TableStats {
+        let runs = self.levels.iter().flatten();
+        let page_cnt: usize = runs.clone().map(|run| run.page_count()).sum();
+        let tombstone_cnt = runs.map(|run| run.tombstone_cnt).sum::<u64>()
+            + self
+                .memtable
+                .values()
+                .filter(|value| value.is_none())
+                .count() as u64;
+        TableStats {
+            deleted_count: tombstone_cnt,
+            ..self.stats.to_table_stats(page_cnt as u32)
+        }
+    }
+}
+
+fn check_tuple_size(tuple: &Tuple) -> Result<()> {
+    if tuple.tuple_size() > MAX_RUN_VALUE_SIZE {
+        return errinput!(
+            "Tuple of {} bytes is too large for an LSM table",
+            tuple.tuple_size()
+        );
+    }
+    Ok(())
+}
+
+impl ScanLocked for LsmTree {
+    /// Scans read a snapshot of the tree and never register here, so writers never wait.
+    fn scan_lock(&self) -> &Arc<ScanLock> {
+        &self.scan_lock
+    }
+}
+
+impl Drop for LsmTree {
+    /// Flushes the memtable, so that the tree can be reopened with its tuples once the buffer
+    /// pool is flushed.
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serial_test::serial;
+
+    use super::*;
+    use crate::test_utils::get_bpm_arc_with_pool_size;
+
+    fn scan_all(tree: &LsmTree) -> Result<BTreeMap<u64, Bytes>> {
+        tree.scan()?
+            .map(|item| item.map(|(rid, tuple)| (rid, tuple.data())))
+            .collect()
+    }
+
+    #[test]
+    #[serial]
+    fn test_lsm_tree() -> Result<()> {
+        let bpm = get_bpm_arc_with_pool_size(10);
+        let mut tree = LsmTree::new(bpm.clone());
+        let mut expected = BTreeMap::new();
+
+        // Enough tuples to flush the memtable many times over and fill a few levels.
+        for i in 0..20000 {
+            let data = Bytes::from(vec![(i % 251) as u8; 40 + i % 80]);
+            let rid = tree.insert_tuple(&Tuple::new(data.clone()))?;
+            expected.insert(rid, data);
+        }
+        assert!(tree.levels.len() >= 3);
+        assert!(tree.levels[0].len() <= LEVEL0_RUN_LIMIT);
+        assert!(tree.levels[1..].iter().all(|runs| runs.len() <= 1));
+
+        // Writes to tuples that are already in runs shadow them, and reads see the newest version.
+        let rids: Vec<u64> = expected.keys().copied().collect();
+        for &rid in rids.iter().step_by(3) {
+            tree.delete_tuple(rid)?;
+            expected.remove(&rid);
+        }
+        for &rid in rids.iter().skip(1).step_by(3) {
+            let data = Bytes::from(format!("updated {}", rid));
+            tree.update_tuple(rid, &Tuple::new(data.clone()))?;
+            expected.insert(rid, data);
+        }
+        assert!(tree.get_tuple(rids[0]).is_err());
+        assert!(tree
+            .update_tuple(rids[0], &Tuple::new(Bytes::new()))
+            .is_err());
+        tree.delete_tuple(rids[0])?;
+        assert_eq!(expected[&rids[1]], tree.get_tuple(rids[1])?.data());
+        assert_eq!(expected[&rids[2]], tree.get_tuple(rids[2])?.data());
+        assert!(tree.get_tuple(tree.next_record_id()).is_err());
+        assert_eq!(expected, scan_all(&tree)?);
+
+        let stats = tree.stats();
+        assert_eq!(expected.len() as u64, stats.row_count);
+        let tuple_bytes: usize = expected.values().map(Bytes::len).sum();
+        assert_eq!(tuple_bytes as u64, stats.tuple_bytes);
+        let page_cnt: usize = tree
+            .levels
+            .iter()
+            .flatten()
+            .map(|run| run.page_count())
+            .sum();
+        assert_eq!(page_cnt as u64, stats.page_count);
+        assert!(stats.deleted_count <= rids.len() as u64 / 3 + 1);
+
+        // A scan reads the tree as it was when it started, even as runs are merged away.
+        let mut scan = tree.scan()?;
+        let first = scan.next().unwrap()?;
+        for i in 0..5000 {
+            tree.insert_tuple(&Tuple::new(Bytes::from(vec![1; 60 + i % 40])))?;
+        }
+        tree.delete_tuple(*expected.keys().last().unwrap())?;
+        let rest = scan.collect::<Result<Vec<_>>>()?;
+        assert_eq!(expected.len(), rest.len() + 1);
+        assert_eq!(*expected.keys().next().unwrap(), first.0);
+        assert_eq!(*expected.keys().last().unwrap(), rest.last().unwrap().0);
+
+        // Dropping the tree flushes its memtable, so it reopens with every tuple.
+        let expected = scan_all(&tree)?;
+        let header_page_id = tree.header_page_id();
+        drop(tree);
+        let mut tree = LsmTree::open(header_page_id, bpm.clone())?;
+        assert_eq!(expected, scan_all(&tree)?);
+        assert_eq!(expected.len() as u64, tree.stats().row_count);
+
+        // Truncating frees every run, and record ids aren't reused.
+        let next_record_id = tree.next_record_id();
+        tree.truncate()?;
+        assert!(scan_all(&tree)?.is_empty());
+        assert_eq!(TableStats::default(), tree.stats());
+        assert_eq!(
+            next_record_id,
+            tree.insert_tuple(&Tuple::new(Bytes::new()))?
+        );
+        let large = Tuple::new(Bytes::from(vec![0; MAX_RUN_VALUE_SIZE + 1]));
+        assert!(tree.insert_tuple(&large).is_err());
+
+        Ok(())
+    }
+}
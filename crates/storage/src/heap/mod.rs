@@ -1,2 +1,14 @@
+pub(crate) mod column_encoding;
+pub(crate) mod column_iterator;
+pub(crate) mod column_store;
+pub(crate) mod free_space_map;
+pub(crate) mod heap_stats;
+pub(crate) mod lsm_iterator;
+pub(crate) mod lsm_tree;
+pub(crate) mod pax_heap;
+pub(crate) mod pax_tuple_iterator;
+pub(crate) mod sampler;
+pub(crate) mod scan_filter;
+pub(crate) mod scan_lock;
 pub(crate) mod table_heap;
 pub(crate) mod table_tuple_iterator;
@@ -0,0 +1,429 @@
+use std::sync::{Arc, RwLock};
+
+use rustdb_catalog::catalog::TableStats;
+use rustdb_catalog::field::Field;
+use rustdb_catalog::schema::Schema;
+use rustdb_catalog::serde::Serde;
+use rustdb_catalog::tuple::Tuple;
+use rustdb_catalog::types::Type;
+use rustdb_error::{errinput, Error};
+
+use crate::buffer_pool::BufferPoolManager;
+use crate::heap::heap_stats::HeapStats;
+use crate::heap::scan_lock::{ScanLock, ScanLocked};
+use crate::page::heap_header_page::{HeapHeaderPageMut, HeapHeaderPageRef};
+use crate::page::pax_page::{PaxPage, PaxPageMut, PaxPageRef};
+use crate::page::INVALID_PAGE_ID;
+use crate::record_id::RecordId;
+use crate::replacer::replacer::AccessType;
+use crate::typedef::PageId;
+use crate::Result;
+
+/// A table heap whose pages use the PAX layout (see [`PaxPage`]), so that reading a few columns
+/// of a wide table only touches those columns' bytes.
+///
+/// Tuples go in and come out in the row-major [`Serde`] format, as with a regular table heap, so
+/// the heap needs the table's schema to split them into column values. Tuples are appended to
+/// the last page; deleted rows are not reused.
+pub struct PaxTableHeap {
+    table_name: String,
+    schema: Schema,
+    page_cnt: u32,
+    bpm: Arc<RwLock<BufferPoolManager>>,
+    header_page_id: PageId,
+    first_page_id: PageId,
+    last_page_id: PageId,
+    stats: HeapStats,
+    scan_lock: Arc<ScanLock>,
+}
+
+impl PaxTableHeap {
+    /// Create a new PAX table heap for tuples with the given schema. A new root page and a header
+    /// page are allocated from the buffer pool.
+    pub fn new(name: &str, schema: Schema, bpm: Arc<RwLock<BufferPoolManager>>) -> PaxTableHeap {
+        let page_handle =
+            BufferPoolManager::create_page_handle(&bpm).expect("Failed to create page handle");
+        let mut new_page = PaxPageMut::from(page_handle);
+        new_page.init(INVALID_PAGE_ID, &column_types(&schema));
+        let new_page_id = new_page.page_id();
+        drop(new_page);
+
+        // PAX heaps always append, so they have no free space map.
+        let page_handle =
+            BufferPoolManager::create_page_handle(&bpm).expect("Failed to create page handle");
+        let mut header_page = HeapHeaderPageMut::from(page_handle);
+        header_page
+            .init(name, new_page_id, INVALID_PAGE_ID)
+            .expect("Failed to initialize heap header page");
+        let header_page_id = header_page.page_id();
+        drop(header_page);
+
+        PaxTableHeap {
+            table_name: name.to_string(),
+            schema,
+            page_cnt: 1,
+            bpm,
+            header_page_id,
+            first_page_id: new_page_id,
+            last_page_id: new_page_id,
+            stats: HeapStats::default(),
+            scan_lock: Arc::default(),
+        }
+    }
+
+    /// Reopen the PAX table heap whose header page is `header_page_id`. Pages don't record column
+    /// names, so the caller has to provide the schema the heap was created with. As with
+    /// [`crate::heap::table_heap::TableHeap::open`], the heap's statistics are counted again.
+    pub fn open(
+        header_page_id: PageId,
+        schema: Schema,
+        bpm: Arc<RwLock<BufferPoolManager>>,
+    ) -> Result<PaxTableHeap> {
+        let header_page = HeapHeaderPageRef::try_from(BufferPoolManager::fetch_page_handle(
+            &bpm,
+            header_page_id,
+            AccessType::Unknown,
+        )?)?;
+        let table_name = header_page.table_name();
+        let page_cnt = header_page.page_count();
+        let first_page_id = header_page.first_page_id();
+        let last_page_id = header_page.last_page_id();
+        drop(header_page);
+
+        let mut table_heap = PaxTableHeap {
+            table_name,
+            schema,
+            page_cnt,
+            bpm,
+            header_page_id,
+            first_page_id,
+            last_page_id,
+            stats: HeapStats::default(),
+            scan_lock: Arc::default(),
+        };
+        table_heap.stats = table_heap.count_stats()?;
+        Ok(table_heap)
+    }
+
+    /// Counts the heap's tuples and deleted rows from its pages.
+    fn count_stats(&self) -> Result<HeapStats> {
+        let columns = self.all_columns();
+        let (mut tuple_cnt, mut tuple_bytes, mut deleted_cnt) = (0, 0, 0);
+        for page_id in self.chain_page_ids()? {
+            let page = self.fetch_page(page_id, AccessType::Unknown)?;
+            for slot_id in 0..page.tuple_count() {
+                let rid = RecordId::new(page_id, slot_id);
+                if page.is_deleted(&rid)? {
+                    deleted_cnt += 1;
+                } else {
+                    tuple_cnt += 1;
+                    let tuple = project_tuple(&page, &rid, &self.schema, &columns)?;
+                    tuple_bytes += tuple.tuple_size() as u64;
+                }
+            }
+        }
+        Ok(HeapStats::new(tuple_cnt, tuple_bytes, deleted_cnt))
+    }
+
+    /// Returns the heap's statistics. Deleted rows are never reclaimed, so they keep counting.
+    pub(crate) fn stats(&self) -> TableStats {
+        self.stats.to_table_stats(self.page_cnt)
+    }
+
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// Retrieve a tuple given its record id.
+    pub fn get_tuple(&self, rid: &RecordId) -> Result<Tuple> {
+        self.get_columns(rid, &self.all_columns())
+    }
+
+    fn all_columns(&self) -> Vec<usize> {
+        (0..self.schema.num_columns()).collect()
+    }
+
+    /// Retrieve only the given columns of a tuple, in the given order. The returned tuple is
+    /// serialized as if its schema only had those columns (see [`PaxTableHeap::project_schema`]).
+    pub fn get_columns(&self, rid: &RecordId, columns: &[usize]) -> Result<Tuple> {
+        let page = self.fetch_page(rid.page_id(), AccessType::Lookup)?;
+        if page.is_deleted(rid)? {
+            return errinput!("Tuple {} was deleted", rid.to_string());
+        }
+        project_tuple(&page, rid, &self.schema, columns)
+    }
+
+    /// Insert a tuple into the table, returning its record id.
+    pub fn insert_tuple(&mut self, tuple: &Tuple) -> Result<RecordId> {
+        let values = self.split_tuple(tuple)?;
+
+        let page_handle = BufferPoolManager::fetch_page_mut_handle(
+            &self.bpm,
+            self.last_page_id,
+            AccessType::Unknown,
+        )?;
+        match PaxPageMut::from(page_handle).insert_tuple(&values) {
+            Ok(rid) => {
+                self.stats.inserted(tuple.tuple_size());
+                return Ok(rid);
+            }
+            Err(Error::OutOfBounds) => {}
+            Err(e) => return Err(e),
+        }
+
+        // The last page is full, so start a new one.
+        let mut new_page = PaxPageMut::from(BufferPoolManager::create_page_handle(&self.bpm)?);
+        new_page.init(INVALID_PAGE_ID, &column_types(&self.schema));
+        let new_page_id = new_page.page_id();
+        let result = new_page.insert_tuple(&values);
+        drop(new_page);
+        let rid = match result {
+            Ok(rid) => rid,
+            Err(e) => {
+                self.bpm.write().unwrap().delete_page(new_page_id)?;
+                return match e {
+                    Error::OutOfBounds => errinput!("Tuple is too large for a PAX page"),
+                    e => Err(e),
+                };
+            }
+        };
+
+        let page_handle = BufferPoolManager::fetch_page_mut_handle(
+            &self.bpm,
+            self.last_page_id,
+            AccessType::Unknown,
+        )?;
+        PaxPageMut::from(page_handle).set_next_page_id(new_page_id);
+        self.last_page_id = new_page_id;
+        self.page_cnt += 1;
+        let page_handle = BufferPoolManager::fetch_page_mut_handle(
+            &self.bpm,
+            self.header_page_id,
+            AccessType::Unknown,
+        )?;
+        HeapHeaderPageMut::from(page_handle).set_last_page(self.last_page_id, self.page_cnt);
+
+        self.stats.inserted(tuple.tuple_size());
+        Ok(rid)
+    }
+
+    /// Overwrite the tuple at `rid`, keeping its record id. Fails with `Error::OutOfBounds` if its
+    /// page has no room left for the new variable-length values.
+    pub fn update_tuple(&self, rid: &RecordId, tuple: &Tuple) -> Result<()> {
+        let values = self.split_tuple(tuple)?;
+        let mut page = self.fetch_page_mut(rid.page_id())?;
+        if page.is_deleted(rid)? {
+            return errinput!("Cannot update deleted tuple {}", rid.to_string());
+        }
+        let old_size = project_tuple(&page, rid, &self.schema, &self.all_columns())?.tuple_size();
+        page.update_tuple(rid, &values)?;
+        self.stats.resized(old_size, tuple.tuple_size());
+        Ok(())
+    }
+
+    /// Delete a tuple from the table. Deleting it again does nothing.
+    pub fn delete_tuple(&self, rid: &RecordId) -> Result<()> {
+        let mut page = self.fetch_page_mut(rid.page_id())?;
+        if page.is_deleted(rid)? {
+            return Ok(());
+        }
+        let size = project_tuple(&page, rid, &self.schema, &self.all_columns())?.tuple_size();
+        page.set_deleted(rid, true)?;
+        self.stats.deleted(size);
+        self.stats.slot_deleted();
+        Ok(())
+    }
+
+    /// Returns the schema of the tuples [`PaxTableHeap::get_columns`] returns for `columns`.
+    pub fn project_schema(&self, columns: &[usize]) -> Result<Schema> {
+        let columns = columns
+            .iter()
+            .map(|&column| self.schema.column_at(column).cloned())
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Schema::new(&columns))
+    }
+
+    pub(crate) fn first_page_id(&self) -> PageId {
+        self.first_page_id
+    }
+
+    pub(crate) fn header_page_id(&self) -> PageId {
+        self.header_page_id
+    }
+
+    /// Returns the ids of every page the heap owns, starting with its header page.
+    pub(crate) fn page_ids(&self) -> Result<Vec<PageId>> {
+        let mut page_ids = vec![self.header_page_id];
+        page_ids.extend(self.chain_page_ids()?);
+        Ok(page_ids)
+    }
+
+    /// Returns the ids of the pages in the heap's page chain, in chain order.
+    pub(crate) fn chain_page_ids(&self) -> Result<Vec<PageId>> {
+        let mut page_ids = Vec::with_capacity(self.page_cnt as usize);
+        let mut page_id = self.first_page_id;
+        while page_id != INVALID_PAGE_ID {
+            page_ids.push(page_id);
+            page_id = self.fetch_page(page_id, AccessType::Unknown)?.next_page_id();
+        }
+        Ok(page_ids)
+    }
+
+    /// Remove every tuple from the heap, deleting all of its pages but the header page and
+    /// starting over with a new root page. Fails without changing the heap if any of its pages is
+    /// pinned.
+    pub fn truncate(&mut self) -> Result<()> {
+        let old_page_ids = self.chain_page_ids()?;
+        let first_page_id = {
+            let mut page = PaxPageMut::from(BufferPoolManager::create_page_handle(&self.bpm)?);
+            page.init(INVALID_PAGE_ID, &column_types(&self.schema));
+            page.page_id()
+        };
+
+        let result = self.bpm.write().unwrap().delete_pages(&old_page_ids);
+        if let Err(e) = result {
+            self.bpm.write().unwrap().delete_page(first_page_id)?;
+            return Err(e);
+        }
+
+        let page_handle = BufferPoolManager::fetch_page_mut_handle(
+            &self.bpm,
+            self.header_page_id,
+            AccessType::Unknown,
+        )?;
+        HeapHeaderPageMut::from(page_handle).init(
+            &self.table_name,
+            first_page_id,
+            INVALID_PAGE_ID,
+        )?;
+        self.page_cnt = 1;
+        self.first_page_id = first_page_id;
+        self.last_page_id = first_page_id;
+        self.stats = HeapStats::default();
+        Ok(())
+    }
+
+    /// Splits a serialized tuple into the bytes of each of its column values.
+    fn split_tuple(&self, tuple: &Tuple) -> Result<Vec<Vec<u8>>> {
+        if tuple.tuple_size() < self.schema.size() {
+            return errinput!(
+                "Tuple of {} bytes is too small for schema {}",
+                tuple.tuple_size(),
+                self.schema
+            );
+        }
+        Ok(Serde::deserialize(&tuple.data(), &self.schema)
+            .iter()
+            .map(Field::to_bytes)
+            .collect())
+    }
+
+    fn fetch_page(&self, page_id: PageId, access_type: AccessType) -> Result<PaxPageRef<'_>> {
+        let handle = BufferPoolManager::fetch_page_handle(&self.bpm, page_id, access_type)?;
+        PaxPageRef::try_from(handle)
+    }
+
+    fn fetch_page_mut(&self, page_id: PageId) -> Result<PaxPageMut<'_>> {
+        let handle =
+            BufferPoolManager::fetch_page_mut_handle(&self.bpm, page_id, AccessType::Unknown)?;
+        Ok(PaxPageMut::from(handle))
+    }
+}
+
+impl ScanLocked for PaxTableHeap {
+    fn scan_lock(&self) -> &Arc<ScanLock> {
+        &self.scan_lock
+    }
+}
+
+fn column_types(schema: &Schema) -> Vec<Type> {
+    schema.columns().iter().map(|c| c.field_type()).collect()
+}
+
+/// Reads the given columns of the tuple at `rid` out of their minipages and serializes them.
+pub(crate) fn project_tuple<T: std::ops::Deref<Target = crate::frame::PageFrame>>(
+    page: &PaxPage<T>,
+    rid: &RecordId,
+    schema: &Schema,
+    columns: &[usize],
+) -> Result<Tuple> {
+    let fields = columns
+        .iter()
+        .map(|&column| {
+            let field_type = schema.column_at(column)?.field_type();
+            Ok(Field::from_bytes(page.get_value(rid, column)?, field_type))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Tuple::new(Serde::serialize(&fields).into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use rustdb_catalog::column::Column;
+    use serial_test::serial;
+
+    use super::*;
+    use crate::test_utils::get_bpm_arc_with_pool_size;
+
+    fn schema() -> Schema {
+        Schema::new(&[
+            Column::new("id".to_string(), Type::Integer),
+            Column::new("name".to_string(), Type::Varchar),
+            Column::new("score".to_string(), Type::Float),
+        ])
+    }
+
+    fn row(i: i32) -> Vec<Field> {
+        vec![
+            Field::Integer(i),
+            Field::Varchar(format!("name {}", i)),
+            Field::Float(i as f64 / 2.0),
+        ]
+    }
+
+    #[test]
+    #[serial]
+    fn test_pax_table_heap() -> Result<()> {
+        let bpm = get_bpm_arc_with_pool_size(10);
+        let mut table_heap = PaxTableHeap::new("table", schema(), bpm.clone());
+
+        let mut rids = Vec::new();
+        for i in 0..500 {
+            let tuple = Tuple::new(Serde::serialize(&row(i)).into());
+            rids.push(table_heap.insert_tuple(&tuple)?);
+        }
+        assert!(table_heap.page_cnt > 1);
+
+        // Whole tuples come back in the same format they went in.
+        let tuple = table_heap.get_tuple(&rids[321])?;
+        assert_eq!(row(321), Serde::deserialize(&tuple.data(), &schema()));
+
+        // A projection only returns the requested columns, in the requested order.
+        let columns = [2, 0];
+        let projected_schema = table_heap.project_schema(&columns)?;
+        let tuple = table_heap.get_columns(&rids[321], &columns)?;
+        assert_eq!(
+            vec![Field::Float(160.5), Field::Integer(321)],
+            Serde::deserialize(&tuple.data(), &projected_schema)
+        );
+        assert!(table_heap.get_columns(&rids[0], &[3]).is_err());
+
+        let tuple = Tuple::new(Serde::serialize(&row(-1)).into());
+        table_heap.update_tuple(&rids[7], &tuple)?;
+        assert_eq!(
+            row(-1),
+            Serde::deserialize(&table_heap.get_tuple(&rids[7])?.data(), &schema())
+        );
+
+        table_heap.delete_tuple(&rids[8])?;
+        assert!(table_heap.get_tuple(&rids[8]).is_err());
+        assert!(table_heap.update_tuple(&rids[8], &tuple).is_err());
+
+        // Tuples that don't match the schema are rejected.
+        assert!(table_heap
+            .insert_tuple(&Tuple::new(vec![1, 2].into()))
+            .is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+use std::sync::{Arc, RwLock};
+
+use rustdb_catalog::field::Field;
+use rustdb_catalog::predicate::Predicate;
+use rustdb_catalog::schema::Schema;
+use rustdb_catalog::tuple::Tuple;
+
+use crate::buffer_pool::BufferPoolManager;
+use crate::heap::pax_heap::{project_tuple, PaxTableHeap};
+use crate::heap::sampler::Sampler;
+use crate::heap::scan_lock::{ScanGuard, ScanLocked};
+use crate::page::pax_page::PaxPageRef;
+use crate::page::INVALID_PAGE_ID;
+use crate::record_id::RecordId;
+use crate::replacer::replacer::AccessType;
+use crate::typedef::PageId;
+use crate::Result;
+
+/// An iterator over the non-deleted tuples of a PAX table heap that only reads the requested
+/// columns. Emitted tuples are serialized with the schema returned by
+/// [`PaxTableHeap::project_schema`] for those columns.
+pub struct PaxTupleIterator {
+    bpm: Arc<RwLock<BufferPoolManager>>,
+    schema: Schema,
+    columns: Vec<usize>,
+    current_page_id: PageId,
+    current_slot: u32,
+    /// The page the scan stops before, or `INVALID_PAGE_ID` to scan to the end of the chain.
+    stop_page_id: PageId,
+    /// Only tuples satisfying this predicate are emitted, if there is one.
+    predicate: Option<Predicate>,
+    /// Which pages to read and which tuples to emit, for a sampling scan.
+    sampler: Option<Sampler>,
+    /// Keeps writers out of the heap while a stable scan runs.
+    guard: Option<ScanGuard>,
+}
+
+impl PaxTupleIterator {
+    /// Creates an iterator emitting every column of each tuple.
+    pub fn new(bpm: Arc<RwLock<BufferPoolManager>>, table_heap: Arc<RwLock<PaxTableHeap>>) -> Self {
+        let columns = (0..table_heap.read().unwrap().schema().num_columns()).collect();
+        Self::with_columns(bpm, table_heap, columns).unwrap()
+    }
+
+    /// Creates an iterator emitting only `columns` of each tuple, in that order. Fails if any of
+    /// them isn't a column of the table.
+    pub fn with_columns(
+        bpm: Arc<RwLock<BufferPoolManager>>,
+        table_heap: Arc<RwLock<PaxTableHeap>>,
+        columns: Vec<usize>,
+    ) -> Result<Self> {
+        let first_page_id = table_heap.read().unwrap().first_page_id();
+        Self::with_range(bpm, table_heap, columns, first_page_id, INVALID_PAGE_ID)
+    }
+
+    /// Like [`PaxTupleIterator::with_columns`], but only scans the pages of the heap's page chain
+    /// from `start_page_id` up to, but excluding, `stop_page_id`. An `INVALID_PAGE_ID` start
+    /// makes the iterator empty, while an `INVALID_PAGE_ID` stop scans to the end of the chain.
+    pub(crate) fn with_range(
+        bpm: Arc<RwLock<BufferPoolManager>>,
+        table_heap: Arc<RwLock<PaxTableHeap>>,
+        columns: Vec<usize>,
+        start_page_id: PageId,
+        stop_page_id: PageId,
+    ) -> Result<Self> {
+        let table_heap = table_heap.read().unwrap();
+        Self::from_heap(bpm, &table_heap, columns, start_page_id, stop_page_id)
+    }
+
+    fn from_heap(
+        bpm: Arc<RwLock<BufferPoolManager>>,
+        table_heap: &PaxTableHeap,
+        columns: Vec<usize>,
+        start_page_id: PageId,
+        stop_page_id: PageId,
+    ) -> Result<Self> {
+        table_heap.project_schema(&columns)?;
+        Ok(Self {
+            bpm,
+            schema: table_heap.schema().clone(),
+            columns,
+            current_page_id: start_page_id,
+            current_slot: 0,
+            stop_page_id,
+            predicate: None,
+            sampler: None,
+            guard: None,
+        })
+    }
+
+    /// Creates an iterator emitting every column of each tuple, which blocks the storage
+    /// engine's writes to the table heap until it is exhausted or dropped (see
+    /// [`crate::heap::table_tuple_iterator::TableTupleIterator::stable`]).
+    pub fn stable(
+        bpm: Arc<RwLock<BufferPoolManager>>,
+        table_heap: Arc<RwLock<PaxTableHeap>>,
+    ) -> Self {
+        let table_heap = table_heap.read().unwrap();
+        let columns = (0..table_heap.schema().num_columns()).collect();
+        let first_page_id = table_heap.first_page_id();
+        let mut iter =
+            Self::from_heap(bpm, &table_heap, columns, first_page_id, INVALID_PAGE_ID).unwrap();
+        iter.guard = Some(table_heap.scan_lock().acquire());
+        iter
+    }
+
+    /// Makes the iterator skip the slots of its first page before `slot_id`.
+    pub(crate) fn starting_at_slot(mut self, slot_id: u32) -> Self {
+        self.current_slot = slot_id;
+        self
+    }
+
+    /// Returns the record id of the next slot the iterator will look at. Its page is
+    /// `INVALID_PAGE_ID` once the iterator is exhausted.
+    pub(crate) fn position(&self) -> RecordId {
+        RecordId::new(self.current_page_id, self.current_slot)
+    }
+
+    /// Makes the iterator only emit tuples satisfying `predicate`, which is evaluated against the
+    /// minipages of the columns it refers to. Fails if it doesn't fit the table's schema.
+    pub(crate) fn with_predicate(mut self, predicate: Predicate) -> Result<Self> {
+        predicate.validate(&self.schema)?;
+        self.predicate = Some(predicate);
+        Ok(self)
+    }
+
+    /// Makes the iterator only emit a random sample of the tuples it would otherwise emit.
+    pub(crate) fn with_sampler(mut self, sampler: Sampler) -> Self {
+        self.sampler = Some(sampler);
+        self
+    }
+}
+
+impl Iterator for PaxTupleIterator {
+    type Item = Result<(rustdb_catalog::schema::RecordId, Tuple)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.next_tuple();
+        if item.is_none() {
+            self.guard.take();
+        }
+        item
+    }
+}
+
+impl PaxTupleIterator {
+    fn next_tuple(&mut self) -> Option<Result<(rustdb_catalog::schema::RecordId, Tuple)>> {
+        while self.current_page_id != INVALID_PAGE_ID {
+            let page = match BufferPoolManager::fetch_page_handle(
+                &self.bpm,
+                self.current_page_id,
+                AccessType::Scan,
+            )
+            .and_then(PaxPageRef::try_from)
+            {
+                Ok(page) => page,
+                Err(e) => return Some(Err(e)),
+            };
+
+            // As in `TableTupleIterator`, pages left out of the sample are skipped on entry.
+            if self.current_slot == 0 && !self.sampler.as_mut().is_none_or(Sampler::sample_page) {
+                self.current_slot = page.tuple_count();
+            }
+
+            while self.current_slot < page.tuple_count() {
+                let rid = RecordId::new(self.current_page_id, self.current_slot);
+                self.current_slot += 1;
+                match page.is_deleted(&rid) {
+                    Ok(true) => continue,
+                    Ok(false) => {}
+                    Err(e) => return Some(Err(e)),
+                }
+                if !self.sampler.as_mut().is_none_or(Sampler::sample_tuple) {
+                    continue;
+                }
+                if let Some(predicate) = &self.predicate {
+                    let mut column = |i: usize| {
+                        let field_type = self.schema.column_at(i)?.field_type();
+                        Ok(Field::from_bytes(page.get_value(&rid, i)?, field_type))
+                    };
+                    match predicate.evaluate(&mut column) {
+                        Ok(true) => {}
+                        Ok(false) => continue,
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+                let tuple = project_tuple(&page, &rid, &self.schema, &self.columns);
+                return Some(tuple.map(|tuple| (rid.into(), tuple)));
+            }
+
+            self.current_page_id = page.next_page_id();
+            if self.current_page_id == self.stop_page_id {
+                self.current_page_id = INVALID_PAGE_ID;
+            }
+            self.current_slot = 0;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustdb_catalog::column::Column;
+    use rustdb_catalog::serde::Serde;
+    use rustdb_catalog::types::Type;
+    use serial_test::serial;
+
+    use super::*;
+    use crate::test_utils::get_bpm_arc_with_pool_size;
+
+    #[test]
+    #[serial]
+    fn test_pax_iterator_projection() -> Result<()> {
+        let bpm = get_bpm_arc_with_pool_size(10);
+
+        let schema = Schema::new(&[
+            Column::new("a".to_string(), Type::Integer),
+            Column::new("b".to_string(), Type::Boolean),
+            Column::new("c".to_string(), Type::Integer),
+        ]);
+        let mut table_heap = PaxTableHeap::new("table", schema, bpm.clone());
+        let mut rids = Vec::new();
+        for i in 0..1000 {
+            let fields = [
+                Field::Integer(i),
+                Field::Boolean(i % 2 == 0),
+                Field::Integer(-i),
+            ];
+            rids.push(table_heap.insert_tuple(&Tuple::new(Serde::serialize(&fields).into()))?);
+        }
+        table_heap.delete_tuple(&rids[10])?;
+        let table_heap = Arc::new(RwLock::new(table_heap));
+
+        let projected_schema = table_heap.read().unwrap().project_schema(&[2])?;
+        let iter = PaxTupleIterator::with_columns(bpm.clone(), table_heap.clone(), vec![2])?;
+        let values: Vec<Field> = iter
+            .map(|item| Ok(Serde::deserialize(&item?.1.data(), &projected_schema).remove(0)))
+            .collect::<Result<_>>()?;
+        let expected: Vec<Field> = (0..1000)
+            .filter(|&i| i != 10)
+            .map(|i| Field::Integer(-i))
+            .collect();
+        assert_eq!(expected, values);
+
+        assert_eq!(
+            999,
+            PaxTupleIterator::new(bpm.clone(), table_heap.clone()).count()
+        );
+        assert!(PaxTupleIterator::with_columns(bpm.clone(), table_heap, vec![0, 3]).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rustdb_catalog::catalog::SampleMethod;
+
+use crate::Result;
+
+/// Decides which pages a sampling scan reads and which tuples it emits. Its random number
+/// generator is seeded, so a scan over the same table with the same seed picks the same sample.
+pub(crate) struct Sampler {
+    method: SampleMethod,
+    probability: f64,
+    rng: StdRng,
+}
+
+impl Sampler {
+    /// Fails if the probability of `method` isn't between 0 and 1.
+    pub(crate) fn new(method: SampleMethod, seed: u64) -> Result<Self> {
+        Ok(Self {
+            method,
+            probability: method.probability()?,
+            rng: StdRng::seed_from_u64(seed),
+        })
+    }
+
+    /// Whether the scan should read the page it is moving to. Always true for row-level sampling.
+    pub(crate) fn sample_page(&mut self) -> bool {
+        match self.method {
+            SampleMethod::System(_) => self.rng.random_bool(self.probability),
+            SampleMethod::Bernoulli(_) => true,
+        }
+    }
+
+    /// Whether the scan should emit the live tuple it is looking at. Always true for page-level
+    /// sampling, as the tuples of a page that was read are all part of the sample.
+    pub(crate) fn sample_tuple(&mut self) -> bool {
+        match self.method {
+            SampleMethod::System(_) => true,
+            SampleMethod::Bernoulli(_) => self.rng.random_bool(self.probability),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use rustdb_catalog::field::Field;
+use rustdb_catalog::predicate::Predicate;
+use rustdb_catalog::schema::Schema;
+use rustdb_catalog::serde::Serde;
+use rustdb_catalog::tuple::Tuple;
+use rustdb_error::errdata;
+
+use crate::Result;
+
+/// The predicate and projection of a filtered scan over row-major tuples. Both are applied to a
+/// tuple's serialized bytes, decoding only the fields they need, so that a scan can check tuples
+/// while they are still in their page and only copy out the fields of those that match.
+pub(crate) struct ScanFilter {
+    schema: Schema,
+    columns: Vec<usize>,
+    predicate: Option<Predicate>,
+}
+
+impl ScanFilter {
+    /// Creates a filter emitting `columns` of the tuples with the given schema that satisfy
+    /// `predicate`. Fails if either refers to columns the schema doesn't have.
+    pub(crate) fn new(
+        schema: Schema,
+        columns: Vec<usize>,
+        predicate: Option<Predicate>,
+    ) -> Result<Self> {
+        if let Some(predicate) = &predicate {
+            predicate.validate(&schema)?;
+        }
+        for &column in &columns {
+            schema.column_at(column)?;
+        }
+        Ok(Self {
+            schema,
+            columns,
+            predicate,
+        })
+    }
+
+    /// Returns the projected tuple if the tuple serialized in `bytes` satisfies the predicate.
+    pub(crate) fn apply(&self, bytes: &[u8]) -> Result<Option<Tuple>> {
+        if bytes.len() < self.schema.size() {
+            return errdata!(
+                "Tuple of {} bytes is too small for schema {}",
+                bytes.len(),
+                self.schema
+            );
+        }
+        if let Some(predicate) = &self.predicate {
+            let mut column = |i| Serde::deserialize_column(bytes, &self.schema, i);
+            if !predicate.evaluate(&mut column)? {
+                return Ok(None);
+            }
+        }
+        let fields: Vec<Field> = self
+            .columns
+            .iter()
+            .map(|&i| Serde::deserialize_column(bytes, &self.schema, i))
+            .collect::<Result<_>>()?;
+        Ok(Some(Tuple::new(Serde::serialize(&fields).into())))
+    }
+}
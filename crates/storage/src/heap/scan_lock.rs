@@ -0,0 +1,58 @@
+use std::sync::{Arc, Condvar, Mutex, RwLock, RwLockWriteGuard};
+
+/// Lets scans keep writers out of a table heap for as long as they run.
+///
+/// A heap's `RwLock` can't do this on its own, as its read guards borrow the lock and so can't be
+/// stored in an iterator next to the `Arc` that owns it. Scans instead register themselves here
+/// while holding the heap's read lock, and get back a [`ScanGuard`] they own. Writers take the
+/// heap's write lock, which keeps new scans from registering, and then wait for the registered
+/// ones to drop their guards (see [`lock_for_write`]).
+#[derive(Default)]
+pub(crate) struct ScanLock {
+    scans: Mutex<usize>,
+    released: Condvar,
+}
+
+impl ScanLock {
+    /// Registers a scan. The caller must hold the heap's read lock.
+    pub(crate) fn acquire(self: &Arc<Self>) -> ScanGuard {
+        *self.scans.lock().unwrap() += 1;
+        ScanGuard(self.clone())
+    }
+
+    /// Blocks until no scan is registered.
+    fn wait(&self) {
+        let mut scans = self.scans.lock().unwrap();
+        while *scans > 0 {
+            scans = self.released.wait(scans).unwrap();
+        }
+    }
+}
+
+/// Keeps writers out of a table heap until it is dropped.
+pub(crate) struct ScanGuard(Arc<ScanLock>);
+
+impl Drop for ScanGuard {
+    fn drop(&mut self) {
+        let mut scans = self.0.scans.lock().unwrap();
+        *scans -= 1;
+        if *scans == 0 {
+            self.0.released.notify_all();
+        }
+    }
+}
+
+/// A table heap whose writers respect stable scans.
+pub(crate) trait ScanLocked {
+    fn scan_lock(&self) -> &Arc<ScanLock>;
+}
+
+/// Write-locks a table heap, then waits for the stable scans already running on it to finish.
+///
+/// A thread must not call this while it holds a [`ScanGuard`] on the same heap, as it would wait
+/// for itself forever.
+pub(crate) fn lock_for_write<T: ScanLocked>(table_heap: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    let table_heap = table_heap.write().unwrap();
+    table_heap.scan_lock().wait();
+    table_heap
+}
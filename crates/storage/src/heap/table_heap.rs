@@ -1,119 +1,845 @@
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
+use rustdb_catalog::catalog::TableStats;
 use rustdb_catalog::tuple::Tuple;
-use rustdb_error::Error;
+use rustdb_error::{errdata, errinput, Error};
 
 use crate::page::INVALID_PAGE_ID;
 use crate::{
     buffer_pool::BufferPoolManager,
-    page::table_page::{TablePageMut, TablePageRef, TupleMetadata},
+    heap::free_space_map::FreeSpaceMap,
+    heap::heap_stats::HeapStats,
+    heap::scan_lock::{ScanLock, ScanLocked},
+    page::heap_header_page::{HeapHeaderPageMut, HeapHeaderPageRef},
+    page::overflow_page::{
+        free_overflow_chain, overflow_chain_page_ids, read_overflow_chain, write_overflow_chain,
+        OverflowPointer,
+    },
+    page::table_page::{
+        decode_forward, TablePageMut, TablePageRef, TupleMetadata, FORWARD_POINTER_SIZE,
+        MAX_INLINE_TUPLE_SIZE, TABLE_PAGE_HEADER_SIZE, TUPLE_INFO_SIZE,
+    },
+    page::PAGE_SIZE,
     record_id::RecordId,
+    replacer::replacer::AccessType,
+    storage::{VacuumOptions, VacuumReport},
     typedef::PageId,
     Result,
 };
 
+/// Number of tuples `insert_tuples` prepares at a time before placing them in pages.
+const BULK_INSERT_CHUNK_SIZE: usize = 256;
+/// Most pages `insert_tuples` allocates at once.
+const BULK_INSERT_PAGE_BATCH: usize = 16;
+
+/// Returns the size of the tuple a slot that isn't forwarded stands for, given its contents.
+fn stored_tuple_size(metadata: &TupleMetadata, stored: &Tuple) -> usize {
+    if metadata.is_overflow() {
+        OverflowPointer::from_tuple(stored).size as usize
+    } else {
+        stored.tuple_size()
+    }
+}
+
+/// Returns the number of bytes a prepared tuple takes in a table page, including its slot.
+fn slot_size(tuple: &Tuple) -> usize {
+    tuple.tuple_size().max(FORWARD_POINTER_SIZE) + TUPLE_INFO_SIZE
+}
+
 pub struct TableHeap {
     table_name: String,
     page_cnt: u32,
     bpm: Arc<RwLock<BufferPoolManager>>,
+    header_page_id: PageId,
     first_page_id: PageId,
     last_page_id: PageId,
+    free_space_map: FreeSpaceMap,
+    stats: HeapStats,
+    scan_lock: Arc<ScanLock>,
 }
 
 impl TableHeap {
-    /// Create a new table heap. A new root page is allocated from the buffer pool.
+    /// Create a new table heap. A new root page is allocated from the buffer pool, along with a
+    /// header page recording where the heap's pages are (see [`TableHeap::open`]).
     pub fn new(name: &str, bpm: Arc<RwLock<BufferPoolManager>>) -> TableHeap {
 
         let bpm_clone = bpm.clone();
         let page_handle = BufferPoolManager::create_page_handle(&bpm_clone)
             .expect("Failed to create page handle");
-        let new_page = TablePageMut::from(page_handle);
+        let mut new_page = TablePageMut::from(page_handle);
+        new_page.init_header(INVALID_PAGE_ID);
         let new_page_id = new_page.page_id();
+        let available_space = new_page.available_space();
+        drop(new_page);
+
+        let free_space_map =
+            FreeSpaceMap::new(bpm.clone()).expect("Failed to create free space map");
+        free_space_map
+            .update(new_page_id, available_space)
+            .expect("Failed to update free space map");
+
+        let mut header_page = HeapHeaderPageMut::from(
+            BufferPoolManager::create_page_handle(&bpm_clone)
+                .expect("Failed to create page handle"),
+        );
+        header_page
+            .init(name, new_page_id, free_space_map.first_page_id())
+            .expect("Failed to initialize heap header page");
+        let header_page_id = header_page.page_id();
+        drop(header_page);
         
         Self {
             table_name: name.parse().unwrap(),
             bpm,
-            page_cnt: 0,
+            page_cnt: 1,
+            header_page_id,
             first_page_id: new_page_id,
-            last_page_id: new_page_id
+            last_page_id: new_page_id,
+            free_space_map,
+            stats: HeapStats::default(),
+            scan_lock: Arc::default(),
+        }
+
+    }
+
+    /// Reopen the table heap whose header page is `header_page_id`, e.g. after a restart. Its
+    /// statistics aren't persisted, so every page of the heap is read to count them again.
+    pub fn open(header_page_id: PageId, bpm: Arc<RwLock<BufferPoolManager>>) -> Result<TableHeap> {
+        let header_page = HeapHeaderPageRef::try_from(BufferPoolManager::fetch_page_handle(
+            &bpm,
+            header_page_id,
+            AccessType::Unknown,
+        )?)?;
+        let table_name = header_page.table_name();
+        let page_cnt = header_page.page_count();
+        let first_page_id = header_page.first_page_id();
+        let last_page_id = header_page.last_page_id();
+        let free_space_map = FreeSpaceMap::open(bpm.clone(), header_page.free_space_map_page_id())?;
+        drop(header_page);
+
+        let mut table_heap = Self {
+            table_name,
+            bpm,
+            page_cnt,
+            header_page_id,
+            first_page_id,
+            last_page_id,
+            free_space_map,
+            stats: HeapStats::default(),
+            scan_lock: Arc::default(),
+        };
+        table_heap.stats = table_heap.count_stats()?;
+        Ok(table_heap)
+    }
+
+    /// Counts the heap's tuples and deleted slots from its pages.
+    fn count_stats(&self) -> Result<HeapStats> {
+        let (mut tuple_cnt, mut tuple_bytes, mut deleted_cnt) = (0, 0, 0);
+        for page_id in self.chain_page_ids()? {
+            let page = TablePageRef::try_from(BufferPoolManager::fetch_page_handle(
+                &self.bpm,
+                page_id,
+                AccessType::Unknown,
+            )?)?;
+            deleted_cnt += page.deleted_count() as u64;
+            for slot_id in 0..page.tuple_count() {
+                let (metadata, stored) = page.get_tuple(&RecordId::new(page_id, slot_id))?;
+                if metadata.is_deleted() {
+                    continue;
+                }
+                // A moved tuple counts as a tuple in its home slot, but its size is known here.
+                if !metadata.is_relocated() {
+                    tuple_cnt += 1;
+                }
+                if !metadata.is_forwarded() {
+                    tuple_bytes += stored_tuple_size(&metadata, &stored) as u64;
+                }
+            }
         }
+        Ok(HeapStats::new(tuple_cnt, tuple_bytes, deleted_cnt))
+    }
 
+    /// Returns the heap's statistics. Its page count is that of its page chain, not counting its
+    /// header page, free space map and overflow pages.
+    pub(crate) fn stats(&self) -> TableStats {
+        self.stats.to_table_stats(self.page_cnt)
+    }
+
+    /// Persist the end of the page chain to the header page, after pages were appended.
+    fn save_last_page(&self) -> Result<()> {
+        let page_handle = BufferPoolManager::fetch_page_mut_handle(
+            &self.bpm,
+            self.header_page_id,
+            AccessType::Unknown,
+        )?;
+        HeapHeaderPageMut::from(page_handle).set_last_page(self.last_page_id, self.page_cnt);
+        Ok(())
     }
 
     /// Retrieve a tuple given its record id.
     pub fn get_tuple(&self, rid: &RecordId) -> Result<(TupleMetadata, Tuple)> {
         
         
-        let page_frame_handle = BufferPoolManager::fetch_page_handle(&self.bpm, rid.page_id())?;
-        let table_page = TablePageRef::from(page_frame_handle);
+        let (mut metadata, tuple) = {
+            let page_frame_handle =
+                BufferPoolManager::fetch_page_handle(&self.bpm, rid.page_id(), AccessType::Lookup)?;
+            TablePageRef::try_from(page_frame_handle)?.get_tuple(rid)?
+        };
 
-        table_page.get_tuple(rid)
-        
+        // The slot may only point to where the tuple actually lives.
+        let tuple = resolve_tuple(&self.bpm, metadata, tuple, AccessType::Lookup)?;
+        metadata.set_forwarded(false);
+        metadata.set_overflow(false);
+        Ok((metadata, tuple))
     }
 
     /// Delete a tuple given its record id, returning the deleted tuple (and its metadata).
     pub fn delete_tuple(&self, rid: &RecordId) -> Result<(TupleMetadata, Tuple)> {
 
-        let (mut tuple_metadata, tuple) = self.get_tuple(rid)?;
+        let (tuple_metadata, tuple) = self.get_tuple(rid)?;
         let original_tuple_metadata = tuple_metadata.clone();
-        tuple_metadata.set_deleted(true);
 
-        let page_frame_handle = BufferPoolManager::fetch_page_mut_handle(&self.bpm, rid.page_id())?;
-        let mut table_page = TablePageMut::from(page_frame_handle);
-        let res = table_page.update_tuple_metadata(rid, tuple_metadata);
+        let target = self.mark_deleted(rid)?;
+        if let Some(target) = target {
+            self.mark_deleted(&target)?;
+        }
+        if !tuple_metadata.is_deleted() {
+            self.stats.deleted(tuple.tuple_size());
+        }
         
         Ok((original_tuple_metadata, tuple))
         
     }
 
+    /// Marks the tuple in slot `rid` as deleted, returning where the slot forwarded to, if it did.
+    /// Does nothing if the slot was already deleted.
+    fn mark_deleted(&self, rid: &RecordId) -> Result<Option<RecordId>> {
+        let (metadata, stub) = {
+            let page_frame_handle = BufferPoolManager::fetch_page_mut_handle(
+                &self.bpm,
+                rid.page_id(),
+                AccessType::Lookup,
+            )?;
+            let mut table_page = TablePageMut::from(page_frame_handle);
+            let slot = table_page.get_tuple(rid)?;
+            // The pointers of a deleted slot are stale: what they lead to may have been reused.
+            if slot.0.is_deleted() {
+                return Ok(None);
+            }
+            self.stats.track(&mut table_page, |page| {
+                page.update_tuple_metadata(rid, TupleMetadata::new(true))
+            })?;
+            self.free_space_map
+                .update(rid.page_id(), table_page.available_space())?;
+            slot
+        };
+
+        self.free_overflow(&metadata, &stub)?;
+        Ok(metadata.is_forwarded().then(|| decode_forward(&stub)))
+    }
+
+    /// Frees the overflow pages of a live slot that stored its tuple there.
+    fn free_overflow(&self, metadata: &TupleMetadata, stub: &Tuple) -> Result<()> {
+        if metadata.is_overflow() && !metadata.is_deleted() {
+            free_overflow_chain(&self.bpm, OverflowPointer::from_tuple(stub))?;
+        }
+        Ok(())
+    }
+
+    /// Returns what to store in a slot for `tuple`: the tuple itself, or a pointer to the overflow
+    /// pages it was spilled to if it is too large for a table page.
+    fn prepare_tuple(&self, tuple: &Tuple) -> Result<(TupleMetadata, Tuple)> {
+        let mut metadata = TupleMetadata::new(false);
+        if tuple.tuple_size() <= MAX_INLINE_TUPLE_SIZE {
+            return Ok((metadata, Tuple::new(tuple.data())));
+        }
+        let pointer = write_overflow_chain(&self.bpm, &tuple.data())?;
+        metadata.set_overflow(true);
+        Ok((metadata, pointer.to_tuple()))
+    }
+
+    /// Replace the tuple at `rid` with `tuple`, keeping its record id.
+    ///
+    /// If the new tuple no longer fits in its page, it is moved to another page and its slot is
+    /// turned into a forwarding pointer. There is never more than one hop: when a forwarded tuple
+    /// moves again, the original slot is pointed at the new location. Tuples too large for any
+    /// page are spilled to overflow pages and always stay in their home slot.
+    pub fn update_tuple(&mut self, rid: &RecordId, tuple: &Tuple) -> Result<()> {
+        let old_size = self.tuple_size(rid)?;
+        self.replace_tuple(rid, tuple)?;
+        self.stats.resized(old_size, tuple.tuple_size());
+        Ok(())
+    }
+
+    /// Returns the size of the tuple at `rid`, without reading it from overflow pages.
+    fn tuple_size(&self, rid: &RecordId) -> Result<usize> {
+        let page_frame_handle =
+            BufferPoolManager::fetch_page_handle(&self.bpm, rid.page_id(), AccessType::Lookup)?;
+        let (metadata, stored) = TablePageRef::try_from(page_frame_handle)?.get_tuple(rid)?;
+        if !metadata.is_forwarded() || metadata.is_deleted() {
+            return Ok(stored_tuple_size(&metadata, &stored));
+        }
+        let target = decode_forward(&stored);
+        let page_frame_handle =
+            BufferPoolManager::fetch_page_handle(&self.bpm, target.page_id(), AccessType::Lookup)?;
+        let (metadata, stored) = TablePageRef::try_from(page_frame_handle)?.get_tuple(&target)?;
+        Ok(stored_tuple_size(&metadata, &stored))
+    }
+
+    fn replace_tuple(&mut self, rid: &RecordId, tuple: &Tuple) -> Result<()> {
+        // Try the tuple's home page first; this also brings a forwarded tuple back home.
+        let (mut metadata, stored, old_metadata, old_stub) = {
+            let page_frame_handle = BufferPoolManager::fetch_page_mut_handle(
+                &self.bpm,
+                rid.page_id(),
+                AccessType::Lookup,
+            )?;
+            let mut home_page = TablePageMut::from(page_frame_handle);
+
+            let (old_metadata, old_stub) = home_page.get_tuple(rid)?;
+            if old_metadata.is_deleted() {
+                return errinput!("Cannot update deleted tuple {}", rid.to_string());
+            }
+
+            let (metadata, stored) = self.prepare_tuple(tuple)?;
+            let result = self.stats.track(&mut home_page, |page| {
+                page.update_tuple(rid, &metadata, &stored)
+            });
+            match result {
+                Ok(()) => {
+                    self.free_space_map
+                        .update(rid.page_id(), home_page.available_space())?;
+                    drop(home_page);
+                    self.free_overflow(&old_metadata, &old_stub)?;
+                    if old_metadata.is_forwarded() {
+                        self.mark_deleted(&decode_forward(&old_stub))?;
+                    }
+                    return Ok(());
+                }
+                Err(Error::OutOfBounds) => (metadata, stored, old_metadata, old_stub),
+                Err(e) => return Err(e),
+            }
+        };
+        metadata.set_relocated(true);
+
+        // Then try to overwrite the copy the slot already forwards to.
+        if old_metadata.is_forwarded() {
+            let old_target = decode_forward(&old_stub);
+            let page_frame_handle = BufferPoolManager::fetch_page_mut_handle(
+                &self.bpm,
+                old_target.page_id(),
+                AccessType::Lookup,
+            )?;
+            let mut target_page = TablePageMut::from(page_frame_handle);
+            let (old_target_metadata, old_target_stub) = target_page.get_tuple(&old_target)?;
+            let result = self.stats.track(&mut target_page, |page| {
+                page.update_tuple(&old_target, &metadata, &stored)
+            });
+            match result {
+                Ok(()) => {
+                    self.free_space_map
+                        .update(old_target.page_id(), target_page.available_space())?;
+                    drop(target_page);
+                    return self.free_overflow(&old_target_metadata, &old_target_stub);
+                }
+                Err(Error::OutOfBounds) => {
+                    drop(target_page);
+                    self.mark_deleted(&old_target)?;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        // Otherwise store the tuple wherever there's room and forward the home slot to it.
+        let new_target = self.insert_tuple_with_metadata(&metadata, &stored)?;
+
+        let page_frame_handle =
+            BufferPoolManager::fetch_page_mut_handle(&self.bpm, rid.page_id(), AccessType::Lookup)?;
+        let mut home_page = TablePageMut::from(page_frame_handle);
+        home_page.set_forward(rid, &new_target)?;
+        self.free_space_map
+            .update(rid.page_id(), home_page.available_space())?;
+        drop(home_page);
+        self.free_overflow(&old_metadata, &old_stub)
+    }
+
     /// Insert a tuple into the table heap.
     pub fn insert_tuple(&mut self, tuple: &Tuple) -> Result<RecordId> {
         // For a newly inserted tuple the metadata is by default not deleted
-        let metadata = TupleMetadata::new(false);
+        let (metadata, stored) = self.prepare_tuple(tuple)?;
+        let rid = self.insert_tuple_with_metadata(&metadata, &stored)?;
+        self.stats.inserted(tuple.tuple_size());
+        Ok(rid)
+    }
+
+    /// Insert many tuples at once, returning their record ids in order.
+    ///
+    /// Meant for loading data: the tuples are appended to the end of the heap, filling one page
+    /// at a time while holding on to it, and new pages are allocated in batches sized for the
+    /// tuples still to be placed. Space freed in earlier pages is not reused.
+    pub fn insert_tuples<'a>(
+        &mut self,
+        tuples: impl IntoIterator<Item = &'a Tuple>,
+    ) -> Result<Vec<RecordId>> {
+        let bpm = self.bpm.clone();
+        let mut rids = Vec::new();
+        let mut tuples = tuples.into_iter().peekable();
+        let mut page_id = self.last_page_id;
 
-        // Try to fetch a mutable handle for the current last page.
-        let mut current_table_page = {
+        while tuples.peek().is_some() {
+            // Spilling a tuple to overflow pages creates pages, so the chunk is prepared before
+            // the page it goes into is fetched.
+            let chunk = tuples
+                .by_ref()
+                .take(BULK_INSERT_CHUNK_SIZE)
+                .map(|tuple| self.prepare_tuple(tuple))
+                .collect::<Result<Vec<_>>>()?;
+            let mut remaining: usize = chunk.iter().map(|(_, stored)| slot_size(stored)).sum();
+            let mut page = TablePageMut::from(BufferPoolManager::fetch_page_mut_handle(
+                &bpm,
+                page_id,
+                AccessType::Unknown,
+            )?);
+
+            for (metadata, stored) in &chunk {
+                let rid = match self
+                    .stats
+                    .track(&mut page, |page| page.insert_tuple(metadata, stored))
+                {
+                    Ok(rid) => rid,
+                    Err(Error::OutOfBounds) => {
+                        self.free_space_map
+                            .update(page.page_id(), page.available_space())?;
+                        let mut next_page_id = page.next_page_id();
+                        if next_page_id == INVALID_PAGE_ID {
+                            // Allocate as many pages as the rest of the chunk needs at least.
+                            let usable = PAGE_SIZE - TABLE_PAGE_HEADER_SIZE;
+                            let count = remaining.div_ceil(usable).clamp(1, BULK_INSERT_PAGE_BATCH);
+                            let (first_page_id, last_page_id) = self.allocate_pages(count)?;
+                            page.set_next_page_id(first_page_id);
+                            next_page_id = first_page_id;
+                            self.last_page_id = last_page_id;
+                            self.page_cnt += count as u32;
+                            self.save_last_page()?;
+                        }
+                        drop(page);
+                        page_id = next_page_id;
+                        page = TablePageMut::from(BufferPoolManager::fetch_page_mut_handle(
+                            &bpm,
+                            page_id,
+                            AccessType::Unknown,
+                        )?);
+                        self.stats
+                            .track(&mut page, |page| page.insert_tuple(metadata, stored))?
+                    }
+                    Err(e) => return Err(e),
+                };
+                remaining -= slot_size(stored);
+                self.stats.inserted(stored_tuple_size(metadata, stored));
+                rids.push(rid);
+            }
+
+            self.free_space_map
+                .update(page.page_id(), page.available_space())?;
+        }
+        Ok(rids)
+    }
+
+    /// Creates `count` empty pages chained together, returning the first and last of them. The
+    /// pages are created back to front, so each can be linked to its successor right away.
+    fn allocate_pages(&self, count: usize) -> Result<(PageId, PageId)> {
+        let mut next_page_id = INVALID_PAGE_ID;
+        let mut last_page_id = INVALID_PAGE_ID;
+        for _ in 0..count {
+            let mut page = TablePageMut::from(BufferPoolManager::create_page_handle(&self.bpm)?);
+            page.init_header(next_page_id);
+            self.free_space_map
+                .update(page.page_id(), page.available_space())?;
+            if last_page_id == INVALID_PAGE_ID {
+                last_page_id = page.page_id();
+            }
+            next_page_id = page.page_id();
+        }
+        Ok((next_page_id, last_page_id))
+    }
+
+    /// Insert a tuple with the given metadata into a page the free space map says has room,
+    /// appending a new page to the heap if none does.
+    fn insert_tuple_with_metadata(
+        &mut self,
+        metadata: &TupleMetadata,
+        tuple: &Tuple,
+    ) -> Result<RecordId> {
+        let needed = tuple.tuple_size().max(FORWARD_POINTER_SIZE) + TUPLE_INFO_SIZE;
+
+        while let Some(page_id) = self.free_space_map.find(needed)? {
             let page_handle =
-                BufferPoolManager::fetch_page_mut_handle(&self.bpm, self.last_page_id)?;
+                BufferPoolManager::fetch_page_mut_handle(&self.bpm, page_id, AccessType::Unknown)?;
             // `TablePage` takes ownership of the page handle, so the page handle won't be dropped
             // (and thus the frame won't be unpinned in the buffer pool) until this table page
             // goes out of scope.
-            TablePageMut::from(page_handle)
-        };
+            let mut table_page = TablePageMut::from(page_handle);
 
-        // Try inserting the tuple into the current page.
-        match current_table_page.insert_tuple(&metadata, tuple) {
-            // It worked!
-            Ok(rid) => Ok(rid),
-            // Uh oh, there isn’t enough free space in the current page...
-            Err(Error::OutOfBounds) => {
-                // Allocate a new page.
-                let mut new_table_page =
-                    TablePageMut::from(BufferPoolManager::create_page_handle(&self.bpm)?);
-                let new_page_id = new_table_page.page_id();
-
-                // Update the current page’s header to point to the new page.
-                current_table_page.set_next_page_id(new_page_id);
-
-                // Initialize the new page (its header’s next_page_id is set to INVALID_PAGE_ID).
-                new_table_page.init_header(INVALID_PAGE_ID);
-
-                // Try inserting the tuple into the new page.
-                let rid = new_table_page.insert_tuple(&metadata, tuple)?;
-                // Update the table heap’s bookkeeping.
-                self.last_page_id = new_page_id;
-                self.page_cnt += 1;
-
-                Ok(rid)
+            let result = self
+                .stats
+                .track(&mut table_page, |page| page.insert_tuple(metadata, tuple));
+            // Either way, the map now learns how much room the page really has. If it was out of
+            // date, the page won't be picked again for this tuple.
+            self.free_space_map
+                .update(page_id, table_page.available_space())?;
+            match result {
+                Ok(rid) => return Ok(rid),
+                Err(Error::OutOfBounds) => continue,
+                Err(e) => return Err(e),
             }
-            Err(e) => Err(e),
         }
+
+        // No page has enough free space, so allocate a new one at the end of the heap.
+        let mut new_table_page =
+            TablePageMut::from(BufferPoolManager::create_page_handle(&self.bpm)?);
+        let new_page_id = new_table_page.page_id();
+
+        // Initialize the new page (its header's next_page_id is set to INVALID_PAGE_ID).
+        new_table_page.init_header(INVALID_PAGE_ID);
+        let rid = new_table_page.insert_tuple(metadata, tuple)?;
+        self.free_space_map
+            .update(new_page_id, new_table_page.available_space())?;
+        drop(new_table_page);
+
+        // Update the current last page's header to point to the new page.
+        let page_handle = BufferPoolManager::fetch_page_mut_handle(
+            &self.bpm,
+            self.last_page_id,
+            AccessType::Unknown,
+        )?;
+        TablePageMut::from(page_handle).set_next_page_id(new_page_id);
+
+        // Update the table heap's bookkeeping.
+        self.last_page_id = new_page_id;
+        self.page_cnt += 1;
+        self.save_last_page()?;
+
+        Ok(rid)
     }
 
     pub(crate) fn first_page_id(&self) -> PageId {
         self.first_page_id
     }
+
+    pub(crate) fn header_page_id(&self) -> PageId {
+        self.header_page_id
+    }
+
+    /// Returns the ids of every page the heap owns: its header page, its free space map and the
+    /// pages returned by [`TableHeap::data_page_ids`].
+    pub(crate) fn page_ids(&self) -> Result<Vec<PageId>> {
+        let mut page_ids = vec![self.header_page_id];
+        page_ids.extend(self.free_space_map.page_ids()?);
+        page_ids.extend(self.data_page_ids()?);
+        Ok(page_ids)
+    }
+
+    /// Returns the ids of the pages in the heap's page chain, in chain order.
+    pub(crate) fn chain_page_ids(&self) -> Result<Vec<PageId>> {
+        let mut page_ids = Vec::with_capacity(self.page_cnt as usize);
+        let mut page_id = self.first_page_id;
+        while page_id != INVALID_PAGE_ID {
+            page_ids.push(page_id);
+            let page_frame_handle =
+                BufferPoolManager::fetch_page_handle(&self.bpm, page_id, AccessType::Unknown)?;
+            page_id = TablePageRef::try_from(page_frame_handle)?.next_page_id();
+        }
+        Ok(page_ids)
+    }
+
+    /// Returns the ids of the pages in the heap's page chain, along with the overflow pages its
+    /// live tuples were spilled to.
+    fn data_page_ids(&self) -> Result<Vec<PageId>> {
+        let mut page_ids = self.chain_page_ids()?;
+        let mut overflow_pointers = Vec::new();
+        for &page_id in &page_ids {
+            let page = TablePageRef::try_from(BufferPoolManager::fetch_page_handle(
+                &self.bpm,
+                page_id,
+                AccessType::Unknown,
+            )?)?;
+            for slot_id in 0..page.tuple_count() {
+                let (metadata, stub) = page.get_tuple(&RecordId::new(page_id, slot_id))?;
+                if metadata.is_overflow() && !metadata.is_deleted() {
+                    overflow_pointers.push(OverflowPointer::from_tuple(&stub));
+                }
+            }
+        }
+        for pointer in overflow_pointers {
+            page_ids.extend(overflow_chain_page_ids(&self.bpm, pointer)?);
+        }
+        Ok(page_ids)
+    }
+
+    /// Remove every tuple from the heap, deleting all of its pages but the header page and
+    /// starting over with a new root page and free space map. Fails without changing the heap if
+    /// any of its pages is pinned.
+    pub fn truncate(&mut self) -> Result<()> {
+        let mut old_page_ids = self.data_page_ids()?;
+        old_page_ids.extend(self.free_space_map.page_ids()?);
+
+        let (first_page_id, available_space) = {
+            let mut page = TablePageMut::from(BufferPoolManager::create_page_handle(&self.bpm)?);
+            page.init_header(INVALID_PAGE_ID);
+            (page.page_id(), page.available_space())
+        };
+        let free_space_map = FreeSpaceMap::new(self.bpm.clone())?;
+        free_space_map.update(first_page_id, available_space)?;
+
+        let result = self.bpm.write().unwrap().delete_pages(&old_page_ids);
+        if let Err(e) = result {
+            let mut new_page_ids = free_space_map.page_ids()?;
+            new_page_ids.push(first_page_id);
+            self.bpm.write().unwrap().delete_pages(&new_page_ids)?;
+            return Err(e);
+        }
+
+        let page_handle = BufferPoolManager::fetch_page_mut_handle(
+            &self.bpm,
+            self.header_page_id,
+            AccessType::Unknown,
+        )?;
+        HeapHeaderPageMut::from(page_handle).init(
+            &self.table_name,
+            first_page_id,
+            free_space_map.first_page_id(),
+        )?;
+        self.page_cnt = 1;
+        self.first_page_id = first_page_id;
+        self.last_page_id = first_page_id;
+        self.free_space_map = free_space_map;
+        self.stats = HeapStats::default();
+        Ok(())
+    }
+
+    /// Reclaim the space taken up by deleted tuples. Every page is compacted, and the pages left
+    /// without any tuple are unlinked from the page chain and freed. With
+    /// [`VacuumOptions::merge_pages`], a page holding nothing but relocated tuples also has them
+    /// moved to the page before it whenever they all fit there, so that it can be freed as well.
+    /// Record ids stay valid either way.
+    ///
+    /// The first page is always kept, so that the heap is never empty. So is a page someone else
+    /// has pinned, which is left for the next vacuum to free.
+    pub fn vacuum(&mut self, options: VacuumOptions) -> Result<VacuumReport> {
+        let mut report = VacuumReport::default();
+        let mut homes = if options.merge_pages {
+            self.home_slots()?
+        } else {
+            HashMap::new()
+        };
+        let mut prev_page_id = INVALID_PAGE_ID;
+        for page_id in self.chain_page_ids()? {
+            let is_empty = {
+                let mut page = TablePageMut::from(BufferPoolManager::fetch_page_mut_handle(
+                    &self.bpm,
+                    page_id,
+                    AccessType::Unknown,
+                )?);
+                if page.reclaimable_space() > 0 {
+                    report.bytes_reclaimed += self.stats.track(&mut page, |page| page.compact());
+                    self.free_space_map
+                        .update(page_id, page.available_space())?;
+                }
+                page.is_empty()
+            };
+            if prev_page_id != INVALID_PAGE_ID {
+                let merged = !is_empty
+                    && options.merge_pages
+                    && self.merge_page(page_id, prev_page_id, &mut homes)?;
+                if (is_empty || merged) && self.free_page(page_id, prev_page_id)? {
+                    report.pages_reclaimed += 1;
+                    continue;
+                }
+            }
+            prev_page_id = page_id;
+        }
+        Ok(report)
+    }
+
+    /// Maps the record id of every relocated tuple (packed into a `u64`) to the slot forwarding
+    /// to it, which has to be updated when the tuple moves.
+    fn home_slots(&self) -> Result<HashMap<u64, RecordId>> {
+        let mut homes = HashMap::new();
+        for page_id in self.chain_page_ids()? {
+            let page = TablePageRef::try_from(BufferPoolManager::fetch_page_handle(
+                &self.bpm,
+                page_id,
+                AccessType::Unknown,
+            )?)?;
+            for slot_id in 0..page.tuple_count() {
+                let rid = RecordId::new(page_id, slot_id);
+                let (metadata, stub) = page.get_tuple(&rid)?;
+                if metadata.is_forwarded() && !metadata.is_deleted() {
+                    homes.insert(u64::from(decode_forward(&stub)), rid);
+                }
+            }
+        }
+        Ok(homes)
+    }
+
+    /// Moves all the tuples of `page_id` to `target_page_id` if they fit there, returning whether
+    /// they did. Only relocated tuples are moved, as their home slot can be pointed at their new
+    /// location; a page holding any other live tuple is left alone, so that its record ids stay
+    /// valid.
+    fn merge_page(
+        &self,
+        page_id: PageId,
+        target_page_id: PageId,
+        homes: &mut HashMap<u64, RecordId>,
+    ) -> Result<bool> {
+        let slots = {
+            let page = TablePageRef::try_from(BufferPoolManager::fetch_page_handle(
+                &self.bpm,
+                page_id,
+                AccessType::Unknown,
+            )?)?;
+            let mut slots = Vec::new();
+            for slot_id in 0..page.tuple_count() {
+                let rid = RecordId::new(page_id, slot_id);
+                let (metadata, stored) = page.get_tuple(&rid)?;
+                if metadata.is_deleted() {
+                    continue;
+                }
+                if !metadata.is_relocated() {
+                    return Ok(false);
+                }
+                slots.push((rid, metadata, stored));
+            }
+            slots
+        };
+        let needed: usize = slots.iter().map(|(_, _, stored)| slot_size(stored)).sum();
+
+        let new_rids = {
+            let mut target_page = TablePageMut::from(BufferPoolManager::fetch_page_mut_handle(
+                &self.bpm,
+                target_page_id,
+                AccessType::Unknown,
+            )?);
+            if target_page.available_space() < needed {
+                return Ok(false);
+            }
+            let new_rids = self.stats.track(&mut target_page, |page| {
+                slots
+                    .iter()
+                    .map(|(_, metadata, stored)| page.insert_tuple(metadata, stored))
+                    .collect::<Result<Vec<_>>>()
+            })?;
+            self.free_space_map
+                .update(target_page_id, target_page.available_space())?;
+            new_rids
+        };
+
+        {
+            let mut page = TablePageMut::from(BufferPoolManager::fetch_page_mut_handle(
+                &self.bpm,
+                page_id,
+                AccessType::Unknown,
+            )?);
+            self.stats.track(&mut page, |page| -> Result<()> {
+                for (rid, _, _) in &slots {
+                    page.update_tuple_metadata(rid, TupleMetadata::new(true))?;
+                }
+                page.compact();
+                Ok(())
+            })?;
+        }
+
+        for ((rid, _, _), new_rid) in slots.into_iter().zip(new_rids) {
+            let Some(home) = homes.remove(&u64::from(rid.clone())) else {
+                return errdata!("No slot forwards to relocated tuple {}", rid.to_string());
+            };
+            let page_frame_handle = BufferPoolManager::fetch_page_mut_handle(
+                &self.bpm,
+                home.page_id(),
+                AccessType::Unknown,
+            )?;
+            TablePageMut::from(page_frame_handle).set_forward(&home, &new_rid)?;
+            homes.insert(u64::from(new_rid), home);
+        }
+        Ok(true)
+    }
+
+    /// Unlinks `page_id` from the page chain, where it follows `prev_page_id`, and frees it.
+    /// Returns false, leaving the page in the chain, if it is pinned.
+    fn free_page(&mut self, page_id: PageId, prev_page_id: PageId) -> Result<bool> {
+        let next_page_id = TablePageRef::try_from(BufferPoolManager::fetch_page_handle(
+            &self.bpm,
+            page_id,
+            AccessType::Unknown,
+        )?)?
+        .next_page_id();
+        let link_prev_page_to = |next_page_id| -> Result<()> {
+            let page_frame_handle = BufferPoolManager::fetch_page_mut_handle(
+                &self.bpm,
+                prev_page_id,
+                AccessType::Unknown,
+            )?;
+            TablePageMut::from(page_frame_handle).set_next_page_id(next_page_id);
+            Ok(())
+        };
+
+        link_prev_page_to(next_page_id)?;
+        let result = self.bpm.write().unwrap().delete_page(page_id);
+        match result {
+            Ok(()) => {}
+            Err(Error::PagePinned(_)) => {
+                link_prev_page_to(page_id)?;
+                return Ok(false);
+            }
+            Err(e) => return Err(e),
+        }
+
+        self.free_space_map.remove(page_id)?;
+        self.page_cnt -= 1;
+        if self.last_page_id == page_id {
+            self.last_page_id = prev_page_id;
+        }
+        self.save_last_page()?;
+        Ok(true)
+    }
+}
+
+impl ScanLocked for TableHeap {
+    fn scan_lock(&self) -> &Arc<ScanLock> {
+        &self.scan_lock
+    }
+}
+
+/// Turns the contents of a slot into the tuple it stands for, following a forwarding pointer to
+/// the tuple's current slot and reading a spilled tuple back from its overflow pages.
+///
+/// The pointers of a deleted slot aren't followed, since the slot or overflow pages they lead to
+/// may have been reused; such a slot stands for an empty tuple.
+///
+/// The caller must not keep the slot's page pinned, as this may fetch several other pages.
+pub(crate) fn resolve_tuple(
+    bpm: &Arc<RwLock<BufferPoolManager>>,
+    metadata: TupleMetadata,
+    data: Tuple,
+    access_type: AccessType,
+) -> Result<Tuple> {
+    if metadata.is_deleted() && (metadata.is_forwarded() || metadata.is_overflow()) {
+        return Ok(Tuple::new(Default::default()));
+    }
+    let (metadata, data) = if metadata.is_forwarded() {
+        let target = decode_forward(&data);
+        let page_frame_handle =
+            BufferPoolManager::fetch_page_handle(bpm, target.page_id(), access_type)?;
+        TablePageRef::try_from(page_frame_handle)?.get_tuple(&target)?
+    } else {
+        (metadata, data)
+    };
+
+    if metadata.is_overflow() {
+        return read_overflow_chain(bpm, OverflowPointer::from_tuple(&data), access_type);
+    }
+    Ok(data)
 }
 
 #[cfg(test)]
@@ -121,24 +847,17 @@ mod tests {
     use rustdb_catalog::tuple::Tuple;
     use serial_test::serial;
 
-    use crate::replacer::lru_k_replacer::LrukReplacer;
-    use std::sync::{Arc, Mutex, RwLock};
+    use std::sync::{Arc, RwLock};
 
-    use crate::disk::disk_manager::DiskManager;
     use crate::heap::table_heap::TableHeap;
+    use crate::heap::table_tuple_iterator::TableTupleIterator;
     use crate::page::table_page::{TABLE_PAGE_HEADER_SIZE, TUPLE_INFO_SIZE};
     use crate::page::PAGE_SIZE;
-    use crate::{buffer_pool::BufferPoolManager, Result};
-
-    pub fn get_bpm_with_pool_size(pool_size: usize) -> BufferPoolManager {
-        let disk_manager = Arc::new(Mutex::new(DiskManager::new("test.db").unwrap()));
-        let replacer = Box::new(LrukReplacer::new(5));
-        BufferPoolManager::new(pool_size, disk_manager, replacer)
-    }
-
-    fn get_bpm_arc_with_pool_size(pool_size: usize) -> Arc<RwLock<BufferPoolManager>> {
-        Arc::new(RwLock::new(get_bpm_with_pool_size(pool_size)))
-    }
+    use crate::record_id::RecordId;
+    use crate::storage::VacuumOptions;
+    use crate::test_utils::get_bpm_arc_with_pool_size;
+    use crate::Result;
+    use rustdb_catalog::catalog::TableStats;
 
     /// Test that we can insert a tuple into the table heap and then retrieve it correctly.
     #[test]
@@ -257,4 +976,463 @@ mod tests {
             }
         }
     }
+
+    /// Test that updates keep record ids stable, forwarding tuples that outgrow their page.
+    #[test]
+    #[serial]
+    fn test_table_heap_update_tuple() -> Result<()> {
+        let bpm = get_bpm_arc_with_pool_size(10);
+        let table_heap = Arc::new(RwLock::new(TableHeap::new("table", bpm.clone())));
+        let scan = || {
+            TableTupleIterator::new(bpm.clone(), table_heap.clone())
+                .map(|item| item.map(|(rid, tuple)| (rid, tuple.data().to_vec())))
+                .collect::<Result<Vec<_>>>()
+        };
+
+        // Fill the first page, leaving only a few free bytes.
+        let (rid_a, rid_b) = {
+            let mut heap = table_heap.write().unwrap();
+            let rid_a = heap.insert_tuple(&Tuple::new(vec![1; 10].into()))?;
+            let big_size = PAGE_SIZE - TABLE_PAGE_HEADER_SIZE - 2 * TUPLE_INFO_SIZE - 10 - 4;
+            let rid_b = heap.insert_tuple(&Tuple::new(vec![9; big_size].into()))?;
+            (rid_a, rid_b)
+        };
+        let big_data = table_heap
+            .read()
+            .unwrap()
+            .get_tuple(&rid_b)?
+            .1
+            .data()
+            .to_vec();
+
+        // The grown tuple moves to a new page, but keeps its record id.
+        table_heap
+            .write()
+            .unwrap()
+            .update_tuple(&rid_a, &Tuple::new(vec![2; 30].into()))?;
+        let (meta, tuple) = table_heap.read().unwrap().get_tuple(&rid_a)?;
+        assert!(!meta.is_deleted() && !meta.is_forwarded());
+        assert_eq!(tuple.data().to_vec(), vec![2; 30]);
+        assert_eq!(
+            scan()?,
+            vec![
+                (rid_a.clone().into(), vec![2; 30]),
+                (rid_b.clone().into(), big_data.clone())
+            ]
+        );
+
+        // Growing it again updates the relocated copy rather than adding another hop.
+        table_heap
+            .write()
+            .unwrap()
+            .update_tuple(&rid_a, &Tuple::new(vec![3; 40].into()))?;
+        assert_eq!(
+            table_heap
+                .read()
+                .unwrap()
+                .get_tuple(&rid_a)?
+                .1
+                .data()
+                .to_vec(),
+            vec![3; 40]
+        );
+
+        // Once it fits again, the tuple moves back into its home slot.
+        table_heap
+            .write()
+            .unwrap()
+            .update_tuple(&rid_a, &Tuple::new(vec![4; 8].into()))?;
+        assert_eq!(
+            scan()?,
+            vec![
+                (rid_a.clone().into(), vec![4; 8]),
+                (rid_b.clone().into(), big_data.clone())
+            ]
+        );
+
+        // Deleting a forwarded tuple deletes it everywhere.
+        table_heap
+            .write()
+            .unwrap()
+            .update_tuple(&rid_a, &Tuple::new(vec![5; 50].into()))?;
+        let (meta, tuple) = table_heap.read().unwrap().delete_tuple(&rid_a)?;
+        assert!(!meta.is_deleted());
+        assert_eq!(tuple.data().to_vec(), vec![5; 50]);
+        assert_eq!(scan()?, vec![(rid_b.clone().into(), big_data)]);
+        assert!(table_heap
+            .write()
+            .unwrap()
+            .update_tuple(&rid_a, &Tuple::new(vec![6].into()))
+            .is_err());
+
+        Ok(())
+    }
+
+    /// Test that deleting a tuple again leaves alone whatever reused its forwarded slot or its
+    /// overflow pages.
+    #[test]
+    #[serial]
+    fn test_table_heap_delete_twice() -> Result<()> {
+        let bpm = get_bpm_arc_with_pool_size(10);
+        let mut table_heap = TableHeap::new("table", bpm.clone());
+
+        // Fill the first page so that growing its first tuple moves it to another page.
+        let tuples_per_page = (PAGE_SIZE - TABLE_PAGE_HEADER_SIZE) / (100 + TUPLE_INFO_SIZE);
+        let tuples: Vec<_> = (0..tuples_per_page)
+            .map(|_| Tuple::new(vec![1; 100].into()))
+            .collect();
+        let rid_a = table_heap.insert_tuples(&tuples)?[0].clone();
+        table_heap.update_tuple(&rid_a, &Tuple::new(vec![2; 200].into()))?;
+        table_heap.delete_tuple(&rid_a)?;
+
+        // A new tuple takes the slot the deleted one had been moved to.
+        let rid_b = table_heap.insert_tuple(&Tuple::new(vec![3; 200].into()))?;
+        assert_ne!(rid_a.page_id(), rid_b.page_id());
+        assert_eq!(0, rid_b.slot_id());
+        let (meta, _) = table_heap.delete_tuple(&rid_a)?;
+        assert!(meta.is_deleted());
+        let (meta, tuple) = table_heap.get_tuple(&rid_b)?;
+        assert!(!meta.is_deleted());
+        assert_eq!(vec![3; 200], tuple.data().to_vec());
+        assert_eq!(tuples_per_page as u64, table_heap.stats().row_count);
+
+        // The overflow pages of a deleted tuple aren't read or freed again.
+        let rid_c = table_heap.insert_tuple(&Tuple::new(vec![4; 3 * PAGE_SIZE].into()))?;
+        table_heap.delete_tuple(&rid_c)?;
+        let rid_d = table_heap.insert_tuple(&Tuple::new(vec![5; 3 * PAGE_SIZE].into()))?;
+        let (meta, _) = table_heap.delete_tuple(&rid_c)?;
+        assert!(meta.is_deleted());
+        assert!(table_heap.get_tuple(&rid_c)?.0.is_deleted());
+        let (_, tuple) = table_heap.get_tuple(&rid_d)?;
+        assert_eq!(vec![5; 3 * PAGE_SIZE], tuple.data().to_vec());
+        assert_eq!(tuples_per_page as u64 + 1, table_heap.stats().row_count);
+
+        Ok(())
+    }
+
+    /// Test that the space of deleted tuples is reused instead of allocating new pages.
+    #[test]
+    #[serial]
+    fn test_table_heap_reuses_deleted_space() -> Result<()> {
+        let bpm = get_bpm_arc_with_pool_size(10);
+        let mut table_heap = TableHeap::new("table", bpm.clone());
+
+        // Fill the first page exactly.
+        let tuples_per_page = (PAGE_SIZE - TABLE_PAGE_HEADER_SIZE) / (100 + TUPLE_INFO_SIZE);
+        let tuple = Tuple::new(vec![1; 100].into());
+        let rids = (0..tuples_per_page)
+            .map(|_| table_heap.insert_tuple(&tuple))
+            .collect::<Result<Vec<_>>>()?;
+        assert!(rids.iter().all(|rid| rid.page_id() == rids[0].page_id()));
+
+        // Once its tuples are deleted, the page is compacted and takes as many tuples again.
+        for rid in &rids {
+            table_heap.delete_tuple(rid)?;
+        }
+        let tuple = Tuple::new(vec![2; 100].into());
+        let new_rids = (0..tuples_per_page)
+            .map(|_| table_heap.insert_tuple(&tuple))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(rids, new_rids);
+
+        let iter = TableTupleIterator::new(bpm.clone(), Arc::new(RwLock::new(table_heap)));
+        let tuples = iter.collect::<Result<Vec<_>>>()?;
+        assert_eq!(tuples_per_page, tuples.len());
+        assert!(tuples.iter().all(|(_, tuple)| tuple.data()[..] == [2; 100]));
+
+        Ok(())
+    }
+
+    /// Test that tuples too large for a page are spilled to overflow pages and read back whole.
+    #[test]
+    #[serial]
+    fn test_table_heap_oversized_tuples() -> Result<()> {
+        let bpm = get_bpm_arc_with_pool_size(4);
+        let table_heap = Arc::new(RwLock::new(TableHeap::new("table", bpm.clone())));
+        let scan = || {
+            TableTupleIterator::new(bpm.clone(), table_heap.clone())
+                .map(|item| item.map(|(_, tuple)| tuple.data().to_vec()))
+                .collect::<Result<Vec<_>>>()
+        };
+        let oversized = |len: usize, seed: u8| -> Vec<u8> {
+            (0..len).map(|i| (i as u8).wrapping_mul(seed)).collect()
+        };
+
+        let big = oversized(3 * PAGE_SIZE, 7);
+        let (rid_big, rid_small) = {
+            let mut heap = table_heap.write().unwrap();
+            let rid_big = heap.insert_tuple(&Tuple::new(big.clone().into()))?;
+            let rid_small = heap.insert_tuple(&Tuple::new(vec![1, 2, 3].into()))?;
+            (rid_big, rid_small)
+        };
+
+        // Only a pointer is stored in the slot, so both tuples share a page.
+        assert_eq!(rid_big.page_id(), rid_small.page_id());
+        let (meta, tuple) = table_heap.read().unwrap().get_tuple(&rid_big)?;
+        assert!(!meta.is_overflow());
+        assert_eq!(big, tuple.data().to_vec());
+        assert_eq!(scan()?, vec![big, vec![1, 2, 3]]);
+
+        // Updates can switch between spilled and inline storage in either direction.
+        let bigger = oversized(5 * PAGE_SIZE, 13);
+        let mut heap = table_heap.write().unwrap();
+        heap.update_tuple(&rid_big, &Tuple::new(bigger.clone().into()))?;
+        assert_eq!(bigger, heap.get_tuple(&rid_big)?.1.data().to_vec());
+        heap.update_tuple(&rid_big, &Tuple::new(vec![4; 100].into()))?;
+        assert_eq!(vec![4; 100], heap.get_tuple(&rid_big)?.1.data().to_vec());
+        heap.update_tuple(&rid_small, &Tuple::new(bigger.clone().into()))?;
+        assert_eq!(bigger, heap.get_tuple(&rid_small)?.1.data().to_vec());
+
+        let (_, deleted) = heap.delete_tuple(&rid_small)?;
+        assert_eq!(bigger, deleted.data().to_vec());
+        drop(heap);
+        assert_eq!(scan()?, vec![vec![4; 100]]);
+
+        Ok(())
+    }
+
+    /// Test that inserts go to earlier pages once deletes have freed space in them.
+    #[test]
+    #[serial]
+    fn test_table_heap_free_space_map() -> Result<()> {
+        let bpm = get_bpm_arc_with_pool_size(10);
+        let mut table_heap = TableHeap::new("table", bpm.clone());
+
+        // Fill two pages, spilling a single tuple into a third.
+        let tuple = Tuple::new(vec![1; 500].into());
+        let mut rids = Vec::new();
+        while rids.last().map_or(true, |rid: &RecordId| {
+            rid.page_id() == table_heap.first_page_id
+        }) {
+            rids.push(table_heap.insert_tuple(&tuple)?);
+        }
+        let second_page_id = rids.last().unwrap().page_id();
+        while rids.last().unwrap().page_id() == second_page_id {
+            rids.push(table_heap.insert_tuple(&tuple)?);
+        }
+        let last_page_id = table_heap.last_page_id;
+
+        // The first two pages only have a few bytes left, so this goes to the last one.
+        let small = Tuple::new(vec![2; 100].into());
+        assert_eq!(last_page_id, table_heap.insert_tuple(&small)?.page_id());
+
+        // Free some space in the first page; the next large tuple goes there, not to a new page.
+        table_heap.delete_tuple(&rids[0])?;
+        let rid = table_heap.insert_tuple(&tuple)?;
+        assert_eq!(table_heap.first_page_id, rid.page_id());
+        assert_eq!(last_page_id, table_heap.last_page_id);
+
+        // The first page is full again, so the next tuple goes to the last page rather than a new one.
+        let rid = table_heap.insert_tuple(&tuple)?;
+        assert_eq!(last_page_id, rid.page_id());
+        assert_eq!(last_page_id, table_heap.last_page_id);
+        assert_eq!(Some(last_page_id), table_heap.free_space_map.find(500)?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_table_heap_insert_tuples() -> Result<()> {
+        let bpm = get_bpm_arc_with_pool_size(10);
+        let mut table_heap = TableHeap::new("table", bpm.clone());
+        let first_rid = table_heap.insert_tuple(&Tuple::new(vec![0; 10].into()))?;
+        let page_cnt = table_heap.page_cnt;
+
+        let mut data: Vec<Vec<u8>> = (0..2000).map(|i| vec![(i % 256) as u8; 100]).collect();
+        data[1000] = vec![7; 3 * PAGE_SIZE];
+        let tuples: Vec<Tuple> = data.iter().map(|d| Tuple::new(d.clone().into())).collect();
+        let rids = table_heap.insert_tuples(&tuples)?;
+        assert_eq!(data.len(), rids.len());
+
+        // The load continued in the last page, and every page it allocated got tuples.
+        assert_eq!(first_rid.page_id(), rids[0].page_id());
+        let mut page_ids: Vec<_> = rids.iter().map(RecordId::page_id).collect();
+        page_ids.dedup();
+        assert_eq!(
+            page_ids.len() - 1,
+            (table_heap.page_cnt - page_cnt) as usize
+        );
+        assert_eq!(table_heap.last_page_id, *page_ids.last().unwrap());
+
+        for (rid, expected) in rids.iter().zip(&data) {
+            assert_eq!(expected[..], table_heap.get_tuple(rid)?.1.data()[..]);
+        }
+
+        // The pages are chained in insertion order, and later inserts still find room.
+        let iter = TableTupleIterator::new(bpm.clone(), Arc::new(RwLock::new(table_heap)));
+        let scanned = iter.collect::<Result<Vec<_>>>()?;
+        assert_eq!(data.len() + 1, scanned.len());
+        for ((rid, tuple), expected) in scanned[1..].iter().zip(&data) {
+            assert_eq!(expected[..], tuple.data()[..]);
+            assert!(rids.contains(&RecordId::from(*rid)));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_table_heap_open() -> Result<()> {
+        let bpm = get_bpm_arc_with_pool_size(10);
+        let mut table_heap = TableHeap::new("table", bpm.clone());
+        assert_eq!(1, table_heap.page_cnt);
+
+        let tuple = Tuple::new(vec![3; 1000].into());
+        let rids = (0..10)
+            .map(|_| table_heap.insert_tuple(&tuple))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut reopened = TableHeap::open(table_heap.header_page_id(), bpm.clone())?;
+        assert_eq!("table", reopened.table_name);
+        assert_eq!(table_heap.page_cnt, reopened.page_cnt);
+        assert_eq!(table_heap.first_page_id, reopened.first_page_id);
+        assert_eq!(table_heap.last_page_id, reopened.last_page_id);
+        for rid in &rids {
+            assert_eq!(tuple.data(), reopened.get_tuple(rid)?.1.data());
+        }
+
+        // The reopened heap keeps using the free space map and keeps the header up to date.
+        reopened.delete_tuple(&rids[0])?;
+        assert_eq!(rids[0].page_id(), reopened.insert_tuple(&tuple)?.page_id());
+        let more: Vec<Tuple> = (0..10).map(|_| Tuple::new(vec![4; 1000].into())).collect();
+        reopened.insert_tuples(&more)?;
+        let again = TableHeap::open(table_heap.header_page_id(), bpm.clone())?;
+        assert_eq!(reopened.page_cnt, again.page_cnt);
+        assert_eq!(reopened.last_page_id, again.last_page_id);
+
+        // Other pages can't be opened as a heap.
+        assert!(TableHeap::open(table_heap.first_page_id, bpm.clone()).is_err());
+
+        Ok(())
+    }
+
+    /// Test that vacuuming frees the pages emptied by deletes, and with merging also the pages
+    /// holding only relocated tuples that fit in the page before them, keeping every record id
+    /// valid.
+    #[test]
+    #[serial]
+    fn test_table_heap_vacuum() -> Result<()> {
+        let bpm = get_bpm_arc_with_pool_size(10);
+        let mut table_heap = TableHeap::new("table", bpm.clone());
+
+        // Fill six pages, and put one more tuple in a seventh.
+        let tuples_per_page = (PAGE_SIZE - TABLE_PAGE_HEADER_SIZE) / (100 + TUPLE_INFO_SIZE);
+        let mut live: Vec<(RecordId, Vec<u8>)> = (0..tuples_per_page * 6 + 1)
+            .map(|i| {
+                let data = (i as u32).to_le_bytes().repeat(25);
+                Ok((
+                    table_heap.insert_tuple(&Tuple::new(data.clone().into()))?,
+                    data,
+                ))
+            })
+            .collect::<Result<_>>()?;
+        let pages = table_heap.chain_page_ids()?;
+        assert_eq!(7, pages.len());
+
+        // Growing a tuple of the full first page moves it to the last page.
+        live[0].1 = vec![9; 600];
+        table_heap.update_tuple(&live[0].0, &Tuple::new(live[0].1.clone().into()))?;
+
+        // Empty the third and fourth pages, keep two tuples in the fifth and sixth, and leave only
+        // the moved tuple in the seventh.
+        let emptied = [pages[2], pages[3]];
+        live.retain(|(rid, _)| {
+            let page = pages
+                .iter()
+                .position(|&page_id| page_id == rid.page_id())
+                .unwrap();
+            let keep = match page {
+                2 | 3 | 6 => false,
+                4 | 5 => rid.slot_id() < 2,
+                _ => true,
+            };
+            if !keep {
+                table_heap.delete_tuple(rid).unwrap();
+            }
+            keep
+        });
+
+        let report = table_heap.vacuum(VacuumOptions::default())?;
+        assert_eq!(2, report.pages_reclaimed);
+        assert!(report.bytes_reclaimed > 0);
+        let chain = table_heap.chain_page_ids()?;
+        assert!(chain.iter().all(|page_id| !emptied.contains(page_id)));
+        assert_eq!(5, chain.len());
+        for (rid, data) in &live {
+            assert_eq!(data[..], table_heap.get_tuple(rid)?.1.data()[..]);
+        }
+
+        // The moved tuple fits in the sixth page, and is still reached from the first page. The
+        // sixth page would fit in the fifth, but its tuples would lose their record ids.
+        let report = table_heap.vacuum(VacuumOptions { merge_pages: true })?;
+        assert_eq!(1, report.pages_reclaimed);
+        assert_eq!(
+            vec![pages[0], pages[1], pages[4], pages[5]],
+            table_heap.chain_page_ids()?
+        );
+        for (rid, data) in &live {
+            assert_eq!(data[..], table_heap.get_tuple(rid)?.1.data()[..]);
+        }
+        let iter = TableTupleIterator::new(bpm.clone(), Arc::new(RwLock::new(table_heap)));
+        assert_eq!(live.len(), iter.count());
+
+        Ok(())
+    }
+
+    /// Test that the statistics the heap keeps as it is written to match those counted from its
+    /// pages.
+    #[test]
+    #[serial]
+    fn test_table_heap_stats() -> Result<()> {
+        let bpm = get_bpm_arc_with_pool_size(10);
+        let mut table_heap = TableHeap::new("table", bpm.clone());
+        let check = |table_heap: &TableHeap| -> Result<TableStats> {
+            let stats = table_heap.stats();
+            let counted = table_heap
+                .count_stats()?
+                .to_table_stats(table_heap.page_cnt);
+            assert_eq!(counted, stats);
+            assert_eq!(table_heap.chain_page_ids()?.len() as u64, stats.page_count);
+            Ok(stats)
+        };
+
+        let tuples: Vec<Tuple> = (0..300_u32)
+            .map(|i| Tuple::new(i.to_le_bytes().repeat(25).into()))
+            .collect();
+        let mut rids = table_heap.insert_tuples(&tuples)?;
+        rids.push(table_heap.insert_tuple(&Tuple::new(vec![1; 3 * PAGE_SIZE].into()))?);
+        let stats = check(&table_heap)?;
+        assert_eq!(301, stats.row_count);
+        assert_eq!((300 * 100 + 3 * PAGE_SIZE) as u64, stats.tuple_bytes);
+        assert_eq!(0, stats.deleted_count);
+
+        // Growing a tuple of a full page moves it, which leaves no deleted slot behind. Shrinking
+        // the oversized tuple brings it back inline.
+        table_heap.update_tuple(&rids[0], &Tuple::new(vec![2; 500].into()))?;
+        table_heap.update_tuple(&rids[300], &Tuple::new(vec![3; 10].into()))?;
+        for rid in &rids[1..50] {
+            table_heap.delete_tuple(rid)?;
+        }
+        table_heap.delete_tuple(&rids[1])?;
+        let stats = check(&table_heap)?;
+        assert_eq!(252, stats.row_count);
+        assert_eq!((250 * 100 + 500 + 10) as u64, stats.tuple_bytes);
+        assert!(stats.deleted_count > 0);
+        assert!(stats.deleted_ratio() > 0.0);
+
+        table_heap.vacuum(VacuumOptions { merge_pages: true })?;
+        let stats = check(&table_heap)?;
+        assert_eq!(252, stats.row_count);
+        assert_eq!(0, stats.deleted_count);
+
+        // Reopening the heap counts the same statistics from its pages.
+        let header_page_id = table_heap.header_page_id();
+        assert_eq!(stats, TableHeap::open(header_page_id, bpm.clone())?.stats());
+
+        table_heap.truncate()?;
+        assert_eq!(0, check(&table_heap)?.row_count);
+
+        Ok(())
+    }
 }
@@ -2,34 +2,108 @@ use std::sync::{Arc, RwLock};
 
 use crate::page::INVALID_PAGE_ID;
 use crate::record_id::RecordId;
+use crate::replacer::replacer::AccessType;
 use crate::{
-    buffer_pool::BufferPoolManager, page::table_page::TablePageRef, typedef::PageId, Result,
+    buffer_pool::BufferPoolManager,
+    page::table_page::TablePageRef,
+    typedef::PageId,
+    Result,
 };
 use rustdb_catalog::tuple::Tuple;
 use rustdb_error::Error;
 
-use crate::heap::table_heap::TableHeap;
+use crate::heap::sampler::Sampler;
+use crate::heap::scan_filter::ScanFilter;
+use crate::heap::scan_lock::{ScanGuard, ScanLocked};
+use crate::heap::table_heap::{resolve_tuple, TableHeap};
+use bytes::Bytes;
 
 /// An iterator over all non-deleted tuples in a table heap.
 ///
-/// This iterator acquires a read lock on the TableHeap (via an Arc<RwLock<TableHeap>>)
-/// and holds the read guard for its lifetime, ensuring that the table remains stable
-/// (i.e. unmodified) during iteration.
+/// An iterator created with [`TableTupleIterator::new`] only locks the table heap to find its
+/// first page, so writes can interleave with the scan: it sees a change if it hasn't passed the
+/// page the change was made to yet. One created with [`TableTupleIterator::stable`] instead keeps
+/// the storage engine's writers out of the heap until it is exhausted or dropped, so it sees the
+/// table exactly as it was when it was created.
 pub struct TableTupleIterator {
     bpm: Arc<RwLock<BufferPoolManager>>,
     current_page_id: PageId,
     current_slot: u32,
+    /// The page the scan stops before, or `INVALID_PAGE_ID` to scan to the end of the chain.
+    stop_page_id: PageId,
+    /// Which tuples to emit and which of their fields, if not all of them.
+    filter: Option<ScanFilter>,
+    /// Which pages to read and which tuples to emit, for a sampling scan.
+    sampler: Option<Sampler>,
+    /// Keeps writers out of the heap while a stable scan runs.
+    guard: Option<ScanGuard>,
 }
 
 impl TableTupleIterator {
     /// Creates a new `TableTupleIterator` by taking an Arc to the table heap's RwLock.
-    /// It acquires the read guard internally.
+    /// It acquires the read guard internally, just long enough to find the heap's first page.
     pub fn new(bpm: Arc<RwLock<BufferPoolManager>>, table_heap: Arc<RwLock<TableHeap>>) -> Self {
         let first_page_id = table_heap.read().unwrap().first_page_id();
+        Self::with_range(bpm, first_page_id, INVALID_PAGE_ID)
+    }
+
+    /// Creates an iterator that blocks the storage engine's writes to the table heap until it is
+    /// exhausted or dropped. The thread using it must not write to the table in the meantime.
+    pub fn stable(bpm: Arc<RwLock<BufferPoolManager>>, table_heap: Arc<RwLock<TableHeap>>) -> Self {
+        let table_heap = table_heap.read().unwrap();
+        let mut iter = Self::with_range(bpm, table_heap.first_page_id(), INVALID_PAGE_ID);
+        iter.guard = Some(table_heap.scan_lock().acquire());
+        iter
+    }
+
+    /// Creates an iterator over the pages of a table heap's page chain from `start_page_id` up
+    /// to, but excluding, `stop_page_id`. An `INVALID_PAGE_ID` start makes the iterator empty,
+    /// while an `INVALID_PAGE_ID` stop scans to the end of the chain.
+    pub(crate) fn with_range(
+        bpm: Arc<RwLock<BufferPoolManager>>,
+        start_page_id: PageId,
+        stop_page_id: PageId,
+    ) -> Self {
         Self {
             bpm,
-            current_page_id: first_page_id,
+            current_page_id: start_page_id,
             current_slot: 0,
+            stop_page_id,
+            filter: None,
+            sampler: None,
+            guard: None,
+        }
+    }
+
+    /// Makes the iterator skip the slots of its first page before `slot_id`.
+    pub(crate) fn starting_at_slot(mut self, slot_id: u32) -> Self {
+        self.current_slot = slot_id;
+        self
+    }
+
+    /// Returns the record id of the next slot the iterator will look at. Its page is
+    /// `INVALID_PAGE_ID` once the iterator is exhausted.
+    pub(crate) fn position(&self) -> RecordId {
+        RecordId::new(self.current_page_id, self.current_slot)
+    }
+
+    /// Makes the iterator only emit the tuples that pass `filter`, projected to its columns.
+    pub(crate) fn with_filter(mut self, filter: ScanFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Makes the iterator only emit a random sample of the tuples it would otherwise emit.
+    pub(crate) fn with_sampler(mut self, sampler: Sampler) -> Self {
+        self.sampler = Some(sampler);
+        self
+    }
+
+    /// Returns what to emit for a live tuple with the given bytes, if anything.
+    fn emit(&self, data: &[u8]) -> Result<Option<Tuple>> {
+        match &self.filter {
+            Some(filter) => filter.apply(data),
+            None => Ok(Some(Tuple::new(Bytes::copy_from_slice(data)))),
         }
     }
 }
@@ -46,19 +120,45 @@ impl Iterator for TableTupleIterator {
     /// (The exception to this is an out-of-bounds error, which might signal that the current page
     /// doesn't have more tuples to emit and that the iterator should move to the next page.)
     fn next(&mut self) -> Option<Self::Item> {
+        let item = self.next_tuple();
+        if item.is_none() {
+            // Let writers in as soon as the scan is over.
+            self.guard.take();
+        }
+        item
+    }
+}
 
+impl TableTupleIterator {
+    fn next_tuple(&mut self) -> Option<Result<(rustdb_catalog::schema::RecordId, Tuple)>> {
         loop {
-            let page_frame_handle = match BufferPoolManager::fetch_page_handle(&self.bpm, self.current_page_id) {
+            if self.current_page_id == INVALID_PAGE_ID {
+                return None;
+            }
+            let page_frame_handle = match BufferPoolManager::fetch_page_handle(
+                &self.bpm,
+                self.current_page_id,
+                AccessType::Scan,
+            ) {
                 Ok(handle) => handle,
                 Err(e) => return Some(Err(e)),
             };
 
-            let table_page = TablePageRef::from(page_frame_handle);
+            let table_page = match TablePageRef::try_from(page_frame_handle) {
+                Ok(table_page) => table_page,
+                Err(e) => return Some(Err(e)),
+            };
 
             // get tuple
             let num_tuples_in_page = table_page.tuple_count();
             let slot_array = table_page.slot_array();
+            let mut unresolved = None;
 
+            // A page left out of the sample is skipped without looking at its tuples. Pages are
+            // only sampled on entry, not when the scan comes back to them after emitting a tuple.
+            if self.current_slot == 0 && !self.sampler.as_mut().is_none_or(Sampler::sample_page) {
+                self.current_slot = num_tuples_in_page;
+            }
 
             while self.current_slot < num_tuples_in_page {
                 let slot_id = self.current_slot;
@@ -68,18 +168,54 @@ impl Iterator for TableTupleIterator {
                 let record_id_clone = record_id.clone();
                 let packed: u64 = u64::from(record_id_clone);
 
-                match table_page.get_tuple(&record_id) {
-                    Ok((tuple_metadata, tuple)) => {
-                        if !tuple_metadata.is_deleted() {
-                            return Some(Ok((packed, tuple)));
+                match table_page.tuple_bytes(&record_id) {
+                    Ok((tuple_metadata, data)) => {
+                        // Relocated tuples are emitted through the slot forwarding to them.
+                        if tuple_metadata.is_deleted() || tuple_metadata.is_relocated() {
+                            continue;
+                        }
+                        if !self.sampler.as_mut().is_none_or(Sampler::sample_tuple) {
+                            continue;
+                        }
+                        if tuple_metadata.is_forwarded() || tuple_metadata.is_overflow() {
+                            let stub = Tuple::new(Bytes::copy_from_slice(data));
+                            unresolved = Some((packed, tuple_metadata, stub));
+                            break;
+                        }
+                        // Tuples that don't pass the filter are skipped without being copied.
+                        match self.emit(data) {
+                            Ok(Some(tuple)) => return Some(Ok((packed, tuple))),
+                            Ok(None) => continue,
+                            Err(e) => return Some(Err(e)),
                         }
                     }
                     Err(e) => return Some(Err(e)),
                 }
             }
 
+            if let Some((packed, tuple_metadata, stub)) = unresolved {
+                // The tuple lives in other pages; unpin this one first, the scan only needs one.
+                drop(table_page);
+                let tuple = match resolve_tuple(&self.bpm, tuple_metadata, stub, AccessType::Scan) {
+                    Ok(tuple) => tuple,
+                    Err(e) => return Some(Err(e)),
+                };
+                if self.filter.is_none() {
+                    return Some(Ok((packed, tuple)));
+                }
+                match self.emit(&tuple.data()) {
+                    Ok(Some(tuple)) => return Some(Ok((packed, tuple))),
+                    // Resume the scan after this slot.
+                    Ok(None) => continue,
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+
             // if we need next page
             self.current_page_id = table_page.next_page_id();
+            if self.current_page_id == self.stop_page_id {
+                self.current_page_id = INVALID_PAGE_ID;
+            }
             if self.current_page_id == INVALID_PAGE_ID {
                 return None;
             }
@@ -91,15 +227,11 @@ impl Iterator for TableTupleIterator {
 
 #[cfg(test)]
 mod tests {
-    use std::sync::{Arc, Mutex, RwLock};
+    use std::sync::{Arc, RwLock};
 
     use rustdb_catalog::tuple::Tuple;
 
-    use crate::{
-        buffer_pool::BufferPoolManager, disk::disk_manager::DiskManager,
-        heap::table_heap::TableHeap, replacer::lru_k_replacer::LrukReplacer,
-        Result,
-    };
+    use crate::{heap::table_heap::TableHeap, test_utils::get_bpm_arc_with_pool_size, Result};
 
     use super::TableTupleIterator;
 
@@ -107,9 +239,7 @@ mod tests {
     #[test]
     fn test_table_iterator() -> Result<()> {
         // Set up a test disk and buffer pool manager.
-        let disk = Arc::new(Mutex::new(DiskManager::new("test.db").unwrap()));
-        let replacer = Box::new(LrukReplacer::new(3));
-        let bpm = Arc::new(RwLock::new(BufferPoolManager::new(10, disk, replacer)));
+        let bpm = get_bpm_arc_with_pool_size(10);
 
         let mut table_heap = TableHeap::new("table", bpm.clone());
 
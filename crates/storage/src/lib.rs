@@ -1,5 +1,8 @@
 #![allow(dead_code)]
 pub(crate) mod buffer_pool;
+#[cfg(test)]
+mod conformance;
+pub(crate) mod directory;
 pub(crate) mod disk;
 pub(crate) mod frame;
 pub(crate) mod frame_handle;
@@ -9,5 +12,8 @@ pub(crate) mod page;
 pub(crate) mod record_id;
 pub(crate) mod replacer;
 pub mod storage;
+#[cfg(test)]
+mod test_utils;
+pub mod trace;
 pub(crate) mod typedef;
 pub(crate) type Result<T> = std::result::Result<T, rustdb_error::Error>;
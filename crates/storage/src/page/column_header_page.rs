@@ -0,0 +1,98 @@
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
+use bytemuck::{Pod, Zeroable};
+use rustdb_error::{errdata, errinput};
+
+use crate::frame::PageFrame;
+use crate::frame_handle::{PageFrameMutHandle, PageFrameRefHandle};
+use crate::page::page_header::{PageHeader, PageType};
+use crate::page::PAGE_SIZE;
+use crate::typedef::PageId;
+use crate::Result;
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+pub(crate) struct ColumnHeader {
+    common: PageHeader,
+    chain_cnt: u32,
+}
+
+pub(crate) const COLUMN_HEADER_SIZE: usize = mem::size_of::<ColumnHeader>();
+/// Most page chains a column store can have, so that their first pages fit in its header page.
+pub(crate) const MAX_CHAIN_CNT: usize = (PAGE_SIZE - COLUMN_HEADER_SIZE) / mem::size_of::<PageId>();
+
+/// The root page of a column store, holding the first page of each of its page chains, or
+/// `INVALID_PAGE_ID` for chains that have no pages yet.
+pub struct ColumnHeaderPage<T> {
+    page_frame_handle: T,
+}
+
+impl<T: Deref<Target = PageFrame>> ColumnHeaderPage<T> {
+    pub(crate) fn page_id(&self) -> PageId {
+        self.page_frame_handle.page_id()
+    }
+
+    pub(crate) fn header(&self) -> &ColumnHeader {
+        bytemuck::from_bytes(&self.page_frame_handle.data()[..COLUMN_HEADER_SIZE])
+    }
+
+    pub(crate) fn first_page_ids(&self) -> Result<Vec<PageId>> {
+        let chain_cnt = self.header().chain_cnt as usize;
+        if chain_cnt > MAX_CHAIN_CNT {
+            return errdata!(
+                "Column header page {} lists {} chains",
+                self.page_id(),
+                chain_cnt
+            );
+        }
+        let data = &self.page_frame_handle.data()[COLUMN_HEADER_SIZE..];
+        Ok(data
+            .chunks_exact(mem::size_of::<PageId>())
+            .take(chain_cnt)
+            .map(bytemuck::pod_read_unaligned)
+            .collect())
+    }
+}
+
+impl<T: DerefMut<Target = PageFrame> + Deref<Target = PageFrame>> ColumnHeaderPage<T> {
+    /// Records the first page of each chain, replacing what the page held.
+    pub(crate) fn init(&mut self, first_page_ids: &[PageId]) -> Result<()> {
+        if first_page_ids.len() > MAX_CHAIN_CNT {
+            return errinput!(
+                "A column store can't have more than {} columns",
+                MAX_CHAIN_CNT - 1
+            );
+        }
+        let header = ColumnHeader {
+            common: PageHeader::new(PageType::ColumnHeader),
+            chain_cnt: first_page_ids.len() as u32,
+        };
+        let page = self.page_frame_handle.data_mut();
+        page[..COLUMN_HEADER_SIZE].copy_from_slice(bytemuck::bytes_of(&header));
+        let page_ids: &[u8] = bytemuck::cast_slice(first_page_ids);
+        page[COLUMN_HEADER_SIZE..][..page_ids.len()].copy_from_slice(page_ids);
+        Ok(())
+    }
+}
+
+/// Type alias for immutable ColumnHeaderPage
+pub type ColumnHeaderPageRef<'a> = ColumnHeaderPage<PageFrameRefHandle<'a>>;
+/// Type alias for mutable ColumnHeaderPage
+pub type ColumnHeaderPageMut<'a> = ColumnHeaderPage<PageFrameMutHandle<'a>>;
+
+impl<'a> TryFrom<PageFrameRefHandle<'a>> for ColumnHeaderPageRef<'a> {
+    type Error = rustdb_error::Error;
+
+    fn try_from(page_frame_handle: PageFrameRefHandle<'a>) -> Result<Self> {
+        let page = ColumnHeaderPage { page_frame_handle };
+        page.header().common.validate(PageType::ColumnHeader)?;
+        Ok(page)
+    }
+}
+
+impl<'a> From<PageFrameMutHandle<'a>> for ColumnHeaderPageMut<'a> {
+    fn from(page_frame_handle: PageFrameMutHandle<'a>) -> Self {
+        ColumnHeaderPage { page_frame_handle }
+    }
+}
@@ -0,0 +1,122 @@
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
+use bytemuck::{Pod, Zeroable};
+use rustdb_catalog::types::Type;
+use rustdb_error::errdata;
+
+use crate::frame::PageFrame;
+use crate::frame_handle::{PageFrameMutHandle, PageFrameRefHandle};
+use crate::heap::column_encoding::{self, Encoding, Value};
+use crate::page::page_header::{PageHeader, PageType};
+use crate::page::PAGE_SIZE;
+use crate::typedef::PageId;
+use crate::Result;
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+pub(crate) struct ColumnPageHeader {
+    common: PageHeader,
+    next_page_id: PageId,
+    row_cnt: u32,
+    data_len: u32,
+    encoding: u8,
+    _padding: [u8; 3],
+}
+
+pub(crate) const COLUMN_PAGE_HEADER_SIZE: usize = mem::size_of::<ColumnPageHeader>();
+/// Number of bytes of encoded values a column page can hold.
+pub(crate) const COLUMN_PAGE_CAPACITY: usize = PAGE_SIZE - COLUMN_PAGE_HEADER_SIZE;
+
+/// A page of a column of a column store, holding the values of one column for a range of
+/// consecutive rows, encoded as one block (see [`Encoding`]). The pages of a column are chained
+/// through `next_page_id`, in row order.
+pub struct ColumnPage<T> {
+    page_frame_handle: T,
+}
+
+impl<T: Deref<Target = PageFrame>> ColumnPage<T> {
+    pub(crate) fn page_id(&self) -> PageId {
+        self.page_frame_handle.page_id()
+    }
+
+    pub(crate) fn next_page_id(&self) -> PageId {
+        self.header().next_page_id
+    }
+
+    pub(crate) fn header(&self) -> &ColumnPageHeader {
+        bytemuck::from_bytes(&self.page_frame_handle.data()[..COLUMN_PAGE_HEADER_SIZE])
+    }
+
+    /// Number of rows the page holds a value for.
+    pub(crate) fn row_count(&self) -> u32 {
+        self.header().row_cnt
+    }
+
+    pub(crate) fn encoding(&self) -> Result<Encoding> {
+        Encoding::try_from(self.header().encoding)
+    }
+
+    /// Decodes the page's values, which are of type `ty`.
+    pub(crate) fn values(&self, ty: Type) -> Result<Vec<Value>> {
+        let data_len = self.header().data_len as usize;
+        if data_len > COLUMN_PAGE_CAPACITY {
+            return errdata!("Column page {} holds {} bytes", self.page_id(), data_len);
+        }
+        let data = &self.page_frame_handle.data()[COLUMN_PAGE_HEADER_SIZE..][..data_len];
+        column_encoding::decode(ty, self.encoding()?, data, self.row_count() as usize)
+    }
+}
+
+impl<T: DerefMut<Target = PageFrame> + Deref<Target = PageFrame>> ColumnPage<T> {
+    pub(crate) fn header_mut(&mut self) -> &mut ColumnPageHeader {
+        bytemuck::from_bytes_mut(&mut self.page_frame_handle.data_mut()[..COLUMN_PAGE_HEADER_SIZE])
+    }
+
+    /// Stores the values of `row_cnt` rows, encoded with `encoding` into `data`, which must fit
+    /// in [`COLUMN_PAGE_CAPACITY`] bytes.
+    pub(crate) fn init(
+        &mut self,
+        next_page_id: PageId,
+        row_cnt: u32,
+        encoding: Encoding,
+        data: &[u8],
+    ) {
+        let header = ColumnPageHeader {
+            common: PageHeader::new(PageType::Column),
+            next_page_id,
+            row_cnt,
+            data_len: data.len() as u32,
+            encoding: encoding as u8,
+            _padding: [0; 3],
+        };
+        let page = self.page_frame_handle.data_mut();
+        page[..COLUMN_PAGE_HEADER_SIZE].copy_from_slice(bytemuck::bytes_of(&header));
+        page[COLUMN_PAGE_HEADER_SIZE..][..data.len()].copy_from_slice(data);
+    }
+
+    pub(crate) fn set_next_page_id(&mut self, next_page_id: PageId) {
+        self.header_mut().next_page_id = next_page_id;
+    }
+}
+
+/// Type alias for immutable ColumnPage
+pub type ColumnPageRef<'a> = ColumnPage<PageFrameRefHandle<'a>>;
+/// Type alias for mutable ColumnPage
+pub type ColumnPageMut<'a> = ColumnPage<PageFrameMutHandle<'a>>;
+
+impl<'a> TryFrom<PageFrameRefHandle<'a>> for ColumnPageRef<'a> {
+    type Error = rustdb_error::Error;
+
+    fn try_from(page_frame_handle: PageFrameRefHandle<'a>) -> Result<Self> {
+        let page = ColumnPage { page_frame_handle };
+        page.header().common.validate(PageType::Column)?;
+        Ok(page)
+    }
+}
+
+impl<'a> From<PageFrameMutHandle<'a>> for ColumnPageMut<'a> {
+    fn from(page_frame_handle: PageFrameMutHandle<'a>) -> Self {
+        ColumnPage { page_frame_handle }
+    }
+}
@@ -0,0 +1,94 @@
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::frame::PageFrame;
+use crate::frame_handle::{PageFrameMutHandle, PageFrameRefHandle};
+use crate::page::page_header::{PageHeader, PageType};
+use crate::page::PAGE_SIZE;
+use crate::typedef::PageId;
+use crate::Result;
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+pub(crate) struct FreeSpacePageHeader {
+    common: PageHeader,
+    next_page_id: PageId,
+}
+
+pub(crate) const FREE_SPACE_PAGE_HEADER_SIZE: usize = mem::size_of::<FreeSpacePageHeader>();
+/// Number of heap pages a single free space page keeps track of, one byte each.
+pub(crate) const FREE_SPACE_PAGE_CAPACITY: usize = PAGE_SIZE - FREE_SPACE_PAGE_HEADER_SIZE;
+
+/// A page of a table's free space map: the free space category of each of a range of
+/// [`FREE_SPACE_PAGE_CAPACITY`] consecutive heap page ids, indexed by their offset in the range.
+/// The pages of a free space map are chained together through `next_page_id`.
+pub struct FreeSpacePage<T> {
+    page_frame_handle: T,
+}
+
+impl<T: Deref<Target = PageFrame>> FreeSpacePage<T> {
+    pub(crate) fn page_id(&self) -> PageId {
+        self.page_frame_handle.page_id()
+    }
+
+    pub(crate) fn next_page_id(&self) -> PageId {
+        self.header().next_page_id
+    }
+
+    pub(crate) fn header(&self) -> &FreeSpacePageHeader {
+        bytemuck::from_bytes(&self.page_frame_handle.data()[..FREE_SPACE_PAGE_HEADER_SIZE])
+    }
+
+    /// Returns the category of every heap page of the page's range.
+    pub(crate) fn categories(&self) -> &[u8] {
+        &self.page_frame_handle.data()[FREE_SPACE_PAGE_HEADER_SIZE..]
+    }
+}
+
+impl<T: DerefMut<Target = PageFrame> + Deref<Target = PageFrame>> FreeSpacePage<T> {
+    pub(crate) fn header_mut(&mut self) -> &mut FreeSpacePageHeader {
+        bytemuck::from_bytes_mut(
+            &mut self.page_frame_handle.data_mut()[..FREE_SPACE_PAGE_HEADER_SIZE],
+        )
+    }
+
+    pub(crate) fn categories_mut(&mut self) -> &mut [u8] {
+        &mut self.page_frame_handle.data_mut()[FREE_SPACE_PAGE_HEADER_SIZE..]
+    }
+
+    /// Initializes the page with every heap page of its range recorded as having no free space.
+    pub(crate) fn init(&mut self, next_page_id: PageId) {
+        *self.header_mut() = FreeSpacePageHeader {
+            common: PageHeader::new(PageType::FreeSpace),
+            next_page_id,
+        };
+        self.categories_mut().fill(0);
+    }
+
+    pub(crate) fn set_next_page_id(&mut self, next_page_id: PageId) {
+        self.header_mut().next_page_id = next_page_id;
+    }
+}
+
+/// Type alias for immutable FreeSpacePage
+pub type FreeSpacePageRef<'a> = FreeSpacePage<PageFrameRefHandle<'a>>;
+/// Type alias for mutable FreeSpacePage
+pub type FreeSpacePageMut<'a> = FreeSpacePage<PageFrameMutHandle<'a>>;
+
+impl<'a> TryFrom<PageFrameRefHandle<'a>> for FreeSpacePageRef<'a> {
+    type Error = rustdb_error::Error;
+
+    fn try_from(page_frame_handle: PageFrameRefHandle<'a>) -> Result<Self> {
+        let page = FreeSpacePage { page_frame_handle };
+        page.header().common.validate(PageType::FreeSpace)?;
+        Ok(page)
+    }
+}
+
+impl<'a> From<PageFrameMutHandle<'a>> for FreeSpacePageMut<'a> {
+    fn from(page_frame_handle: PageFrameMutHandle<'a>) -> Self {
+        FreeSpacePage { page_frame_handle }
+    }
+}
@@ -0,0 +1,123 @@
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
+use bytemuck::{Pod, Zeroable};
+use rustdb_error::errinput;
+
+use crate::frame::PageFrame;
+use crate::frame_handle::{PageFrameMutHandle, PageFrameRefHandle};
+use crate::page::page_header::{PageHeader, PageType};
+use crate::page::PAGE_SIZE;
+use crate::typedef::PageId;
+use crate::Result;
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+pub(crate) struct HeapHeader {
+    common: PageHeader,
+    first_page_id: PageId,
+    last_page_id: PageId,
+    page_cnt: u32,
+    free_space_map_page_id: PageId,
+    name_len: u32,
+}
+
+pub(crate) const HEAP_HEADER_SIZE: usize = mem::size_of::<HeapHeader>();
+/// Longest table name a heap header page can store.
+pub(crate) const MAX_TABLE_NAME_LEN: usize = PAGE_SIZE - HEAP_HEADER_SIZE;
+
+/// The root page of a table heap. It records where the heap's page chain starts and ends, how
+/// many pages it has, and where its free space map lives, so that the heap can be reopened from
+/// this page alone. The table's name is stored after the header.
+pub struct HeapHeaderPage<T> {
+    page_frame_handle: T,
+}
+
+impl<T: Deref<Target = PageFrame>> HeapHeaderPage<T> {
+    pub(crate) fn page_id(&self) -> PageId {
+        self.page_frame_handle.page_id()
+    }
+
+    pub(crate) fn header(&self) -> &HeapHeader {
+        bytemuck::from_bytes(&self.page_frame_handle.data()[..HEAP_HEADER_SIZE])
+    }
+
+    pub(crate) fn first_page_id(&self) -> PageId {
+        self.header().first_page_id
+    }
+
+    pub(crate) fn last_page_id(&self) -> PageId {
+        self.header().last_page_id
+    }
+
+    pub(crate) fn page_count(&self) -> u32 {
+        self.header().page_cnt
+    }
+
+    pub(crate) fn free_space_map_page_id(&self) -> PageId {
+        self.header().free_space_map_page_id
+    }
+
+    pub(crate) fn table_name(&self) -> String {
+        let name_len = self.header().name_len as usize;
+        let name = &self.page_frame_handle.data()[HEAP_HEADER_SIZE..][..name_len];
+        String::from_utf8_lossy(name).into_owned()
+    }
+}
+
+impl<T: DerefMut<Target = PageFrame> + Deref<Target = PageFrame>> HeapHeaderPage<T> {
+    pub(crate) fn header_mut(&mut self) -> &mut HeapHeader {
+        bytemuck::from_bytes_mut(&mut self.page_frame_handle.data_mut()[..HEAP_HEADER_SIZE])
+    }
+
+    /// Initializes the page for a heap named `table_name` whose only page is `first_page_id`.
+    pub(crate) fn init(
+        &mut self,
+        table_name: &str,
+        first_page_id: PageId,
+        free_space_map_page_id: PageId,
+    ) -> Result<()> {
+        if table_name.len() > MAX_TABLE_NAME_LEN {
+            return errinput!("Table name is longer than {} bytes", MAX_TABLE_NAME_LEN);
+        }
+        *self.header_mut() = HeapHeader {
+            common: PageHeader::new(PageType::HeapHeader),
+            first_page_id,
+            last_page_id: first_page_id,
+            page_cnt: 1,
+            free_space_map_page_id,
+            name_len: table_name.len() as u32,
+        };
+        self.page_frame_handle.data_mut()[HEAP_HEADER_SIZE..][..table_name.len()]
+            .copy_from_slice(table_name.as_bytes());
+        Ok(())
+    }
+
+    /// Records that the heap's page chain now ends at `last_page_id` and has `page_cnt` pages.
+    pub(crate) fn set_last_page(&mut self, last_page_id: PageId, page_cnt: u32) {
+        let header = self.header_mut();
+        header.last_page_id = last_page_id;
+        header.page_cnt = page_cnt;
+    }
+}
+
+/// Type alias for immutable HeapHeaderPage
+pub type HeapHeaderPageRef<'a> = HeapHeaderPage<PageFrameRefHandle<'a>>;
+/// Type alias for mutable HeapHeaderPage
+pub type HeapHeaderPageMut<'a> = HeapHeaderPage<PageFrameMutHandle<'a>>;
+
+impl<'a> TryFrom<PageFrameRefHandle<'a>> for HeapHeaderPageRef<'a> {
+    type Error = rustdb_error::Error;
+
+    fn try_from(page_frame_handle: PageFrameRefHandle<'a>) -> Result<Self> {
+        let page = HeapHeaderPage { page_frame_handle };
+        page.header().common.validate(PageType::HeapHeader)?;
+        Ok(page)
+    }
+}
+
+impl<'a> From<PageFrameMutHandle<'a>> for HeapHeaderPageMut<'a> {
+    fn from(page_frame_handle: PageFrameMutHandle<'a>) -> Self {
+        HeapHeaderPage { page_frame_handle }
+    }
+}
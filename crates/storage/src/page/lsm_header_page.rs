@@ -0,0 +1,108 @@
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
+use bytemuck::{Pod, Zeroable};
+use rustdb_error::{errdata, errinput};
+
+use crate::frame::PageFrame;
+use crate::frame_handle::{PageFrameMutHandle, PageFrameRefHandle};
+use crate::page::page_header::{PageHeader, PageType};
+use crate::page::PAGE_SIZE;
+use crate::typedef::PageId;
+use crate::Result;
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+pub(crate) struct LsmHeader {
+    common: PageHeader,
+    /// Stored as bytes, like the LSN of [`PageHeader`], so the header only needs 4-byte alignment.
+    next_record_id: [u8; 8],
+    run_cnt: u32,
+}
+
+/// Where a sorted run is: the level it belongs to and the first page of its chain.
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct RunLocation {
+    pub(crate) level: u32,
+    pub(crate) first_page_id: PageId,
+}
+
+pub(crate) const LSM_HEADER_SIZE: usize = mem::size_of::<LsmHeader>();
+const RUN_LOCATION_SIZE: usize = mem::size_of::<RunLocation>();
+/// Most runs an LSM tree can have, so that their locations fit in its header page.
+pub(crate) const MAX_RUN_CNT: usize = (PAGE_SIZE - LSM_HEADER_SIZE) / RUN_LOCATION_SIZE;
+
+/// The root page of an LSM tree, listing its sorted runs level by level along with the record id
+/// its next tuple gets, so that the tree can be reopened from this page alone. Runs are listed
+/// from newest to oldest.
+pub struct LsmHeaderPage<T> {
+    page_frame_handle: T,
+}
+
+impl<T: Deref<Target = PageFrame>> LsmHeaderPage<T> {
+    pub(crate) fn page_id(&self) -> PageId {
+        self.page_frame_handle.page_id()
+    }
+
+    pub(crate) fn header(&self) -> &LsmHeader {
+        bytemuck::from_bytes(&self.page_frame_handle.data()[..LSM_HEADER_SIZE])
+    }
+
+    pub(crate) fn next_record_id(&self) -> u64 {
+        u64::from_le_bytes(self.header().next_record_id)
+    }
+
+    pub(crate) fn runs(&self) -> Result<Vec<RunLocation>> {
+        let run_cnt = self.header().run_cnt as usize;
+        if run_cnt > MAX_RUN_CNT {
+            return errdata!("LSM header page {} lists {} runs", self.page_id(), run_cnt);
+        }
+        let data = &self.page_frame_handle.data()[LSM_HEADER_SIZE..];
+        Ok(data
+            .chunks_exact(RUN_LOCATION_SIZE)
+            .take(run_cnt)
+            .map(bytemuck::pod_read_unaligned)
+            .collect())
+    }
+}
+
+impl<T: DerefMut<Target = PageFrame> + Deref<Target = PageFrame>> LsmHeaderPage<T> {
+    /// Records the tree's runs and the record id of its next tuple, replacing what the page held.
+    pub(crate) fn init(&mut self, next_record_id: u64, runs: &[RunLocation]) -> Result<()> {
+        if runs.len() > MAX_RUN_CNT {
+            return errinput!("An LSM tree can't have more than {} runs", MAX_RUN_CNT);
+        }
+        let header = LsmHeader {
+            common: PageHeader::new(PageType::LsmHeader),
+            next_record_id: next_record_id.to_le_bytes(),
+            run_cnt: runs.len() as u32,
+        };
+        let page = self.page_frame_handle.data_mut();
+        page[..LSM_HEADER_SIZE].copy_from_slice(bytemuck::bytes_of(&header));
+        let locations: &[u8] = bytemuck::cast_slice(runs);
+        page[LSM_HEADER_SIZE..][..locations.len()].copy_from_slice(locations);
+        Ok(())
+    }
+}
+
+/// Type alias for immutable LsmHeaderPage
+pub type LsmHeaderPageRef<'a> = LsmHeaderPage<PageFrameRefHandle<'a>>;
+/// Type alias for mutable LsmHeaderPage
+pub type LsmHeaderPageMut<'a> = LsmHeaderPage<PageFrameMutHandle<'a>>;
+
+impl<'a> TryFrom<PageFrameRefHandle<'a>> for LsmHeaderPageRef<'a> {
+    type Error = rustdb_error::Error;
+
+    fn try_from(page_frame_handle: PageFrameRefHandle<'a>) -> Result<Self> {
+        let page = LsmHeaderPage { page_frame_handle };
+        page.header().common.validate(PageType::LsmHeader)?;
+        Ok(page)
+    }
+}
+
+impl<'a> From<PageFrameMutHandle<'a>> for LsmHeaderPageMut<'a> {
+    fn from(page_frame_handle: PageFrameMutHandle<'a>) -> Self {
+        LsmHeaderPage { page_frame_handle }
+    }
+}
@@ -1,4 +1,13 @@
 use crate::typedef::PageId;
+pub(crate) mod column_header_page;
+pub(crate) mod column_page;
+pub(crate) mod free_space_page;
+pub(crate) mod heap_header_page;
+pub(crate) mod lsm_header_page;
+pub(crate) mod overflow_page;
+pub(crate) mod page_header;
+pub(crate) mod pax_page;
+pub(crate) mod run_page;
 pub(crate) mod table_page;
 
 pub(crate) const INVALID_PAGE_ID: PageId = 0;
@@ -0,0 +1,235 @@
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, RwLock};
+
+use bytemuck::{Pod, Zeroable};
+use bytes::{Bytes, BytesMut};
+use rustdb_catalog::tuple::Tuple;
+use rustdb_error::errdata;
+
+use crate::buffer_pool::BufferPoolManager;
+use crate::frame::PageFrame;
+use crate::frame_handle::{PageFrameMutHandle, PageFrameRefHandle};
+use crate::page::page_header::{PageHeader, PageType};
+use crate::page::{INVALID_PAGE_ID, PAGE_SIZE};
+use crate::replacer::replacer::AccessType;
+use crate::typedef::PageId;
+use crate::Result;
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+pub(crate) struct OverflowPageHeader {
+    common: PageHeader,
+    next_page_id: PageId,
+    data_len: u32,
+}
+
+pub(crate) const OVERFLOW_PAGE_HEADER_SIZE: usize = mem::size_of::<OverflowPageHeader>();
+/// Number of tuple bytes stored in each overflow page.
+pub(crate) const OVERFLOW_PAGE_CAPACITY: usize = PAGE_SIZE - OVERFLOW_PAGE_HEADER_SIZE;
+
+/// A page holding one chunk of a tuple too large for a table page. The chunks of a tuple are
+/// chained together through `next_page_id`.
+pub struct OverflowPage<T> {
+    page_frame_handle: T,
+}
+
+impl<T: Deref<Target = PageFrame>> OverflowPage<T> {
+    pub(crate) fn page_id(&self) -> PageId {
+        self.page_frame_handle.page_id()
+    }
+
+    pub(crate) fn next_page_id(&self) -> PageId {
+        self.header().next_page_id
+    }
+
+    pub(crate) fn header(&self) -> &OverflowPageHeader {
+        bytemuck::from_bytes(&self.page_frame_handle.data()[..OVERFLOW_PAGE_HEADER_SIZE])
+    }
+
+    /// Returns the chunk of tuple data stored in this page.
+    pub(crate) fn data(&self) -> &[u8] {
+        let data_len = self.header().data_len as usize;
+        &self.page_frame_handle.data()[OVERFLOW_PAGE_HEADER_SIZE..][..data_len]
+    }
+}
+
+impl<T: DerefMut<Target = PageFrame> + Deref<Target = PageFrame>> OverflowPage<T> {
+    /// Stores `data`, which must fit in [`OVERFLOW_PAGE_CAPACITY`] bytes, and links the page to
+    /// the next one in the chain.
+    pub(crate) fn init(&mut self, next_page_id: PageId, data: &[u8]) {
+        let header = OverflowPageHeader {
+            common: PageHeader::new(PageType::Overflow),
+            next_page_id,
+            data_len: data.len() as u32,
+        };
+        let page = self.page_frame_handle.data_mut();
+        page[..OVERFLOW_PAGE_HEADER_SIZE].copy_from_slice(bytemuck::bytes_of(&header));
+        page[OVERFLOW_PAGE_HEADER_SIZE..][..data.len()].copy_from_slice(data);
+    }
+}
+
+/// Type alias for immutable OverflowPage
+pub type OverflowPageRef<'a> = OverflowPage<PageFrameRefHandle<'a>>;
+/// Type alias for mutable OverflowPage
+pub type OverflowPageMut<'a> = OverflowPage<PageFrameMutHandle<'a>>;
+
+impl<'a> TryFrom<PageFrameRefHandle<'a>> for OverflowPageRef<'a> {
+    type Error = rustdb_error::Error;
+
+    fn try_from(page_frame_handle: PageFrameRefHandle<'a>) -> Result<Self> {
+        let page = OverflowPage { page_frame_handle };
+        page.header().common.validate(PageType::Overflow)?;
+        Ok(page)
+    }
+}
+
+impl<'a> From<PageFrameMutHandle<'a>> for OverflowPageMut<'a> {
+    fn from(page_frame_handle: PageFrameMutHandle<'a>) -> Self {
+        OverflowPage { page_frame_handle }
+    }
+}
+
+/// What a table page slot stores in place of a tuple that was spilled to overflow pages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct OverflowPointer {
+    pub(crate) first_page_id: PageId,
+    pub(crate) size: u32,
+}
+
+impl OverflowPointer {
+    /// Size of an encoded pointer, which fits in the space every table page slot owns.
+    pub(crate) const SIZE: usize = 2 * mem::size_of::<u32>();
+
+    pub(crate) fn to_tuple(self) -> Tuple {
+        let mut data = Vec::with_capacity(Self::SIZE);
+        data.extend_from_slice(&self.first_page_id.to_le_bytes());
+        data.extend_from_slice(&self.size.to_le_bytes());
+        Tuple::new(data.into())
+    }
+
+    pub(crate) fn from_tuple(stub: &Tuple) -> Self {
+        let data = stub.data();
+        Self {
+            first_page_id: PageId::from_le_bytes(data[..4].try_into().unwrap()),
+            size: u32::from_le_bytes(data[4..Self::SIZE].try_into().unwrap()),
+        }
+    }
+}
+
+/// Writes `data` to a new chain of overflow pages and returns a pointer to it.
+pub(crate) fn write_overflow_chain(
+    bpm: &Arc<RwLock<BufferPoolManager>>,
+    data: &[u8],
+) -> Result<OverflowPointer> {
+    // Write the chunks back to front, so that each page can be linked to its successor right away.
+    let mut next_page_id = INVALID_PAGE_ID;
+    for chunk in data.chunks(OVERFLOW_PAGE_CAPACITY).rev() {
+        let mut page = OverflowPageMut::from(BufferPoolManager::create_page_handle(bpm)?);
+        page.init(next_page_id, chunk);
+        next_page_id = page.page_id();
+    }
+    Ok(OverflowPointer {
+        first_page_id: next_page_id,
+        size: data.len() as u32,
+    })
+}
+
+/// Reads the tuple stored in the overflow chain `pointer` points to.
+pub(crate) fn read_overflow_chain(
+    bpm: &Arc<RwLock<BufferPoolManager>>,
+    pointer: OverflowPointer,
+    access_type: AccessType,
+) -> Result<Tuple> {
+    let mut data = BytesMut::with_capacity(pointer.size as usize);
+    let mut page_id = pointer.first_page_id;
+    while page_id != INVALID_PAGE_ID {
+        let page = OverflowPageRef::try_from(BufferPoolManager::fetch_page_handle(
+            bpm,
+            page_id,
+            access_type,
+        )?)?;
+        data.extend_from_slice(page.data());
+        page_id = page.next_page_id();
+    }
+
+    if data.len() != pointer.size as usize {
+        return errdata!(
+            "Overflow chain at page {} holds {} bytes, expected {}",
+            pointer.first_page_id,
+            data.len(),
+            pointer.size
+        );
+    }
+    Ok(Tuple::new(Bytes::from(data)))
+}
+
+/// Returns the ids of the pages of the overflow chain `pointer` points to.
+pub(crate) fn overflow_chain_page_ids(
+    bpm: &Arc<RwLock<BufferPoolManager>>,
+    pointer: OverflowPointer,
+) -> Result<Vec<PageId>> {
+    let mut page_ids = Vec::new();
+    let mut page_id = pointer.first_page_id;
+    while page_id != INVALID_PAGE_ID {
+        page_ids.push(page_id);
+        page_id = OverflowPageRef::try_from(BufferPoolManager::fetch_page_handle(
+            bpm,
+            page_id,
+            AccessType::Unknown,
+        )?)?
+        .next_page_id();
+    }
+    Ok(page_ids)
+}
+
+/// Deletes every page of the overflow chain `pointer` points to.
+pub(crate) fn free_overflow_chain(
+    bpm: &Arc<RwLock<BufferPoolManager>>,
+    pointer: OverflowPointer,
+) -> Result<()> {
+    let mut page_id = pointer.first_page_id;
+    while page_id != INVALID_PAGE_ID {
+        let next_page_id = {
+            let page = OverflowPageRef::try_from(BufferPoolManager::fetch_page_handle(
+                bpm,
+                page_id,
+                AccessType::Unknown,
+            )?)?;
+            page.next_page_id()
+        };
+        bpm.write().unwrap().delete_page(page_id)?;
+        page_id = next_page_id;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+    use crate::test_utils::get_bpm_arc_with_pool_size;
+
+    #[test]
+    #[serial]
+    fn test_overflow_chain() -> Result<()> {
+        // The pool is smaller than the chain, so pages have to be evicted and read back.
+        let bpm = get_bpm_arc_with_pool_size(2);
+        let data: Vec<u8> = (0..3 * OVERFLOW_PAGE_CAPACITY + 100)
+            .map(|i| (i % 251) as u8)
+            .collect();
+
+        let pointer = write_overflow_chain(&bpm, &data)?;
+        assert_eq!(data.len(), pointer.size as usize);
+        assert_eq!(pointer, OverflowPointer::from_tuple(&pointer.to_tuple()));
+
+        let tuple = read_overflow_chain(&bpm, pointer, AccessType::Lookup)?;
+        assert_eq!(data, tuple.data().to_vec());
+
+        free_overflow_chain(&bpm, pointer)?;
+        assert_eq!(2, bpm.read().unwrap().free_frame_count());
+
+        Ok(())
+    }
+}
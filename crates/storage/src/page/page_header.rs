@@ -0,0 +1,149 @@
+use std::mem;
+
+use bytemuck::{Pod, Zeroable};
+use rustdb_error::errdata;
+
+use crate::Result;
+
+/// Marks a page as written by rustdb, so that garbage or zeroed pages are caught on read.
+pub(crate) const PAGE_MAGIC: u32 = u32::from_le_bytes(*b"RSDB");
+/// Version of the on-disk page format. Bump it whenever a page layout changes.
+pub(crate) const PAGE_FORMAT_VERSION: u8 = 1;
+
+/// The kind of data a page holds.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PageType {
+    Table = 1,
+    Overflow = 2,
+    FreeSpace = 3,
+    Pax = 4,
+    HeapHeader = 5,
+    Run = 6,
+    LsmHeader = 7,
+    Column = 8,
+    ColumnHeader = 9,
+}
+
+impl TryFrom<u8> for PageType {
+    type Error = rustdb_error::Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            1 => Ok(PageType::Table),
+            2 => Ok(PageType::Overflow),
+            3 => Ok(PageType::FreeSpace),
+            4 => Ok(PageType::Pax),
+            5 => Ok(PageType::HeapHeader),
+            6 => Ok(PageType::Run),
+            7 => Ok(PageType::LsmHeader),
+            8 => Ok(PageType::Column),
+            9 => Ok(PageType::ColumnHeader),
+            _ => errdata!("Invalid page type: {}", value),
+        }
+    }
+}
+
+/// The header every page starts with, in front of the header of its page type. It makes a page
+/// self-describing: a reader can tell what the page holds and which format it was written in
+/// without knowing where the page came from.
+///
+/// The LSN and checksum are reserved for logging and torn-page detection; nothing fills them in
+/// yet. The LSN is stored as bytes so that the header only needs 4-byte alignment, like the page
+/// type headers that embed it.
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone, Debug)]
+pub(crate) struct PageHeader {
+    magic: u32,
+    page_type: u8,
+    version: u8,
+    _padding: [u8; 2],
+    lsn: [u8; 8],
+    checksum: u32,
+}
+
+pub(crate) const PAGE_HEADER_SIZE: usize = mem::size_of::<PageHeader>();
+
+impl PageHeader {
+    pub(crate) fn new(page_type: PageType) -> Self {
+        Self {
+            magic: PAGE_MAGIC,
+            page_type: page_type as u8,
+            version: PAGE_FORMAT_VERSION,
+            _padding: [0; 2],
+            lsn: [0; 8],
+            checksum: 0,
+        }
+    }
+
+    pub(crate) fn page_type(&self) -> Result<PageType> {
+        PageType::try_from(self.page_type)
+    }
+
+    pub(crate) fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub(crate) fn lsn(&self) -> u64 {
+        u64::from_le_bytes(self.lsn)
+    }
+
+    pub(crate) fn set_lsn(&mut self, lsn: u64) {
+        self.lsn = lsn.to_le_bytes();
+    }
+
+    pub(crate) fn checksum(&self) -> u32 {
+        self.checksum
+    }
+
+    pub(crate) fn set_checksum(&mut self, checksum: u32) {
+        self.checksum = checksum;
+    }
+
+    /// Checks that the header belongs to a page of type `expected`, in a format we can read.
+    pub(crate) fn validate(&self, expected: PageType) -> Result<()> {
+        if self.magic != PAGE_MAGIC {
+            return errdata!("Invalid page magic: {:#010x}", self.magic);
+        }
+        if self.version != PAGE_FORMAT_VERSION {
+            return errdata!("Unsupported page format version: {}", self.version);
+        }
+        let page_type = self.page_type()?;
+        if page_type != expected {
+            return errdata!(
+                "Expected a {:?} page, found a {:?} page",
+                expected,
+                page_type
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Reads the common header at the start of a page's data.
+pub(crate) fn read_page_header(data: &[u8]) -> &PageHeader {
+    bytemuck::from_bytes(&data[..PAGE_HEADER_SIZE])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_header_validate() {
+        let mut header = PageHeader::new(PageType::Table);
+        assert!(header.validate(PageType::Table).is_ok());
+        assert!(header.validate(PageType::Overflow).is_err());
+
+        header.set_lsn(42);
+        assert_eq!(42, header.lsn());
+
+        let mut data = [0u8; 64];
+        data[..PAGE_HEADER_SIZE].copy_from_slice(bytemuck::bytes_of(&header));
+        assert_eq!(42, read_page_header(&data).lsn());
+
+        // A zeroed page is not a valid page of any type.
+        let zeroed = [0u8; PAGE_HEADER_SIZE];
+        assert!(read_page_header(&zeroed).validate(PageType::Table).is_err());
+    }
+}
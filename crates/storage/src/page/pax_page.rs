@@ -0,0 +1,364 @@
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
+use bytemuck::{Pod, Zeroable};
+use rustdb_catalog::types::Type;
+use rustdb_error::{errinput, Error};
+
+use crate::frame::PageFrame;
+use crate::frame_handle::{PageFrameMutHandle, PageFrameRefHandle};
+use crate::page::page_header::{PageHeader, PageType};
+use crate::page::PAGE_SIZE;
+use crate::record_id::RecordId;
+use crate::typedef::PageId;
+use crate::Result;
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+pub(crate) struct PaxPageHeader {
+    common: PageHeader,
+    next_page_id: PageId,
+    tuple_cnt: u32,
+    capacity: u32,
+    column_cnt: u32,
+    minipages_end: u32,
+    var_data_start: u32,
+}
+
+/// Where a column's minipage lives in the page, and how wide each of its values is.
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone, Debug)]
+pub(crate) struct MinipageInfo {
+    offset: u16,
+    width: u8,
+    is_var: u8,
+}
+
+pub(crate) const PAX_PAGE_HEADER_SIZE: usize = mem::size_of::<PaxPageHeader>();
+pub(crate) const MINIPAGE_INFO_SIZE: usize = mem::size_of::<MinipageInfo>();
+
+/// Variable-length values are stored at the end of the page. Their minipage holds an entry per
+/// tuple with the value's offset and length, both `u16`.
+const VAR_ENTRY_SIZE: usize = 2 * mem::size_of::<u16>();
+
+/// Number of bytes of variable-length data a page reserves per value when deciding how many
+/// tuples it holds. Pages with longer values fill up before all of their rows are used.
+pub(crate) const PAX_VAR_VALUE_ESTIMATE: usize = 32;
+
+/// Returns the width of a value of type `ty` in its minipage.
+fn minipage_width(ty: Type) -> usize {
+    match ty {
+        Type::Varchar => VAR_ENTRY_SIZE,
+        ty => ty.size(),
+    }
+}
+
+/// Returns the number of tuples a PAX page for columns of type `column_types` can hold: every
+/// tuple takes a deleted flag, its fixed-size values, and an estimate of its variable-length data.
+pub(crate) fn pax_page_capacity(column_types: &[Type]) -> usize {
+    let row_size: usize = 1 + column_types
+        .iter()
+        .map(|&ty| match ty {
+            Type::Varchar => VAR_ENTRY_SIZE + PAX_VAR_VALUE_ESTIMATE,
+            ty => minipage_width(ty),
+        })
+        .sum::<usize>();
+    (PAGE_SIZE - PAX_PAGE_HEADER_SIZE - column_types.len() * MINIPAGE_INFO_SIZE) / row_size
+}
+
+/// A page in the PAX (Partition Attributes Across) layout. Instead of storing each tuple's bytes
+/// together, the page is split into one minipage per column, each holding that column's values
+/// for all of the page's tuples. A scan that only needs a few columns thus only touches their
+/// minipages, while a tuple still lives in a single page.
+///
+/// ```text
+/// | header | minipage directory | deleted flags | minipage 0 | ... | minipage n | free | var data |
+/// ```
+///
+/// Minipages are sized for the page's capacity when the page is initialized, so the fixed-size
+/// part of a tuple always has a place; only variable-length values compete for the free space.
+pub struct PaxPage<T> {
+    page_frame_handle: T,
+}
+
+impl<T: Deref<Target = PageFrame>> PaxPage<T> {
+    pub(crate) fn page_id(&self) -> PageId {
+        self.page_frame_handle.page_id()
+    }
+
+    pub(crate) fn next_page_id(&self) -> PageId {
+        self.header().next_page_id
+    }
+
+    pub(crate) fn tuple_count(&self) -> u32 {
+        self.header().tuple_cnt
+    }
+
+    pub(crate) fn capacity(&self) -> u32 {
+        self.header().capacity
+    }
+
+    pub(crate) fn column_count(&self) -> usize {
+        self.header().column_cnt as usize
+    }
+
+    pub(crate) fn header(&self) -> &PaxPageHeader {
+        bytemuck::from_bytes(&self.page_frame_handle.data()[..PAX_PAGE_HEADER_SIZE])
+    }
+
+    pub(crate) fn minipages(&self) -> &[MinipageInfo] {
+        let directory_end = PAX_PAGE_HEADER_SIZE + self.column_count() * MINIPAGE_INFO_SIZE;
+        bytemuck::cast_slice(&self.page_frame_handle.data()[PAX_PAGE_HEADER_SIZE..directory_end])
+    }
+
+    /// Returns the offset of the deleted flags, which come right after the minipage directory.
+    fn deleted_flags_offset(&self) -> usize {
+        PAX_PAGE_HEADER_SIZE + self.column_count() * MINIPAGE_INFO_SIZE
+    }
+
+    pub(crate) fn is_deleted(&self, rid: &RecordId) -> Result<bool> {
+        self.validate_record_id(rid)?;
+        let deleted_flag = self.deleted_flags_offset() + rid.slot_id() as usize;
+        Ok(self.page_frame_handle.data()[deleted_flag] != 0)
+    }
+
+    /// Returns the bytes of column `column` of the tuple at `rid`, in the format of
+    /// [`rustdb_catalog::field::Field::to_bytes`].
+    pub(crate) fn get_value(&self, rid: &RecordId, column: usize) -> Result<&[u8]> {
+        self.validate_record_id(rid)?;
+        let minipage = self.minipage(column)?;
+        let data = self.page_frame_handle.data();
+        let start = minipage.offset as usize + rid.slot_id() as usize * minipage.width as usize;
+        let value = &data[start..start + minipage.width as usize];
+        if minipage.is_var == 0 {
+            return Ok(value);
+        }
+        let offset = u16::from_le_bytes([value[0], value[1]]) as usize;
+        let len = u16::from_le_bytes([value[2], value[3]]) as usize;
+        Ok(&data[offset..offset + len])
+    }
+
+    /// Returns the number of bytes left for variable-length values.
+    pub(crate) fn free_space(&self) -> usize {
+        let header = self.header();
+        (header.var_data_start - header.minipages_end) as usize
+    }
+
+    fn minipage(&self, column: usize) -> Result<MinipageInfo> {
+        self.minipages()
+            .get(column)
+            .copied()
+            .ok_or_else(|| Error::InvalidInput(format!("Invalid column index {}", column)))
+    }
+
+    fn validate_record_id(&self, rid: &RecordId) -> Result<()> {
+        if rid.page_id() != self.page_id() || rid.slot_id() >= self.tuple_count() {
+            Err(Error::InvalidInput(rid.to_string()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the number of variable-length bytes storing `values` takes.
+    fn var_data_size(&self, values: &[Vec<u8>]) -> Result<usize> {
+        if values.len() != self.column_count() {
+            return errinput!(
+                "Expected {} column values, got {}",
+                self.column_count(),
+                values.len()
+            );
+        }
+        let mut size = 0;
+        for (minipage, value) in self.minipages().iter().zip(values) {
+            if minipage.is_var != 0 {
+                size += value.len();
+            } else if value.len() != minipage.width as usize {
+                return errinput!(
+                    "Expected a {}-byte value, got {} bytes",
+                    minipage.width,
+                    value.len()
+                );
+            }
+        }
+        Ok(size)
+    }
+}
+
+impl<T: DerefMut<Target = PageFrame> + Deref<Target = PageFrame>> PaxPage<T> {
+    pub(crate) fn header_mut(&mut self) -> &mut PaxPageHeader {
+        bytemuck::from_bytes_mut(&mut self.page_frame_handle.data_mut()[..PAX_PAGE_HEADER_SIZE])
+    }
+
+    /// Lays the page out for tuples whose columns have types `column_types`.
+    pub(crate) fn init(&mut self, next_page_id: PageId, column_types: &[Type]) {
+        let capacity = pax_page_capacity(column_types);
+        let directory_end = PAX_PAGE_HEADER_SIZE + column_types.len() * MINIPAGE_INFO_SIZE;
+
+        // The deleted flags come first, followed by one minipage per column.
+        let mut offset = directory_end + capacity;
+        let minipages: Vec<MinipageInfo> = column_types
+            .iter()
+            .map(|&ty| {
+                let width = minipage_width(ty);
+                let minipage = MinipageInfo {
+                    offset: offset as u16,
+                    width: width as u8,
+                    is_var: (ty == Type::Varchar) as u8,
+                };
+                offset += width * capacity;
+                minipage
+            })
+            .collect();
+
+        *self.header_mut() = PaxPageHeader {
+            common: PageHeader::new(PageType::Pax),
+            next_page_id,
+            tuple_cnt: 0,
+            capacity: capacity as u32,
+            column_cnt: column_types.len() as u32,
+            minipages_end: offset as u32,
+            var_data_start: PAGE_SIZE as u32,
+        };
+        let data = self.page_frame_handle.data_mut();
+        data[PAX_PAGE_HEADER_SIZE..directory_end].copy_from_slice(bytemuck::cast_slice(&minipages));
+        data[directory_end..directory_end + capacity].fill(0);
+    }
+
+    pub(crate) fn set_next_page_id(&mut self, next_page_id: PageId) {
+        self.header_mut().next_page_id = next_page_id;
+    }
+
+    /// Appends a tuple given as one value per column, returning its record id. Returns
+    /// `Error::OutOfBounds` if the page has no row left or no room for the variable-length values.
+    pub(crate) fn insert_tuple(&mut self, values: &[Vec<u8>]) -> Result<RecordId> {
+        let var_data_size = self.var_data_size(values)?;
+        if self.tuple_count() >= self.capacity() || self.free_space() < var_data_size {
+            return Err(Error::OutOfBounds);
+        }
+
+        let slot_id = self.tuple_count();
+        self.header_mut().tuple_cnt += 1;
+        let rid = RecordId::new(self.page_id(), slot_id);
+        let deleted_flag = self.deleted_flags_offset() + slot_id as usize;
+        self.page_frame_handle.data_mut()[deleted_flag] = 0;
+        self.write_values(&rid, values);
+        Ok(rid)
+    }
+
+    /// Overwrites the values of the tuple at `rid`. Fixed-size values are replaced in place, while
+    /// variable-length values are written to the page's free space; returns `Error::OutOfBounds`
+    /// without changing the tuple if they don't fit.
+    pub(crate) fn update_tuple(&mut self, rid: &RecordId, values: &[Vec<u8>]) -> Result<()> {
+        self.validate_record_id(rid)?;
+        if self.free_space() < self.var_data_size(values)? {
+            return Err(Error::OutOfBounds);
+        }
+        self.write_values(rid, values);
+        Ok(())
+    }
+
+    pub(crate) fn set_deleted(&mut self, rid: &RecordId, deleted: bool) -> Result<()> {
+        self.validate_record_id(rid)?;
+        let deleted_flag = self.deleted_flags_offset() + rid.slot_id() as usize;
+        self.page_frame_handle.data_mut()[deleted_flag] = deleted as u8;
+        Ok(())
+    }
+
+    /// Writes each value to its minipage, which the caller must have checked has room.
+    fn write_values(&mut self, rid: &RecordId, values: &[Vec<u8>]) {
+        let minipages = self.minipages().to_vec();
+        for (minipage, value) in minipages.iter().zip(values) {
+            let width = minipage.width as usize;
+            let start = minipage.offset as usize + rid.slot_id() as usize * width;
+            if minipage.is_var == 0 {
+                self.page_frame_handle.data_mut()[start..start + width].copy_from_slice(value);
+                continue;
+            }
+
+            let var_data_start = self.header().var_data_start as usize - value.len();
+            self.header_mut().var_data_start = var_data_start as u32;
+            let data = self.page_frame_handle.data_mut();
+            data[var_data_start..var_data_start + value.len()].copy_from_slice(value);
+            data[start..start + 2].copy_from_slice(&(var_data_start as u16).to_le_bytes());
+            data[start + 2..start + 4].copy_from_slice(&(value.len() as u16).to_le_bytes());
+        }
+    }
+}
+
+/// Type alias for immutable PaxPage
+pub type PaxPageRef<'a> = PaxPage<PageFrameRefHandle<'a>>;
+/// Type alias for mutable PaxPage
+pub type PaxPageMut<'a> = PaxPage<PageFrameMutHandle<'a>>;
+
+impl<'a> TryFrom<PageFrameRefHandle<'a>> for PaxPageRef<'a> {
+    type Error = rustdb_error::Error;
+
+    fn try_from(page_frame_handle: PageFrameRefHandle<'a>) -> Result<Self> {
+        let page = PaxPage { page_frame_handle };
+        page.header().common.validate(PageType::Pax)?;
+        Ok(page)
+    }
+}
+
+impl<'a> From<PageFrameMutHandle<'a>> for PaxPageMut<'a> {
+    fn from(page_frame_handle: PageFrameMutHandle<'a>) -> Self {
+        PaxPage { page_frame_handle }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+    use crate::buffer_pool::BufferPoolManager;
+    use crate::page::INVALID_PAGE_ID;
+    use crate::replacer::replacer::AccessType;
+    use crate::test_utils::get_bpm_arc_with_pool_size;
+
+    #[test]
+    #[serial]
+    fn test_pax_page() -> Result<()> {
+        let bpm = get_bpm_arc_with_pool_size(4);
+        let column_types = [Type::Integer, Type::Varchar, Type::Boolean];
+        let capacity = pax_page_capacity(&column_types);
+
+        let page_id;
+        let mut rids = Vec::new();
+        {
+            let mut page = PaxPageMut::from(BufferPoolManager::create_page_handle(&bpm)?);
+            page.init(INVALID_PAGE_ID, &column_types);
+            page_id = page.page_id();
+            assert_eq!(capacity as u32, page.capacity());
+
+            for i in 0..capacity as i32 {
+                let values = vec![
+                    i.to_le_bytes().to_vec(),
+                    format!("row {}", i).into_bytes(),
+                    vec![(i % 2) as u8],
+                ];
+                rids.push(page.insert_tuple(&values)?);
+            }
+            let values = vec![vec![0; 4], vec![], vec![0]];
+            assert_eq!(Err(Error::OutOfBounds), page.insert_tuple(&values));
+            assert!(page.insert_tuple(&[vec![0; 4]]).is_err());
+
+            page.set_deleted(&rids[1], true)?;
+            let values = vec![vec![7; 4], b"updated".to_vec(), vec![1]];
+            page.update_tuple(&rids[2], &values)?;
+        }
+
+        let handle = BufferPoolManager::fetch_page_handle(&bpm, page_id, AccessType::Scan)?;
+        let page = PaxPageRef::try_from(handle)?;
+        assert_eq!(capacity as u32, page.tuple_count());
+        assert_eq!(3_i32.to_le_bytes(), page.get_value(&rids[3], 0)?);
+        assert_eq!(b"row 3", page.get_value(&rids[3], 1)?);
+        assert_eq!([1], page.get_value(&rids[3], 2)?);
+        assert_eq!(b"updated", page.get_value(&rids[2], 1)?);
+        assert!(page.is_deleted(&rids[1])?);
+        assert!(!page.is_deleted(&rids[2])?);
+        assert!(page.get_value(&rids[0], 3).is_err());
+
+        Ok(())
+    }
+}
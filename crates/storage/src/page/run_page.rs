@@ -0,0 +1,149 @@
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
+use bytemuck::{Pod, Zeroable};
+use bytes::Bytes;
+use rustdb_error::errdata;
+
+use crate::frame::PageFrame;
+use crate::frame_handle::{PageFrameMutHandle, PageFrameRefHandle};
+use crate::page::page_header::{PageHeader, PageType};
+use crate::page::{INVALID_PAGE_ID, PAGE_SIZE};
+use crate::typedef::PageId;
+use crate::Result;
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+pub(crate) struct RunPageHeader {
+    common: PageHeader,
+    next_page_id: PageId,
+    entry_cnt: u32,
+    data_len: u32,
+}
+
+pub(crate) const RUN_PAGE_HEADER_SIZE: usize = mem::size_of::<RunPageHeader>();
+/// Number of bytes of entries a run page can hold.
+pub(crate) const RUN_PAGE_CAPACITY: usize = PAGE_SIZE - RUN_PAGE_HEADER_SIZE;
+/// Size of the key and length stored in front of each entry's value.
+pub(crate) const RUN_ENTRY_HEADER_SIZE: usize = mem::size_of::<u64>() + mem::size_of::<u32>();
+/// Largest value an entry can hold, so that it fits in a page on its own.
+pub(crate) const MAX_RUN_VALUE_SIZE: usize = RUN_PAGE_CAPACITY - RUN_ENTRY_HEADER_SIZE;
+/// The length stored for a tombstone, which has no value.
+const TOMBSTONE_LEN: u32 = u32::MAX;
+
+/// A key of an LSM tree along with its value, or `None` for a tombstone marking the key deleted.
+pub(crate) type RunEntry = (u64, Option<Bytes>);
+
+/// Returns the number of bytes an entry with `value` takes up in a run page.
+pub(crate) fn run_entry_size(value: Option<&[u8]>) -> usize {
+    RUN_ENTRY_HEADER_SIZE + value.map_or(0, <[u8]>::len)
+}
+
+/// Appends an entry to `data`, in the format run pages store entries in.
+pub(crate) fn encode_run_entry(data: &mut Vec<u8>, key: u64, value: Option<&[u8]>) {
+    data.extend_from_slice(&key.to_le_bytes());
+    match value {
+        Some(value) => {
+            data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            data.extend_from_slice(value);
+        }
+        None => data.extend_from_slice(&TOMBSTONE_LEN.to_le_bytes()),
+    }
+}
+
+/// A page of a sorted run of an LSM tree. It holds entries sorted by key, each a key and a length
+/// followed by the value, and is chained to the run's next page through `next_page_id`.
+pub struct RunPage<T> {
+    page_frame_handle: T,
+}
+
+impl<T: Deref<Target = PageFrame>> RunPage<T> {
+    pub(crate) fn page_id(&self) -> PageId {
+        self.page_frame_handle.page_id()
+    }
+
+    pub(crate) fn next_page_id(&self) -> PageId {
+        self.header().next_page_id
+    }
+
+    pub(crate) fn header(&self) -> &RunPageHeader {
+        bytemuck::from_bytes(&self.page_frame_handle.data()[..RUN_PAGE_HEADER_SIZE])
+    }
+
+    pub(crate) fn entry_count(&self) -> u32 {
+        self.header().entry_cnt
+    }
+
+    /// Decodes the page's entries, copying their values out of the page.
+    pub(crate) fn entries(&self) -> Result<Vec<RunEntry>> {
+        let data_len = self.header().data_len as usize;
+        if data_len > RUN_PAGE_CAPACITY {
+            return errdata!("Run page {} holds {} bytes", self.page_id(), data_len);
+        }
+        let mut data = &self.page_frame_handle.data()[RUN_PAGE_HEADER_SIZE..][..data_len];
+        let mut entries = Vec::with_capacity(self.entry_count() as usize);
+        for _ in 0..self.entry_count() {
+            if data.len() < RUN_ENTRY_HEADER_SIZE {
+                return errdata!("Truncated entry in run page {}", self.page_id());
+            }
+            let key = u64::from_le_bytes(data[..8].try_into().unwrap());
+            let len = u32::from_le_bytes(data[8..RUN_ENTRY_HEADER_SIZE].try_into().unwrap());
+            data = &data[RUN_ENTRY_HEADER_SIZE..];
+            if len == TOMBSTONE_LEN {
+                entries.push((key, None));
+                continue;
+            }
+            let Some((value, rest)) = data.split_at_checked(len as usize) else {
+                return errdata!("Truncated entry in run page {}", self.page_id());
+            };
+            entries.push((key, Some(Bytes::copy_from_slice(value))));
+            data = rest;
+        }
+        Ok(entries)
+    }
+}
+
+impl<T: DerefMut<Target = PageFrame> + Deref<Target = PageFrame>> RunPage<T> {
+    pub(crate) fn header_mut(&mut self) -> &mut RunPageHeader {
+        bytemuck::from_bytes_mut(&mut self.page_frame_handle.data_mut()[..RUN_PAGE_HEADER_SIZE])
+    }
+
+    /// Stores `entry_cnt` entries encoded with [`encode_run_entry`], which must fit in
+    /// [`RUN_PAGE_CAPACITY`] bytes. The page starts out as the last one of its run.
+    pub(crate) fn init(&mut self, entry_cnt: u32, data: &[u8]) {
+        let header = RunPageHeader {
+            common: PageHeader::new(PageType::Run),
+            next_page_id: INVALID_PAGE_ID,
+            entry_cnt,
+            data_len: data.len() as u32,
+        };
+        let page = self.page_frame_handle.data_mut();
+        page[..RUN_PAGE_HEADER_SIZE].copy_from_slice(bytemuck::bytes_of(&header));
+        page[RUN_PAGE_HEADER_SIZE..][..data.len()].copy_from_slice(data);
+    }
+
+    pub(crate) fn set_next_page_id(&mut self, next_page_id: PageId) {
+        self.header_mut().next_page_id = next_page_id;
+    }
+}
+
+/// Type alias for immutable RunPage
+pub type RunPageRef<'a> = RunPage<PageFrameRefHandle<'a>>;
+/// Type alias for mutable RunPage
+pub type RunPageMut<'a> = RunPage<PageFrameMutHandle<'a>>;
+
+impl<'a> TryFrom<PageFrameRefHandle<'a>> for RunPageRef<'a> {
+    type Error = rustdb_error::Error;
+
+    fn try_from(page_frame_handle: PageFrameRefHandle<'a>) -> Result<Self> {
+        let page = RunPage { page_frame_handle };
+        page.header().common.validate(PageType::Run)?;
+        Ok(page)
+    }
+}
+
+impl<'a> From<PageFrameMutHandle<'a>> for RunPageMut<'a> {
+    fn from(page_frame_handle: PageFrameMutHandle<'a>) -> Self {
+        RunPage { page_frame_handle }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::frame_handle::{PageFrameMutHandle, PageFrameRefHandle};
+use crate::page::page_header::{PageHeader, PageType};
 use crate::page::PAGE_SIZE;
 use crate::record_id::RecordId;
 use crate::Result;
@@ -12,6 +13,7 @@ use std::ops::{Deref, DerefMut};
 #[repr(C)]
 #[derive(Pod, Zeroable, Copy, Clone)]
 pub(crate) struct TablePageHeader {
+    common: PageHeader,
     next_page_id: PageId,
     tuple_cnt: u32,
     deleted_tuple_cnt: u32,
@@ -34,23 +36,63 @@ impl TupleInfo {
     pub(crate) fn size_bytes(&self) -> u16 {
         self.size_bytes
     }
+
+    /// A slot that was reclaimed by compaction. It holds no data and can be reused by an insert.
+    fn reclaimed() -> Self {
+        Self {
+            offset: PAGE_SIZE as u16,
+            size_bytes: 0,
+            metadata: TupleMetadata::new(true),
+        }
+    }
+
+    pub(crate) fn is_reclaimed(&self) -> bool {
+        self.metadata.is_deleted() && self.size_bytes == 0 && self.offset as usize == PAGE_SIZE
+    }
+
+    /// Returns the number of data bytes the slot owns in the page.
+    fn stored_size(&self) -> usize {
+        if self.is_reclaimed() {
+            0
+        } else {
+            (self.size_bytes as usize).max(FORWARD_POINTER_SIZE)
+        }
+    }
 }
 
 pub(crate) const TABLE_PAGE_HEADER_SIZE: usize = mem::size_of::<TablePageHeader>();
 pub(crate) const TUPLE_INFO_SIZE: usize = mem::size_of::<TupleInfo>();
 
+/// Size of a forwarding pointer, which is a packed `RecordId`. Every slot owns at least this many
+/// bytes of tuple data, so that any tuple can later be turned into a forwarding pointer.
+pub(crate) const FORWARD_POINTER_SIZE: usize = mem::size_of::<u64>();
+
+/// A page is compacted once at least this fraction of its (unreclaimed) tuples are deleted.
+pub(crate) const COMPACTION_THRESHOLD: f64 = 0.5;
+
+/// The slot holds a forwarding pointer to the tuple's current location instead of its data.
+const TUPLE_FORWARDED: u8 = 1;
+/// The tuple was moved here from a slot that forwards to it, and is reached through that slot.
+const TUPLE_RELOCATED: u8 = 1 << 1;
+/// The slot holds an `OverflowPointer` to the overflow pages storing the tuple.
+const TUPLE_OVERFLOW: u8 = 1 << 2;
+
+/// The largest tuple that can be stored in a table page; larger ones go to overflow pages.
+pub(crate) const MAX_INLINE_TUPLE_SIZE: usize =
+    PAGE_SIZE - TABLE_PAGE_HEADER_SIZE - TUPLE_INFO_SIZE;
+
 #[repr(C)]
 #[derive(Pod, Zeroable, Copy, Clone)]
 pub struct TupleMetadata {
     is_deleted: u8,
-    _padding: [u8; 1],
+    flags: u8,
 }
 
 impl TupleMetadata {
     pub(crate) fn new(is_deleted: bool) -> Self {
         Self {
             is_deleted: is_deleted as u8,
-            _padding: [0; 1],
+            flags: 0,
         }
     }
 
@@ -61,6 +103,44 @@ impl TupleMetadata {
     pub(crate) fn set_deleted(&mut self, deleted: bool) {
         self.is_deleted = deleted as u8;
     }
+
+    pub(crate) fn is_forwarded(&self) -> bool {
+        self.flags & TUPLE_FORWARDED != 0
+    }
+
+    pub(crate) fn set_forwarded(&mut self, forwarded: bool) {
+        self.set_flag(TUPLE_FORWARDED, forwarded);
+    }
+
+    pub(crate) fn is_relocated(&self) -> bool {
+        self.flags & TUPLE_RELOCATED != 0
+    }
+
+    pub(crate) fn set_relocated(&mut self, relocated: bool) {
+        self.set_flag(TUPLE_RELOCATED, relocated);
+    }
+
+    pub(crate) fn is_overflow(&self) -> bool {
+        self.flags & TUPLE_OVERFLOW != 0
+    }
+
+    pub(crate) fn set_overflow(&mut self, overflow: bool) {
+        self.set_flag(TUPLE_OVERFLOW, overflow);
+    }
+
+    fn set_flag(&mut self, flag: u8, value: bool) {
+        if value {
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
+        }
+    }
+}
+
+/// Decodes the record id stored in a forwarding slot's data.
+pub(crate) fn decode_forward(stub: &Tuple) -> RecordId {
+    let bytes = stub.data()[..FORWARD_POINTER_SIZE].try_into().unwrap();
+    RecordId::from(u64::from_le_bytes(bytes))
 }
 
 /// Generic struct for both mutable and immutable table pages.
@@ -81,6 +161,11 @@ impl<T: Deref<Target = PageFrame>> TablePage<T> {
         self.header().tuple_cnt
     }
 
+    /// Returns the number of slots marked as deleted since the page was last compacted.
+    pub(crate) fn deleted_count(&self) -> u32 {
+        self.header().deleted_tuple_cnt
+    }
+
     /// Immutable access to the header
     pub(crate) fn header(&self) -> &TablePageHeader {
         bytemuck::from_bytes(&self.page_frame_handle.data()[..TABLE_PAGE_HEADER_SIZE])
@@ -94,29 +179,84 @@ impl<T: Deref<Target = PageFrame>> TablePage<T> {
     }
 
     pub(crate) fn get_tuple(&self, rid: &RecordId) -> Result<(TupleMetadata, Tuple)> {
-        let slot_id = rid.slot_id();
-        
-        let tuple_info = self.slot_array()[slot_id as usize];
-        let tuple_offset = tuple_info.offset;
-        let tuple_size = tuple_info.size_bytes;
-        let tuple_metadata = tuple_info.metadata;
+        let (tuple_metadata, data) = self.tuple_bytes(rid)?;
 
         use bytes::Bytes;
 
-        let tuple = Tuple::new(Bytes::copy_from_slice(&self.page_frame_handle.data()[tuple_offset as usize..(tuple_offset + tuple_size) as usize]));
+        let tuple = Tuple::new(Bytes::copy_from_slice(data));
         Ok((tuple_metadata, tuple))
     }
 
-    fn get_next_tuple_offset(&mut self, tuple: &Tuple) -> Result<u16> {
-        let num_tuples = self.tuple_count();
-        
-        if num_tuples == 0 {
-            return Ok((PAGE_SIZE - &tuple.tuple_size()) as u16)
+    /// Like [`TablePage::get_tuple`], but borrows the slot's bytes from the page instead of
+    /// copying them.
+    pub(crate) fn tuple_bytes(&self, rid: &RecordId) -> Result<(TupleMetadata, &[u8])> {
+        let tuple_info = self.slot_array()[rid.slot_id() as usize];
+        let tuple_offset = tuple_info.offset as usize;
+        let tuple_size = tuple_info.size_bytes as usize;
+        let data = &self.page_frame_handle.data()[tuple_offset..tuple_offset + tuple_size];
+        Ok((tuple_info.metadata, data))
+    }
+
+    /// Returns the offset where tuple data starts, i.e. the end of the page's free space. Tuples
+    /// can be moved within the page, so the last slot doesn't necessarily hold the lowest one.
+    fn free_space_end(&self) -> usize {
+        self.slot_array()
+            .iter()
+            .map(|tuple_info| tuple_info.offset as usize)
+            .min()
+            .unwrap_or(PAGE_SIZE)
+    }
+
+    /// Returns the number of free bytes between the slot array and the tuple data.
+    pub(crate) fn free_space(&self) -> usize {
+        let slots_end = TABLE_PAGE_HEADER_SIZE + self.tuple_count() as usize * TUPLE_INFO_SIZE;
+        self.free_space_end().saturating_sub(slots_end)
+    }
+
+    /// Returns the number of free bytes an insert can use, compacting the page if it has to.
+    pub(crate) fn available_space(&self) -> usize {
+        self.free_space() + self.reclaimable_space()
+    }
+
+    /// Returns the offset at which `size` bytes of tuple data can be stored, leaving room for
+    /// `new_slots` more slots, or `Error::OutOfBounds` if the page is too full.
+    fn get_next_tuple_offset(&self, size: usize, new_slots: usize) -> Result<u16> {
+        if self.free_space() < size + new_slots * TUPLE_INFO_SIZE {
+            return Err(Error::OutOfBounds);
         }
-        
-        let last_tuple_offset = self.slot_array()[(num_tuples - 1) as usize].offset;
-        
-        Ok(last_tuple_offset - tuple.tuple_size() as u16)
+        Ok((self.free_space_end() - size) as u16)
+    }
+
+    /// Returns the number of bytes compaction would reclaim: the space of deleted tuples and of
+    /// holes left behind by updates.
+    pub(crate) fn reclaimable_space(&self) -> usize {
+        let used = PAGE_SIZE - self.free_space_end();
+        let live: usize = self
+            .slot_array()
+            .iter()
+            .filter(|tuple_info| !tuple_info.metadata.is_deleted())
+            .map(TupleInfo::stored_size)
+            .sum();
+        used - live
+    }
+
+    /// Returns the fraction of the page's unreclaimed tuples that are deleted.
+    pub(crate) fn deleted_fraction(&self) -> f64 {
+        let tuple_cnt = self
+            .slot_array()
+            .iter()
+            .filter(|tuple_info| !tuple_info.is_reclaimed())
+            .count();
+        if tuple_cnt == 0 {
+            return 0.0;
+        }
+        self.header().deleted_tuple_cnt as f64 / tuple_cnt as f64
+    }
+
+    /// Whether no slot of the page holds a tuple, not even a deleted one, i.e. every slot was
+    /// reclaimed by compaction.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.slot_array().iter().all(TupleInfo::is_reclaimed)
     }
 
     fn validate_record_id(&self, rid: &RecordId) -> Result<()> {
@@ -146,6 +286,7 @@ impl<T: DerefMut<Target = PageFrame> + Deref<Target = PageFrame>> TablePage<T> {
     pub(crate) fn init_header(&mut self, next_page_id: PageId) {
         let header = self.header_mut();
         *header = TablePageHeader {
+            common: PageHeader::new(PageType::Table),
             next_page_id,
             tuple_cnt: 0,
             deleted_tuple_cnt: 0,
@@ -165,30 +306,42 @@ impl<T: DerefMut<Target = PageFrame> + Deref<Target = PageFrame>> TablePage<T> {
 
     pub(crate) fn insert_tuple(&mut self, meta: &TupleMetadata, tuple: &Tuple) -> Result<RecordId> {
 
-        let offset = self.get_next_tuple_offset(&tuple)?;
-        let needed_header_space = 4  + (4 * (self.header_mut().tuple_cnt as u16 + 1));
-
-        // Check if there is enough space for the new tuple
-        if offset <  needed_header_space {
-            return Err(Error::OutOfBounds);  // You should define an appropriate error type here
-        }
-        
+        // Reuse a slot reclaimed by compaction if there is one, instead of growing the slot array
+        let reclaimed_slot = self.slot_array().iter().position(TupleInfo::is_reclaimed);
+        let new_slots = reclaimed_slot.is_none() as usize;
+
+        // Check if there is enough space for the new tuple (and its slot)
+        let stored_size = tuple.tuple_size().max(FORWARD_POINTER_SIZE);
+        let offset = match self.get_next_tuple_offset(stored_size, new_slots) {
+            Ok(offset) => offset,
+            Err(Error::OutOfBounds) if self.reclaimable_space() > 0 => {
+                self.compact();
+                return self.insert_tuple(meta, tuple);
+            }
+            Err(e) => return Err(e),
+        };
 
         //write to num_tuples in mutable header
-        self.header_mut().tuple_cnt += 1;
-        let tuple_cnt = self.header().tuple_cnt;
+        let slot_id = match reclaimed_slot {
+            Some(slot_id) => slot_id,
+            None => {
+                self.header_mut().tuple_cnt += 1;
+                self.header().tuple_cnt as usize - 1
+            }
+        };
         
         // update slot array
-        self.slot_array_mut()[(tuple_cnt-1) as usize].offset = offset;
-        self.slot_array_mut()[(tuple_cnt-1) as usize].size_bytes = tuple.tuple_size() as u16;
-        self.slot_array_mut()[(tuple_cnt-1) as usize].metadata = *meta;
+        let tuple_info = &mut self.slot_array_mut()[slot_id];
+        tuple_info.offset = offset;
+        tuple_info.size_bytes = tuple.tuple_size() as u16;
+        tuple_info.metadata = *meta;
         
         
         // write to page bytes?
         self.page_frame_handle.data_mut()[offset as usize..offset as usize + tuple.tuple_size()].copy_from_slice(&tuple.data());
         
         // ret the record id
-        let record_id = RecordId::new(self.page_id(), tuple_cnt-1);
+        let record_id = RecordId::new(self.page_id(), slot_id as u32);
         Ok(record_id)
     }
 
@@ -200,12 +353,109 @@ impl<T: DerefMut<Target = PageFrame> + Deref<Target = PageFrame>> TablePage<T> {
 
         let slot_id = rid.slot_id();
         let tuple_info = &mut self.slot_array_mut()[slot_id as usize];
+        let was_deleted = tuple_info.metadata.is_deleted();
         
         tuple_info.metadata.set_deleted(metadata.is_deleted());
-        self.header_mut().deleted_tuple_cnt += 1;
+        let header = self.header_mut();
+        match (was_deleted, metadata.is_deleted()) {
+            (false, true) => header.deleted_tuple_cnt += 1,
+            (true, false) => header.deleted_tuple_cnt = header.deleted_tuple_cnt.saturating_sub(1),
+            _ => {}
+        }
+
+        if self.deleted_fraction() >= COMPACTION_THRESHOLD {
+            self.compact();
+        }
         
         Ok(())
     }
+
+    /// Slides the live tuples together at the end of the page, reclaiming the space of deleted
+    /// tuples and of holes left behind by updates. Deleted slots become reclaimed slots, which
+    /// `insert_tuple` reuses, while live tuples keep their slot ids. Returns the number of bytes
+    /// reclaimed.
+    pub(crate) fn compact(&mut self) -> usize {
+        let free_space_before = self.free_space();
+        let old_data = self.page_frame_handle.data().to_vec();
+        let mut slots = self.slot_array().to_vec();
+
+        // Lay the live tuples out again in slot order, as if they had just been inserted.
+        let mut end = PAGE_SIZE;
+        for tuple_info in slots.iter_mut() {
+            if tuple_info.metadata.is_deleted() {
+                *tuple_info = TupleInfo::reclaimed();
+                continue;
+            }
+            let start = tuple_info.offset as usize;
+            let size = tuple_info.size_bytes as usize;
+            end -= tuple_info.stored_size();
+            self.page_frame_handle.data_mut()[end..end + size]
+                .copy_from_slice(&old_data[start..start + size]);
+            tuple_info.offset = end as u16;
+        }
+
+        self.slot_array_mut().copy_from_slice(&slots);
+        self.header_mut().deleted_tuple_cnt = 0;
+        self.free_space() - free_space_before
+    }
+
+    /// Overwrites the tuple at `rid` with `tuple` and its metadata with `meta`, keeping its slot
+    /// and thus its record id.
+    ///
+    /// The new data is written in place if it fits in the space the slot already owns, and moved
+    /// to the page's free space otherwise, compacting the page first if that makes room. If it
+    /// still doesn't fit, returns `Error::OutOfBounds` without changing any tuple, so the caller
+    /// can store the tuple elsewhere and forward to it.
+    pub(crate) fn update_tuple(
+        &mut self,
+        rid: &RecordId,
+        meta: &TupleMetadata,
+        tuple: &Tuple,
+    ) -> Result<()> {
+        self.validate_record_id(rid)?;
+        let slot_id = rid.slot_id() as usize;
+        let tuple_info = self.slot_array()[slot_id];
+
+        let capacity = (tuple_info.size_bytes as usize).max(FORWARD_POINTER_SIZE);
+        let offset = if tuple.tuple_size() <= capacity {
+            tuple_info.offset
+        } else {
+            match self.get_next_tuple_offset(tuple.tuple_size(), 0) {
+                Ok(offset) => offset,
+                Err(Error::OutOfBounds) if self.reclaimable_space() > 0 => {
+                    self.compact();
+                    return self.update_tuple(rid, meta, tuple);
+                }
+                Err(e) => return Err(e),
+            }
+        };
+        self.page_frame_handle.data_mut()[offset as usize..offset as usize + tuple.tuple_size()]
+            .copy_from_slice(&tuple.data());
+
+        let tuple_info = &mut self.slot_array_mut()[slot_id];
+        tuple_info.offset = offset;
+        tuple_info.size_bytes = tuple.tuple_size() as u16;
+        tuple_info.metadata = *meta;
+        Ok(())
+    }
+
+    /// Turns the slot at `rid` into a forwarding pointer to `target`, where its tuple now lives.
+    pub(crate) fn set_forward(&mut self, rid: &RecordId, target: &RecordId) -> Result<()> {
+        self.validate_record_id(rid)?;
+        let slot_id = rid.slot_id() as usize;
+
+        // Every slot owns at least `FORWARD_POINTER_SIZE` bytes, so the pointer always fits.
+        let offset = self.slot_array()[slot_id].offset as usize;
+        let pointer = u64::from(target.clone()).to_le_bytes();
+        self.page_frame_handle.data_mut()[offset..offset + FORWARD_POINTER_SIZE]
+            .copy_from_slice(&pointer);
+
+        let tuple_info = &mut self.slot_array_mut()[slot_id];
+        tuple_info.size_bytes = FORWARD_POINTER_SIZE as u16;
+        tuple_info.metadata = TupleMetadata::new(tuple_info.metadata.is_deleted());
+        tuple_info.metadata.set_forwarded(true);
+        Ok(())
+    }
 }
 
 /// Type alias for immutable TablePage
@@ -213,9 +463,14 @@ pub type TablePageRef<'a> = TablePage<PageFrameRefHandle<'a>>;
 /// Type alias for mutable TablePage
 pub type TablePageMut<'a> = TablePage<PageFrameMutHandle<'a>>;
 
-impl<'a> From<PageFrameRefHandle<'a>> for TablePageRef<'a> {
-    fn from(page_frame_handle: PageFrameRefHandle<'a>) -> Self {
-        TablePage { page_frame_handle }
+/// Wrapping a page for reading checks that it actually is a table page.
+impl<'a> TryFrom<PageFrameRefHandle<'a>> for TablePageRef<'a> {
+    type Error = rustdb_error::Error;
+
+    fn try_from(page_frame_handle: PageFrameRefHandle<'a>) -> Result<Self> {
+        let table_page = TablePage { page_frame_handle };
+        table_page.header().common.validate(PageType::Table)?;
+        Ok(table_page)
     }
 }
 
@@ -227,28 +482,16 @@ impl<'a> From<PageFrameMutHandle<'a>> for TablePageMut<'a> {
 
 #[cfg(test)]
 mod tests {
-    use std::sync::{Arc, Mutex, RwLock};
-
     use rustdb_catalog::tuple::Tuple;
     use serial_test::serial;
 
     use crate::{
-        buffer_pool::BufferPoolManager, disk::disk_manager::DiskManager,
-        replacer::lru_k_replacer::LrukReplacer,
+        buffer_pool::BufferPoolManager, page::INVALID_PAGE_ID, replacer::replacer::AccessType,
+        test_utils::get_bpm_arc_with_pool_size,
     };
 
     use super::*;
 
-    fn get_bpm_with_pool_size(pool_size: usize) -> BufferPoolManager {
-        let disk_manager = Arc::new(Mutex::new(DiskManager::new("test.db").unwrap()));
-        let replacer = Box::new(LrukReplacer::new(5));
-        BufferPoolManager::new(pool_size, disk_manager, replacer)
-    }
-
-    fn get_bpm_arc_with_pool_size(pool_size: usize) -> Arc<RwLock<BufferPoolManager>> {
-        Arc::new(RwLock::new(get_bpm_with_pool_size(pool_size)))
-    }
-
     #[test]
     pub fn test_insert_tuple() {
         let bpm = get_bpm_arc_with_pool_size(10);
@@ -312,9 +555,10 @@ mod tests {
             assert_eq!(slots[1].metadata.is_deleted(), true);
         }
 
-        let frame_handle_1 = BufferPoolManager::fetch_page_handle(&bpm, page_id).unwrap();
+        let frame_handle_1 =
+            BufferPoolManager::fetch_page_handle(&bpm, page_id, AccessType::Unknown).unwrap();
 
-        let table_page1 = TablePageRef::from(frame_handle_1);
+        let table_page1 = TablePageRef::try_from(frame_handle_1).unwrap();
 
         assert_eq!(1, table_page1.page_id());
         assert_eq!(2, table_page1.next_page_id());
@@ -363,9 +607,10 @@ mod tests {
             assert_eq!(retrieved_meta.is_deleted(), metadata.is_deleted());
             assert_eq!(retrieved_tuple.data(), &tuple_data);
         }
-        let frame_handle_1 = BufferPoolManager::fetch_page_handle(&bpm, page_id).unwrap();
+        let frame_handle_1 =
+            BufferPoolManager::fetch_page_handle(&bpm, page_id, AccessType::Unknown).unwrap();
 
-        let table_page1 = TablePageRef::from(frame_handle_1);
+        let table_page1 = TablePageRef::try_from(frame_handle_1).unwrap();
         // Retrieve the tuple
         let (retrieved_meta, retrieved_tuple) = table_page1.get_tuple(&insert_record_id).unwrap();
 
@@ -374,4 +619,185 @@ mod tests {
         assert_eq!(retrieved_tuple.data(), &tuple_data);
     }
 
+    #[test]
+    #[serial]
+    fn test_update_tuple() {
+        let bpm = get_bpm_arc_with_pool_size(10);
+        let frame_handle = BufferPoolManager::create_page_handle(&bpm).unwrap();
+        let mut table_page = TablePageMut::from(frame_handle);
+        table_page.init_header(INVALID_PAGE_ID);
+
+        let meta = TupleMetadata::new(false);
+        let rid1 = table_page
+            .insert_tuple(&meta, &Tuple::new(vec![1; 20].into()))
+            .unwrap();
+        let rid2 = table_page
+            .insert_tuple(&meta, &Tuple::new(vec![2; 20].into()))
+            .unwrap();
+        let offset1 = table_page.slot_array()[0].offset();
+
+        // A smaller tuple is written in place.
+        table_page
+            .update_tuple(&rid1, &meta, &Tuple::new(vec![3; 10].into()))
+            .unwrap();
+        assert_eq!(offset1, table_page.slot_array()[0].offset());
+        assert_eq!(10, table_page.slot_array()[0].size_bytes());
+        assert_eq!(&[3; 10], &table_page.get_tuple(&rid1).unwrap().1.data()[..]);
+
+        // A larger one moves into the page's free space, keeping its slot.
+        table_page
+            .update_tuple(&rid1, &meta, &Tuple::new(vec![4; 30].into()))
+            .unwrap();
+        assert_ne!(offset1, table_page.slot_array()[0].offset());
+        assert_eq!(&[4; 30], &table_page.get_tuple(&rid1).unwrap().1.data()[..]);
+        assert_eq!(&[2; 20], &table_page.get_tuple(&rid2).unwrap().1.data()[..]);
+        assert_eq!(2, table_page.tuple_count());
+
+        // A tuple that doesn't fit even after compaction leaves the tuples untouched.
+        let too_big_size = table_page.free_space() + table_page.reclaimable_space() + 1;
+        let too_big = Tuple::new(vec![5; too_big_size].into());
+        assert_eq!(
+            Err(Error::OutOfBounds),
+            table_page.update_tuple(&rid2, &meta, &too_big)
+        );
+        assert_eq!(&[2; 20], &table_page.get_tuple(&rid2).unwrap().1.data()[..]);
+
+        // The slot can forward to the tuple's new home instead.
+        let target = RecordId::new(42, 7);
+        table_page.set_forward(&rid2, &target).unwrap();
+        let (meta2, stub) = table_page.get_tuple(&rid2).unwrap();
+        assert!(meta2.is_forwarded());
+        assert_eq!(target, decode_forward(&stub));
+
+        // Updating the forwarding slot with a tuple that fits stores it there again.
+        table_page
+            .update_tuple(&rid2, &meta, &Tuple::new(vec![6; 8].into()))
+            .unwrap();
+        let (meta2, tuple2) = table_page.get_tuple(&rid2).unwrap();
+        assert!(!meta2.is_forwarded());
+        assert_eq!(&[6; 8], &tuple2.data()[..]);
+
+        assert!(table_page
+            .update_tuple(&RecordId::new(table_page.page_id(), 2), &meta, &tuple2)
+            .is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_compact_page() {
+        let bpm = get_bpm_arc_with_pool_size(10);
+        let frame_handle = BufferPoolManager::create_page_handle(&bpm).unwrap();
+        let mut table_page = TablePageMut::from(frame_handle);
+        table_page.init_header(INVALID_PAGE_ID);
+
+        let meta = TupleMetadata::new(false);
+        let rids: Vec<RecordId> = (0..4)
+            .map(|i| {
+                let tuple = Tuple::new(vec![i; 100].into());
+                table_page.insert_tuple(&meta, &tuple).unwrap()
+            })
+            .collect();
+        let free_space = table_page.free_space();
+
+        // One deleted tuple out of four stays below the threshold.
+        table_page
+            .update_tuple_metadata(&rids[1], TupleMetadata::new(true))
+            .unwrap();
+        assert_eq!(1, table_page.header().deleted_tuple_cnt);
+        assert_eq!(100, table_page.reclaimable_space());
+        assert_eq!(free_space, table_page.free_space());
+
+        // Deleting a tuple twice doesn't count it twice.
+        table_page
+            .update_tuple_metadata(&rids[1], TupleMetadata::new(true))
+            .unwrap();
+        assert_eq!(1, table_page.header().deleted_tuple_cnt);
+
+        // The second deletion crosses the threshold and compacts the page.
+        table_page
+            .update_tuple_metadata(&rids[2], TupleMetadata::new(true))
+            .unwrap();
+        assert_eq!(0, table_page.header().deleted_tuple_cnt);
+        assert_eq!(0, table_page.reclaimable_space());
+        assert_eq!(free_space + 200, table_page.free_space());
+        assert!(table_page.slot_array()[1].is_reclaimed());
+        assert!(table_page.slot_array()[2].is_reclaimed());
+
+        // Live tuples keep their slots, and deleted ones read as deleted.
+        for i in [0, 3] {
+            let (meta, tuple) = table_page.get_tuple(&rids[i]).unwrap();
+            assert!(!meta.is_deleted());
+            assert_eq!(&[i as u8; 100], &tuple.data()[..]);
+        }
+        assert!(table_page.get_tuple(&rids[1]).unwrap().0.is_deleted());
+
+        // Reclaimed slots are reused before the slot array grows.
+        let tuple = Tuple::new(vec![7; 10].into());
+        assert_eq!(rids[1], table_page.insert_tuple(&meta, &tuple).unwrap());
+        assert_eq!(rids[2], table_page.insert_tuple(&meta, &tuple).unwrap());
+        assert_eq!(4, table_page.insert_tuple(&meta, &tuple).unwrap().slot_id());
+        assert_eq!(5, table_page.tuple_count());
+    }
+
+    #[test]
+    #[serial]
+    fn test_insert_compacts_full_page() {
+        let bpm = get_bpm_arc_with_pool_size(10);
+        let frame_handle = BufferPoolManager::create_page_handle(&bpm).unwrap();
+        let mut table_page = TablePageMut::from(frame_handle);
+        table_page.init_header(INVALID_PAGE_ID);
+
+        let meta = TupleMetadata::new(false);
+        let tuple = Tuple::new(vec![1; 400].into());
+        let mut rids = Vec::new();
+        while let Ok(rid) = table_page.insert_tuple(&meta, &tuple) {
+            rids.push(rid);
+        }
+
+        // A single deletion stays below the threshold, but the insert makes use of its space.
+        table_page
+            .update_tuple_metadata(&rids[0], TupleMetadata::new(true))
+            .unwrap();
+        assert!(table_page.free_space() < 400);
+        let rid = table_page
+            .insert_tuple(&meta, &Tuple::new(vec![2; 400].into()))
+            .unwrap();
+        assert_eq!(rids[0], rid);
+        assert_eq!(&[2; 400], &table_page.get_tuple(&rid).unwrap().1.data()[..]);
+        for rid in &rids[1..] {
+            assert_eq!(&[1; 400], &table_page.get_tuple(rid).unwrap().1.data()[..]);
+        }
+        assert_eq!(
+            Err(Error::OutOfBounds),
+            table_page.insert_tuple(&meta, &tuple)
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_table_page_validates_type() {
+        let bpm = get_bpm_arc_with_pool_size(10);
+
+        // A page that was never initialized as a table page is rejected.
+        let page_id = BufferPoolManager::create_page_handle(&bpm)
+            .unwrap()
+            .page_id();
+        let frame_handle =
+            BufferPoolManager::fetch_page_handle(&bpm, page_id, AccessType::Unknown).unwrap();
+        assert!(matches!(
+            TablePageRef::try_from(frame_handle),
+            Err(Error::InvalidData(_))
+        ));
+
+        {
+            let frame_handle =
+                BufferPoolManager::fetch_page_mut_handle(&bpm, page_id, AccessType::Unknown)
+                    .unwrap();
+            TablePageMut::from(frame_handle).init_header(INVALID_PAGE_ID);
+        }
+        let frame_handle =
+            BufferPoolManager::fetch_page_handle(&bpm, page_id, AccessType::Unknown).unwrap();
+        let table_page = TablePageRef::try_from(frame_handle).unwrap();
+        assert_eq!(Ok(PageType::Table), table_page.header().common.page_type());
+    }
 }
@@ -0,0 +1,301 @@
+use super::lru_list::LruList;
+use super::replacer::{AccessType, Replacer};
+use crate::typedef::{FrameId, PageId};
+use std::collections::HashMap;
+
+/// Represents a frame tracked by the ArcReplacer.
+#[derive(Debug)]
+struct ArcNode {
+    page_id: Option<PageId>, // The page held by the frame, if the buffer pool told us
+    is_evictable: bool,
+    is_loading: bool, // Set until the access that follows `record_page`
+}
+
+impl ArcNode {
+    /// Creates an ArcNode, which is not evictable by default.
+    fn new(page_id: Option<PageId>) -> Self {
+        Self {
+            page_id,
+            is_evictable: false,
+            is_loading: page_id.is_some(),
+        }
+    }
+}
+
+/// Implements the ARC (Adaptive Replacement Cache) replacement policy.
+///
+/// Resident frames live in one of two LRU lists: `t1` holds frames whose page was seen only
+/// once recently, `t2` frames whose page was seen at least twice. Pages evicted from either list
+/// are remembered in the ghost lists `b1` and `b2`. A page coming back while still in a ghost
+/// list means we evicted from the wrong list, so the target size of `t1` is nudged towards the
+/// list it was evicted from. This lets the policy adapt between recency-heavy workloads (e.g.
+/// scans) and frequency-heavy ones (e.g. point lookups).
+#[derive(Debug)]
+pub(crate) struct ArcReplacer {
+    node_store: HashMap<FrameId, ArcNode>,
+    t1: LruList<FrameId>,
+    t2: LruList<FrameId>,
+    b1: LruList<PageId>,
+    b2: LruList<PageId>,
+    target_t1_size: usize, // ARC's adaptive parameter `p`
+    capacity: usize,       // Number of frames in the buffer pool
+    evictable_size: usize, // Number of evictable nodes
+}
+
+impl ArcReplacer {
+    /// Creates a new ARC replacer for a buffer pool with `num_frames` frames.
+    pub(crate) fn new(num_frames: usize) -> Self {
+        ArcReplacer {
+            node_store: HashMap::new(),
+            t1: LruList::new(),
+            t2: LruList::new(),
+            b1: LruList::new(),
+            b2: LruList::new(),
+            target_t1_size: 0,
+            capacity: num_frames,
+            evictable_size: 0,
+        }
+    }
+
+    /// Returns the least recently used evictable frame in `list`.
+    fn lru_evictable(&self, list: &LruList<FrameId>) -> Option<FrameId> {
+        list.iter()
+            .find(|frame_id| self.node_store[frame_id].is_evictable)
+            .copied()
+    }
+
+    /// Stops tracking a frame without remembering its page in a ghost list.
+    fn untrack(&mut self, frame_id: FrameId) {
+        if let Some(node) = self.node_store.remove(&frame_id) {
+            if node.is_evictable {
+                self.evictable_size -= 1;
+            }
+            self.t1.remove(&frame_id);
+            self.t2.remove(&frame_id);
+        }
+    }
+
+    /// Keeps the ghost lists within ARC's bounds: `t1` and `b1` together remember at most one
+    /// buffer pool's worth of pages, and all four lists together at most two.
+    fn trim_ghosts(&mut self) {
+        while self.t1.len() + self.b1.len() > self.capacity && self.b1.pop_front().is_some() {}
+        while self.t1.len() + self.t2.len() + self.b1.len() + self.b2.len() > 2 * self.capacity {
+            if self.b2.pop_front().is_none() && self.b1.pop_front().is_none() {
+                break;
+            }
+        }
+    }
+}
+
+impl Replacer for ArcReplacer {
+    /// Records access to a frame. A repeated access promotes the frame to `t2`, unless it comes
+    /// from a scan: a scan touching a page several times doesn't make it frequently used.
+    fn record_access(&mut self, frame_id: FrameId, access_type: AccessType) {
+        match self.node_store.get_mut(&frame_id) {
+            None => {
+                self.node_store.insert(frame_id, ArcNode::new(None));
+                self.t1.push_back(frame_id);
+            }
+            // This is the access that loaded the page, which `record_page` already placed.
+            Some(node) if node.is_loading => node.is_loading = false,
+            Some(_) if access_type == AccessType::Scan => {}
+            Some(_) => {
+                self.t1.remove(&frame_id);
+                self.t2.push_back(frame_id);
+            }
+        }
+    }
+
+    /// Places a newly loaded page, adapting the target size of `t1` on a ghost hit.
+    fn record_page(&mut self, frame_id: FrameId, page_id: PageId) {
+        self.untrack(frame_id);
+
+        if self.b1.contains(&page_id) {
+            // We shouldn't have evicted this page from t1, so give t1 more room.
+            let delta = (self.b2.len() / self.b1.len()).max(1);
+            self.target_t1_size = (self.target_t1_size + delta).min(self.capacity);
+            self.b1.remove(&page_id);
+            self.t2.push_back(frame_id);
+        } else if self.b2.contains(&page_id) {
+            // We shouldn't have evicted this page from t2, so give t2 more room.
+            let delta = (self.b1.len() / self.b2.len()).max(1);
+            self.target_t1_size = self.target_t1_size.saturating_sub(delta);
+            self.b2.remove(&page_id);
+            self.t2.push_back(frame_id);
+        } else {
+            self.t1.push_back(frame_id);
+            self.trim_ghosts();
+        }
+
+        self.node_store
+            .insert(frame_id, ArcNode::new(Some(page_id)));
+    }
+
+    /// Pins a frame, making it non-evictable.
+    fn pin(&mut self, frame_id: FrameId) {
+        if let Some(node) = self.node_store.get_mut(&frame_id) {
+            if node.is_evictable {
+                node.is_evictable = false;
+                self.evictable_size -= 1;
+            }
+        }
+    }
+
+    /// Unpins a frame, making it evictable.
+    fn unpin(&mut self, frame_id: FrameId) {
+        if let Some(node) = self.node_store.get_mut(&frame_id) {
+            if !node.is_evictable {
+                node.is_evictable = true;
+                self.evictable_size += 1;
+            }
+        }
+    }
+
+    /// Evicts the least recently used frame of `t1` if it is larger than its target size, and of
+    /// `t2` otherwise. Falls back to the other list if the preferred one has no evictable frame.
+    fn evict(&mut self) -> Option<FrameId> {
+        let prefer_t1 = !self.t1.is_empty() && self.t1.len() > self.target_t1_size;
+        let (first, second) = if prefer_t1 {
+            (&self.t1, &self.t2)
+        } else {
+            (&self.t2, &self.t1)
+        };
+        let frame_id = self
+            .lru_evictable(first)
+            .or_else(|| self.lru_evictable(second))?;
+
+        let from_t1 = self.t1.remove(&frame_id);
+        self.t2.remove(&frame_id);
+        let node = self.node_store.remove(&frame_id).unwrap();
+        self.evictable_size -= 1;
+
+        if let Some(page_id) = node.page_id {
+            if from_t1 {
+                self.b1.push_back(page_id);
+            } else {
+                self.b2.push_back(page_id);
+            }
+            self.trim_ghosts();
+        }
+
+        Some(frame_id)
+    }
+
+    /// Removes a frame from the replacer if it is evictable.
+    fn remove(&mut self, frame_id: FrameId) {
+        if self
+            .node_store
+            .get(&frame_id)
+            .is_some_and(|node| node.is_evictable)
+        {
+            self.untrack(frame_id);
+        }
+    }
+
+    /// Returns the number of evictable frames.
+    fn evictable_count(&self) -> usize {
+        self.evictable_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arc_replacer_recency_and_frequency() {
+        let mut arc_replacer = ArcReplacer::new(4);
+
+        arc_replacer.record_access(1, AccessType::Unknown);
+        arc_replacer.record_access(2, AccessType::Unknown);
+        arc_replacer.record_access(3, AccessType::Unknown);
+        arc_replacer.unpin(1);
+        arc_replacer.unpin(2);
+        arc_replacer.unpin(3);
+        assert_eq!(3, arc_replacer.evictable_count());
+
+        // A second access promotes frame 1 to t2, so frames seen only once go first.
+        arc_replacer.record_access(1, AccessType::Unknown);
+        assert_eq!(Some(2), arc_replacer.evict());
+        assert_eq!(Some(3), arc_replacer.evict());
+        assert_eq!(Some(1), arc_replacer.evict());
+        assert_eq!(None, arc_replacer.evict());
+        assert_eq!(0, arc_replacer.evictable_count());
+    }
+
+    #[test]
+    fn test_arc_replacer_pin_and_remove() {
+        let mut arc_replacer = ArcReplacer::new(4);
+
+        arc_replacer.record_access(1, AccessType::Unknown);
+        arc_replacer.record_access(2, AccessType::Unknown);
+        arc_replacer.record_access(3, AccessType::Unknown);
+        arc_replacer.unpin(1);
+        arc_replacer.unpin(2);
+        arc_replacer.unpin(3);
+
+        arc_replacer.pin(1);
+        assert_eq!(2, arc_replacer.evictable_count());
+        assert_eq!(Some(2), arc_replacer.evict());
+
+        arc_replacer.remove(3);
+        assert_eq!(0, arc_replacer.evictable_count());
+        assert_eq!(None, arc_replacer.evict());
+
+        // Removing a pinned frame does nothing.
+        arc_replacer.remove(1);
+        arc_replacer.unpin(1);
+        assert_eq!(Some(1), arc_replacer.evict());
+    }
+
+    #[test]
+    fn test_arc_replacer_ghost_hits() {
+        let mut arc_replacer = ArcReplacer::new(2);
+
+        arc_replacer.record_page(0, 10);
+        arc_replacer.record_access(0, AccessType::Unknown);
+        arc_replacer.record_page(1, 11);
+        arc_replacer.record_access(1, AccessType::Unknown);
+        arc_replacer.unpin(0);
+        arc_replacer.unpin(1);
+
+        // Page 10 is evicted from t1 and remembered in b1.
+        assert_eq!(Some(0), arc_replacer.evict());
+        assert!(arc_replacer.b1.contains(&10));
+
+        // Page 10 comes back: t1 was too small, so its target grows and the page joins t2.
+        arc_replacer.record_page(0, 10);
+        arc_replacer.record_access(0, AccessType::Unknown);
+        arc_replacer.unpin(0);
+        assert_eq!(1, arc_replacer.target_t1_size);
+        assert!(arc_replacer.t2.contains(&0));
+
+        // t1 is now within its target, so the victim comes from t2 and is remembered in b2.
+        assert_eq!(Some(0), arc_replacer.evict());
+        assert!(arc_replacer.b2.contains(&10));
+
+        // Page 10 comes back again: this time t2 was too small, so t1's target shrinks.
+        arc_replacer.record_page(0, 10);
+        arc_replacer.record_access(0, AccessType::Unknown);
+        arc_replacer.unpin(0);
+        assert_eq!(0, arc_replacer.target_t1_size);
+        assert_eq!(Some(1), arc_replacer.evict());
+    }
+
+    #[test]
+    fn test_arc_replacer_scan_access() {
+        let mut arc_replacer = ArcReplacer::new(4);
+
+        arc_replacer.record_access(1, AccessType::Lookup);
+        arc_replacer.record_access(2, AccessType::Lookup);
+        arc_replacer.record_access(1, AccessType::Lookup);
+        arc_replacer.record_access(2, AccessType::Scan);
+        arc_replacer.unpin(1);
+        arc_replacer.unpin(2);
+
+        // Only the repeated lookup promoted its frame to t2.
+        assert!(arc_replacer.t2.contains(&1));
+        assert!(arc_replacer.t1.contains(&2));
+        assert_eq!(Some(2), arc_replacer.evict());
+    }
+}
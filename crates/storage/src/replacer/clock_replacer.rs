@@ -0,0 +1,406 @@
+use super::replacer::{AccessType, Replacer};
+use crate::typedef::FrameId;
+
+/// Represents a slot in the ClockReplacer, one per frame in the buffer pool.
+#[derive(Debug, Default, Clone)]
+struct ClockNode {
+    is_tracked: bool,
+    is_evictable: bool,
+    reference: bool, // Second-chance bit, set on every access
+}
+
+/// Implements the CLOCK (second-chance) replacement policy.
+///
+/// Frames are arranged in a circle that a clock hand sweeps over. Accessing a frame sets its
+/// reference bit; when looking for a victim, the hand clears the bit of every referenced
+/// evictable frame it passes and evicts the first evictable frame whose bit is already clear.
+#[derive(Debug)]
+pub(crate) struct ClockReplacer {
+    nodes: Vec<ClockNode>,
+    evictable_size: usize, // Number of evictable nodes
+    hand: usize,
+}
+
+impl ClockReplacer {
+    /// Creates a new CLOCK replacer able to track frames `0..num_frames`.
+    pub(crate) fn new(num_frames: usize) -> Self {
+        ClockReplacer {
+            nodes: vec![ClockNode::default(); num_frames],
+            evictable_size: 0,
+            hand: 0,
+        }
+    }
+
+    /// Moves the clock hand to the next frame.
+    fn advance_hand(&mut self) {
+        self.hand = (self.hand + 1) % self.nodes.len();
+    }
+}
+
+impl Replacer for ClockReplacer {
+    /// Records access to a frame by setting its reference bit.
+    fn record_access(&mut self, frame_id: FrameId, _access_type: AccessType) {
+        assert!(
+            frame_id < self.nodes.len(),
+            "Frame id {} is out of range for the clock replacer",
+            frame_id
+        );
+
+        let node = &mut self.nodes[frame_id];
+        node.is_tracked = true;
+        node.reference = true;
+    }
+
+    /// Pins a frame, making it non-evictable.
+    fn pin(&mut self, frame_id: FrameId) {
+        let Some(node) = self.nodes.get_mut(frame_id) else {
+            return;
+        };
+
+        if node.is_tracked && node.is_evictable {
+            node.is_evictable = false;
+            self.evictable_size -= 1;
+        }
+    }
+
+    /// Unpins a frame, making it evictable.
+    fn unpin(&mut self, frame_id: FrameId) {
+        let Some(node) = self.nodes.get_mut(frame_id) else {
+            return;
+        };
+
+        if node.is_tracked && !node.is_evictable {
+            node.is_evictable = true;
+            self.evictable_size += 1;
+        }
+    }
+
+    /// Sweeps the clock hand until it finds an evictable frame whose reference bit is clear,
+    /// giving every referenced frame it passes a second chance.
+    fn evict(&mut self) -> Option<FrameId> {
+        if self.evictable_size == 0 {
+            return None;
+        }
+
+        // Each evictable frame loses its reference bit on the first pass, so two full sweeps
+        // always find a victim.
+        for _ in 0..2 * self.nodes.len() {
+            let frame_id = self.hand;
+            self.advance_hand();
+
+            let node = &mut self.nodes[frame_id];
+            if !node.is_tracked || !node.is_evictable {
+                continue;
+            }
+            if node.reference {
+                node.reference = false;
+                continue;
+            }
+
+            *node = ClockNode::default();
+            self.evictable_size -= 1;
+            return Some(frame_id);
+        }
+
+        unreachable!("An evictable frame must be found within two sweeps of the clock hand")
+    }
+
+    /// Removes a frame from the replacer if it is evictable.
+    fn remove(&mut self, frame_id: FrameId) {
+        let Some(node) = self.nodes.get_mut(frame_id) else {
+            return;
+        };
+
+        if node.is_tracked && node.is_evictable {
+            *node = ClockNode::default();
+            self.evictable_size -= 1;
+        }
+    }
+
+    /// Returns the number of evictable frames.
+    fn evictable_count(&self) -> usize {
+        self.evictable_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_replacer_one() {
+        let mut clock_replacer = ClockReplacer::new(7);
+
+        clock_replacer.record_access(1, AccessType::Unknown);
+        clock_replacer.record_access(2, AccessType::Unknown);
+        clock_replacer.record_access(3, AccessType::Unknown);
+        clock_replacer.record_access(4, AccessType::Unknown);
+        clock_replacer.record_access(5, AccessType::Unknown);
+        clock_replacer.record_access(6, AccessType::Unknown);
+        clock_replacer.unpin(1);
+        clock_replacer.unpin(2);
+        clock_replacer.unpin(3);
+        clock_replacer.unpin(4);
+        clock_replacer.unpin(5);
+        clock_replacer.pin(6);
+
+        assert_eq!(5, clock_replacer.evictable_count());
+
+        clock_replacer.record_access(1, AccessType::Unknown);
+        assert_eq!(Some(1), clock_replacer.evict());
+        assert_eq!(Some(2), clock_replacer.evict());
+        assert_eq!(Some(3), clock_replacer.evict());
+        assert_eq!(2, clock_replacer.evictable_count());
+
+        clock_replacer.record_access(3, AccessType::Unknown);
+        clock_replacer.record_access(4, AccessType::Unknown);
+        clock_replacer.record_access(5, AccessType::Unknown);
+        clock_replacer.record_access(4, AccessType::Unknown);
+        clock_replacer.unpin(3);
+        clock_replacer.unpin(4);
+        assert_eq!(3, clock_replacer.evictable_count());
+
+        assert_eq!(Some(4), clock_replacer.evict());
+        assert_eq!(2, clock_replacer.evictable_count());
+
+        clock_replacer.unpin(6);
+        assert_eq!(3, clock_replacer.evictable_count());
+        assert_eq!(Some(5), clock_replacer.evict());
+        assert_eq!(2, clock_replacer.evictable_count());
+
+        clock_replacer.pin(1);
+        assert_eq!(2, clock_replacer.evictable_count());
+        assert_eq!(Some(3), clock_replacer.evict());
+        assert_eq!(1, clock_replacer.evictable_count());
+
+        clock_replacer.record_access(1, AccessType::Unknown);
+        clock_replacer.record_access(1, AccessType::Unknown);
+        clock_replacer.unpin(1);
+        assert_eq!(2, clock_replacer.evictable_count());
+
+        assert_eq!(Some(6), clock_replacer.evict());
+        assert_eq!(1, clock_replacer.evictable_count());
+        assert_eq!(Some(1), clock_replacer.evict());
+        assert_eq!(0, clock_replacer.evictable_count());
+
+        clock_replacer.record_access(1, AccessType::Unknown);
+        clock_replacer.pin(1);
+        assert_eq!(0, clock_replacer.evictable_count());
+
+        assert_eq!(None, clock_replacer.evict());
+
+        clock_replacer.unpin(1);
+        assert_eq!(1, clock_replacer.evictable_count());
+        assert_eq!(Some(1), clock_replacer.evict());
+        assert_eq!(0, clock_replacer.evictable_count());
+
+        assert_eq!(None, clock_replacer.evict());
+        assert_eq!(0, clock_replacer.evictable_count());
+
+        clock_replacer.pin(6);
+        clock_replacer.unpin(6);
+    }
+
+    #[test]
+    fn test_clock_replacer_two() {
+        let mut clock_replacer = ClockReplacer::new(7);
+
+        // Add six frames to the replacer. Frame 6 is non-evictable.
+        clock_replacer.record_access(1, AccessType::Unknown);
+        clock_replacer.record_access(2, AccessType::Unknown);
+        clock_replacer.record_access(3, AccessType::Unknown);
+        clock_replacer.record_access(4, AccessType::Unknown);
+        clock_replacer.record_access(5, AccessType::Unknown);
+        clock_replacer.record_access(6, AccessType::Unknown);
+        clock_replacer.unpin(1);
+        clock_replacer.unpin(2);
+        clock_replacer.unpin(3);
+        clock_replacer.unpin(4);
+        clock_replacer.unpin(5);
+        clock_replacer.pin(6);
+
+        // The size of the replacer is the number of evictable frames
+        assert_eq!(5, clock_replacer.evictable_count());
+
+        // Record an access for frame 1. Every frame is already referenced, so this changes nothing.
+        clock_replacer.record_access(1, AccessType::Unknown);
+
+        // The first sweep clears every reference bit, so frames are evicted in clock order
+        assert_eq!(Some(1), clock_replacer.evict());
+        assert_eq!(Some(2), clock_replacer.evict());
+        assert_eq!(Some(3), clock_replacer.evict());
+        assert_eq!(2, clock_replacer.evictable_count());
+
+        // Insert frame 3 again and reference frames [4, 5]
+        clock_replacer.record_access(3, AccessType::Unknown);
+        clock_replacer.record_access(4, AccessType::Unknown);
+        clock_replacer.record_access(5, AccessType::Unknown);
+        clock_replacer.record_access(4, AccessType::Unknown);
+        clock_replacer.unpin(3);
+        clock_replacer.unpin(4);
+        assert_eq!(3, clock_replacer.evictable_count());
+
+        // The hand clears [4, 5, 3] and comes back around to frame 4
+        assert_eq!(Some(4), clock_replacer.evict());
+        assert_eq!(2, clock_replacer.evictable_count());
+
+        // Set frame 6 to be evictable. It is still referenced, so frame 5 goes first
+        clock_replacer.unpin(6);
+        assert_eq!(3, clock_replacer.evictable_count());
+        assert_eq!(Some(5), clock_replacer.evict());
+        assert_eq!(2, clock_replacer.evictable_count());
+
+        // Pinning a frame the replacer doesn't track is a no-op
+        clock_replacer.pin(1);
+        assert_eq!(2, clock_replacer.evictable_count());
+
+        // Frame 6 loses its second chance, and frame 3 is evicted
+        assert_eq!(Some(3), clock_replacer.evict());
+        assert_eq!(1, clock_replacer.evictable_count());
+
+        // Insert frame 1 again and make it evictable
+        clock_replacer.record_access(1, AccessType::Unknown);
+        clock_replacer.record_access(1, AccessType::Unknown);
+        clock_replacer.unpin(1);
+        assert_eq!(2, clock_replacer.evictable_count());
+
+        // Evict the last two frames
+        assert_eq!(Some(6), clock_replacer.evict());
+        assert_eq!(1, clock_replacer.evictable_count());
+        assert_eq!(Some(1), clock_replacer.evict());
+        assert_eq!(0, clock_replacer.evictable_count());
+
+        // Insert frame 1 again and mark it as non-evictable
+        clock_replacer.record_access(1, AccessType::Unknown);
+        clock_replacer.pin(1);
+        assert_eq!(0, clock_replacer.evictable_count());
+
+        // A failed eviction should not change the size of the replacer
+        assert_eq!(None, clock_replacer.evict());
+
+        // Mark frame 1 as evictable again and evict it
+        clock_replacer.unpin(1);
+        assert_eq!(1, clock_replacer.evictable_count());
+        assert_eq!(Some(1), clock_replacer.evict());
+        assert_eq!(0, clock_replacer.evictable_count());
+
+        // Ensure that eviction on an empty replacer does nothing strange
+        assert_eq!(None, clock_replacer.evict());
+        assert_eq!(0, clock_replacer.evictable_count());
+
+        // Ensure setting a non-existent frame as evictable does not cause issues
+        clock_replacer.unpin(6);
+    }
+
+    #[test]
+    fn test_clock_replacer_evict() {
+        {
+            // Empty and try removing
+            let mut clock_replacer = ClockReplacer::new(4);
+            assert_eq!(None, clock_replacer.evict());
+        }
+
+        {
+            // Can only evict element if evictable=true
+            let mut clock_replacer = ClockReplacer::new(4);
+            clock_replacer.record_access(2, AccessType::Unknown);
+            clock_replacer.pin(2);
+            assert_eq!(None, clock_replacer.evict());
+            clock_replacer.unpin(2);
+            assert_eq!(Some(2), clock_replacer.evict());
+        }
+
+        {
+            // A frame referenced after the hand cleared its bit gets a second chance
+            let mut clock_replacer = ClockReplacer::new(4);
+            clock_replacer.record_access(1, AccessType::Unknown);
+            clock_replacer.record_access(2, AccessType::Unknown);
+            clock_replacer.record_access(3, AccessType::Unknown);
+            clock_replacer.unpin(1);
+            clock_replacer.unpin(2);
+            clock_replacer.unpin(3);
+
+            assert_eq!(Some(1), clock_replacer.evict());
+            clock_replacer.record_access(2, AccessType::Unknown);
+
+            assert_eq!(Some(3), clock_replacer.evict());
+            assert_eq!(Some(2), clock_replacer.evict());
+        }
+
+        {
+            // Removed frames are forgotten, and the hand resumes where it left off
+            let mut clock_replacer = ClockReplacer::new(4);
+            clock_replacer.record_access(0, AccessType::Unknown);
+            clock_replacer.record_access(1, AccessType::Unknown);
+            clock_replacer.record_access(2, AccessType::Unknown);
+            clock_replacer.unpin(0);
+            clock_replacer.unpin(1);
+            clock_replacer.unpin(2);
+
+            assert_eq!(Some(0), clock_replacer.evict());
+            clock_replacer.remove(1);
+            assert_eq!(1, clock_replacer.evictable_count());
+
+            clock_replacer.record_access(0, AccessType::Unknown);
+            clock_replacer.unpin(0);
+            assert_eq!(Some(2), clock_replacer.evict());
+            assert_eq!(Some(0), clock_replacer.evict());
+        }
+
+        {
+            let mut clock_replacer = ClockReplacer::new(1000);
+            for j in 0..4 {
+                for i in (j * 250)..1000 {
+                    clock_replacer.record_access(i, AccessType::Unknown);
+                    clock_replacer.unpin(i);
+                }
+            }
+            assert_eq!(1000, clock_replacer.evictable_count());
+
+            for i in 250..500 {
+                clock_replacer.pin(i);
+            }
+            assert_eq!(750, clock_replacer.evictable_count());
+
+            for i in 0..100 {
+                clock_replacer.remove(i);
+            }
+            assert_eq!(650, clock_replacer.evictable_count());
+
+            for i in 100..600 {
+                if i < 250 || i >= 500 {
+                    assert_eq!(Some(i), clock_replacer.evict());
+                }
+            }
+            assert_eq!(400, clock_replacer.evictable_count());
+
+            for i in 250..500 {
+                clock_replacer.unpin(i);
+            }
+            assert_eq!(650, clock_replacer.evictable_count());
+
+            for i in 600..750 {
+                clock_replacer.record_access(i, AccessType::Unknown);
+                clock_replacer.record_access(i, AccessType::Unknown);
+            }
+            assert_eq!(650, clock_replacer.evictable_count());
+
+            // Frames [250, 500) kept their reference bits while pinned, and [600, 750) were
+            // just referenced, so the hand evicts [750, 1000) first.
+            for i in 750..1000 {
+                assert_eq!(Some(i), clock_replacer.evict());
+            }
+            assert_eq!(400, clock_replacer.evictable_count());
+
+            for i in 600..750 {
+                assert_eq!(Some(i), clock_replacer.evict());
+            }
+            assert_eq!(250, clock_replacer.evictable_count());
+
+            for i in 250..500 {
+                assert_eq!(Some(i), clock_replacer.evict());
+            }
+            assert_eq!(0, clock_replacer.evictable_count());
+        }
+    }
+}
@@ -1,13 +1,14 @@
-use super::replacer::Replacer;
+use super::replacer::{AccessType, Replacer};
 use crate::typedef::FrameId;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeSet, HashMap, VecDeque};
 
 /// Represents a node in the LRUKReplacer, maintaining access history and evictability status.
 #[derive(Debug)]
 struct LrukNode {
     frame_id: FrameId,
     is_evictable: bool,
-    history: VecDeque<u64>, // Stores the last K access timestamps
+    history: VecDeque<u64>, // Stores the last K uncorrelated access timestamps
+    last_access: u64,       // The most recent access, correlated or not
     k: usize,
 }
 
@@ -18,6 +19,7 @@ impl LrukNode {
             frame_id,
             is_evictable: false,
             history: VecDeque::with_capacity(k),
+            last_access: 0,
             k,
         }
     }
@@ -37,14 +39,26 @@ impl LrukNode {
 
     /// Calculates the backward K-distance of this node.
     fn get_backwards_k_distance(&self, current_timestamp: u64) -> u64 {
-
         if self.has_inf_backward_k_dist() {
             return u64::MAX;
         }
-        
+
         current_timestamp - self.get_earliest_timestamp()
     }
 
+    /// Returns the key this node is ordered by in the replacer's eviction index.
+    ///
+    /// Nodes with an infinite backward k-distance sort first. Within each group, the node with
+    /// the earliest timestamp sorts first: for infinite distances that is the tie-breaker, and
+    /// for finite distances the earliest k-th most recent access is the largest distance.
+    fn eviction_key(&self) -> (bool, u64, FrameId) {
+        (
+            !self.has_inf_backward_k_dist(),
+            self.get_earliest_timestamp(),
+            self.frame_id,
+        )
+    }
+
     /// Inserts a new access timestamp, maintaining the last K timestamps.
     fn insert_history_timestamp(&mut self, current_timestamp: u64) {
         assert!(self.history.is_empty() || current_timestamp > *self.history.back().unwrap());
@@ -53,24 +67,55 @@ impl LrukNode {
             self.history.pop_front();
         }
     }
+
+    /// Records an access, treating it as correlated with the previous one if it happens less than
+    /// `correlated_period` timestamps after it.
+    ///
+    /// Following the LRU-K paper, a correlated access only moves `last_access`. The next
+    /// uncorrelated access closes the correlated period, shifting the older history entries
+    /// forward by its length before being recorded, so that a burst of accesses counts as one.
+    fn record_access(&mut self, current_timestamp: u64, correlated_period: u64) {
+        if self.history.is_empty() {
+            self.insert_history_timestamp(current_timestamp);
+        } else if current_timestamp - self.last_access >= correlated_period {
+            let period = self.last_access - *self.history.back().unwrap();
+            for timestamp in self.history.iter_mut() {
+                *timestamp += period;
+            }
+            self.insert_history_timestamp(current_timestamp);
+        }
+        self.last_access = current_timestamp;
+    }
 }
 
 /// Implements the LRU-K replacement policy.
+///
+/// Evictable nodes are kept in an ordered index (see [`LrukNode::eviction_key`]), so that
+/// eviction is O(log n) rather than a scan over every node.
 #[derive(Debug)]
 pub(crate) struct LrukReplacer {
     node_store: HashMap<FrameId, LrukNode>,
-    evictable_size: usize, // Number of evictable nodes
+    evictable: BTreeSet<(bool, u64, FrameId)>, // Eviction index over the evictable nodes
     current_timestamp: u64,
-    k: usize, // Number of accesses to track
+    correlated_period: u64, // Accesses closer than this to the previous one are correlated
+    k: usize,               // Number of accesses to track
 }
 
 impl LrukReplacer {
     /// Creates a new LRU-K replacer instance.
     pub(crate) fn new(k: usize) -> Self {
+        Self::with_correlated_reference_period(k, 0)
+    }
+
+    /// Creates a new LRU-K replacer instance that treats accesses to a frame less than
+    /// `correlated_period` timestamps apart as a single access (the "correlated reference
+    /// period" of the LRU-K paper).
+    pub(crate) fn with_correlated_reference_period(k: usize, correlated_period: u64) -> Self {
         LrukReplacer {
             node_store: HashMap::new(),
-            evictable_size: 0,
+            evictable: BTreeSet::new(),
             current_timestamp: 0,
+            correlated_period,
             k,
         }
     }
@@ -85,108 +130,69 @@ impl LrukReplacer {
 
 impl Replacer for LrukReplacer {
     /// Records access to a frame and updates its history.
-    fn record_access(&mut self, frame_id: FrameId) {
-        
-        if !self.node_store.contains_key(&frame_id) {
-            let new_node = LrukNode::new(frame_id, self.k);
-            self.node_store.insert(frame_id, new_node);
+    ///
+    /// Scan accesses don't count toward the history of a frame that's already tracked, so a
+    /// sequential scan passing over a page doesn't make it look any hotter.
+    fn record_access(&mut self, frame_id: FrameId, access_type: AccessType) {
+        if access_type == AccessType::Scan && self.node_store.contains_key(&frame_id) {
+            return;
         }
-        
-        let accessed_frame = self.node_store.get_mut(&frame_id).unwrap();
-        accessed_frame.insert_history_timestamp(self.current_timestamp);
-        self.advance_timestamp();
-    }
 
-    /// Pins a frame, making it non-evictable.
-    fn pin(&mut self, frame_id: FrameId) {
+        let timestamp = self.advance_timestamp();
+        let node = self
+            .node_store
+            .entry(frame_id)
+            .or_insert_with(|| LrukNode::new(frame_id, self.k));
 
-        if !self.node_store.contains_key(&frame_id) {
-            return;
+        if node.is_evictable {
+            self.evictable.remove(&node.eviction_key());
         }
-        
-        let accessed_frame = self.node_store.get_mut(&frame_id).unwrap();
-        
-        if accessed_frame.is_evictable == true {
-            accessed_frame.is_evictable = false;
-
-            self.evictable_size -= 1;  
+        node.record_access(timestamp, self.correlated_period);
+        if node.is_evictable {
+            self.evictable.insert(node.eviction_key());
         }
-        
+    }
 
+    /// Pins a frame, making it non-evictable.
+    fn pin(&mut self, frame_id: FrameId) {
+        if let Some(node) = self.node_store.get_mut(&frame_id) {
+            if node.is_evictable {
+                node.is_evictable = false;
+                self.evictable.remove(&node.eviction_key());
+            }
+        }
     }
 
     /// Unpins a frame, making it evictable.
     fn unpin(&mut self, frame_id: FrameId) {
-        
-        if !self.node_store.contains_key(&frame_id) {
-            return;
-        }
-        
-        let accessed_frame = self.node_store.get_mut(&frame_id).unwrap();
-        
-        if accessed_frame.is_evictable == false {
-            accessed_frame.is_evictable = true;
-            self.evictable_size += 1; 
+        if let Some(node) = self.node_store.get_mut(&frame_id) {
+            if !node.is_evictable {
+                node.is_evictable = true;
+                self.evictable.insert(node.eviction_key());
+            }
         }
-
-
     }
 
     /// Evicts the frame with the largest backward k-distance.
     fn evict(&mut self) -> Option<FrameId> {
-        let mut max_frame_id = 0;
-        let mut max_k_dist = 0;
-        let mut earliest = u64::MAX;
-        let mut modded = false;
-        
-        for (frame_id, node) in self.node_store.iter() {
-            if node.is_evictable == true && (node.get_backwards_k_distance(self.current_timestamp) > max_k_dist) {
-                max_k_dist = node.get_backwards_k_distance(self.current_timestamp);
-                max_frame_id = *frame_id;
-                earliest = node.get_earliest_timestamp();
-                modded = true;
-            }
-
-            else if node.is_evictable == true && (node.get_backwards_k_distance(self.current_timestamp) == max_k_dist) {
-                if node.get_earliest_timestamp() < earliest {
-                    max_k_dist = node.get_backwards_k_distance(self.current_timestamp);
-                    max_frame_id = *frame_id;
-                    earliest = node.get_earliest_timestamp();
-                    modded = true;
-                }
-            }
-            
-        }
-        
-        if modded {
-            self.node_store.remove(&max_frame_id);
-            self.evictable_size -= 1;
-            return Some(max_frame_id)
-        }
-        
-        else {
-            return None
-        }
-
+        let (_, _, frame_id) = self.evictable.pop_first()?;
+        self.node_store.remove(&frame_id);
+        Some(frame_id)
     }
 
     /// Removes a frame from the replacer if it is evictable.
     fn remove(&mut self, frame_id: FrameId) {
-
-        if !self.node_store.contains_key(&frame_id) {
-            return;
-        }
-        
-        let accessed_frame = self.node_store.get_mut(&frame_id).unwrap();
-        if accessed_frame.is_evictable {
-            self.node_store.remove(&frame_id);
-            self.evictable_size -= 1;
+        if let Some(node) = self.node_store.get(&frame_id) {
+            if node.is_evictable {
+                self.evictable.remove(&node.eviction_key());
+                self.node_store.remove(&frame_id);
+            }
         }
     }
 
     /// Returns the number of evictable frames.
     fn evictable_count(&self) -> usize {
-        self.evictable_size
+        self.evictable.len()
     }
 }
 
@@ -198,12 +204,12 @@ mod tests {
     fn test_lruk_replacer_one() {
         let mut lru_replacer = LrukReplacer::new(2);
 
-        lru_replacer.record_access(1);
-        lru_replacer.record_access(2);
-        lru_replacer.record_access(3);
-        lru_replacer.record_access(4);
-        lru_replacer.record_access(5);
-        lru_replacer.record_access(6);
+        lru_replacer.record_access(1, AccessType::Unknown);
+        lru_replacer.record_access(2, AccessType::Unknown);
+        lru_replacer.record_access(3, AccessType::Unknown);
+        lru_replacer.record_access(4, AccessType::Unknown);
+        lru_replacer.record_access(5, AccessType::Unknown);
+        lru_replacer.record_access(6, AccessType::Unknown);
         lru_replacer.unpin(1);
         lru_replacer.unpin(2);
         lru_replacer.unpin(3);
@@ -213,16 +219,16 @@ mod tests {
 
         assert_eq!(5, lru_replacer.evictable_count());
 
-        lru_replacer.record_access(1);
+        lru_replacer.record_access(1, AccessType::Unknown);
         assert_eq!(Some(2), lru_replacer.evict());
         assert_eq!(Some(3), lru_replacer.evict());
         assert_eq!(Some(4), lru_replacer.evict());
         assert_eq!(2, lru_replacer.evictable_count());
 
-        lru_replacer.record_access(3);
-        lru_replacer.record_access(4);
-        lru_replacer.record_access(5);
-        lru_replacer.record_access(4);
+        lru_replacer.record_access(3, AccessType::Unknown);
+        lru_replacer.record_access(4, AccessType::Unknown);
+        lru_replacer.record_access(5, AccessType::Unknown);
+        lru_replacer.record_access(4, AccessType::Unknown);
         lru_replacer.unpin(3);
         lru_replacer.unpin(4);
         assert_eq!(4, lru_replacer.evictable_count());
@@ -240,8 +246,8 @@ mod tests {
         assert_eq!(Some(5), lru_replacer.evict());
         assert_eq!(1, lru_replacer.evictable_count());
 
-        lru_replacer.record_access(1);
-        lru_replacer.record_access(1);
+        lru_replacer.record_access(1, AccessType::Unknown);
+        lru_replacer.record_access(1, AccessType::Unknown);
         lru_replacer.unpin(1);
         assert_eq!(2, lru_replacer.evictable_count());
 
@@ -250,7 +256,7 @@ mod tests {
         assert_eq!(Some(1), lru_replacer.evict());
         assert_eq!(0, lru_replacer.evictable_count());
 
-        lru_replacer.record_access(1);
+        lru_replacer.record_access(1, AccessType::Unknown);
         lru_replacer.pin(1);
         assert_eq!(0, lru_replacer.evictable_count());
 
@@ -273,12 +279,12 @@ mod tests {
         let mut lru_replacer = LrukReplacer::new(2);
 
         // Add six frames to the replacer. Frame 6 is non-evictable.
-        lru_replacer.record_access(1);
-        lru_replacer.record_access(2);
-        lru_replacer.record_access(3);
-        lru_replacer.record_access(4);
-        lru_replacer.record_access(5);
-        lru_replacer.record_access(6);
+        lru_replacer.record_access(1, AccessType::Unknown);
+        lru_replacer.record_access(2, AccessType::Unknown);
+        lru_replacer.record_access(3, AccessType::Unknown);
+        lru_replacer.record_access(4, AccessType::Unknown);
+        lru_replacer.record_access(5, AccessType::Unknown);
+        lru_replacer.record_access(6, AccessType::Unknown);
         lru_replacer.unpin(1);
         lru_replacer.unpin(2);
         lru_replacer.unpin(3);
@@ -290,7 +296,7 @@ mod tests {
         assert_eq!(5, lru_replacer.evictable_count());
 
         // Record an access for frame 1
-        lru_replacer.record_access(1);
+        lru_replacer.record_access(1, AccessType::Unknown);
 
         // Evict three pages
         assert_eq!(Some(2), lru_replacer.evict());
@@ -299,10 +305,10 @@ mod tests {
         assert_eq!(2, lru_replacer.evictable_count());
 
         // Insert new frames [3, 4] and update history
-        lru_replacer.record_access(3);
-        lru_replacer.record_access(4);
-        lru_replacer.record_access(5);
-        lru_replacer.record_access(4);
+        lru_replacer.record_access(3, AccessType::Unknown);
+        lru_replacer.record_access(4, AccessType::Unknown);
+        lru_replacer.record_access(5, AccessType::Unknown);
+        lru_replacer.record_access(4, AccessType::Unknown);
         lru_replacer.unpin(3);
         lru_replacer.unpin(4);
         assert_eq!(4, lru_replacer.evictable_count());
@@ -326,8 +332,8 @@ mod tests {
         assert_eq!(1, lru_replacer.evictable_count());
 
         // Update history for frame 1 and make it evictable
-        lru_replacer.record_access(1);
-        lru_replacer.record_access(1);
+        lru_replacer.record_access(1, AccessType::Unknown);
+        lru_replacer.record_access(1, AccessType::Unknown);
         lru_replacer.unpin(1);
         assert_eq!(2, lru_replacer.evictable_count());
 
@@ -338,7 +344,7 @@ mod tests {
         assert_eq!(0, lru_replacer.evictable_count());
 
         // Insert frame 1 again and mark it as non-evictable
-        lru_replacer.record_access(1);
+        lru_replacer.record_access(1, AccessType::Unknown);
         lru_replacer.pin(1);
         assert_eq!(0, lru_replacer.evictable_count());
 
@@ -370,7 +376,7 @@ mod tests {
         {
             // Can only evict element if evictable=true
             let mut lru_replacer = LrukReplacer::new(2);
-            lru_replacer.record_access(2);
+            lru_replacer.record_access(2, AccessType::Unknown);
             lru_replacer.pin(2);
             assert_eq!(None, lru_replacer.evict());
             lru_replacer.unpin(2);
@@ -380,10 +386,10 @@ mod tests {
         {
             // Elements with less than k history should have max backward k-dist and get evicted first
             let mut lru_replacer = LrukReplacer::new(3);
-            lru_replacer.record_access(1);
-            lru_replacer.record_access(1);
-            lru_replacer.record_access(2);
-            lru_replacer.record_access(1);
+            lru_replacer.record_access(1, AccessType::Unknown);
+            lru_replacer.record_access(1, AccessType::Unknown);
+            lru_replacer.record_access(2, AccessType::Unknown);
+            lru_replacer.record_access(1, AccessType::Unknown);
             lru_replacer.unpin(2);
             lru_replacer.unpin(1);
 
@@ -394,18 +400,18 @@ mod tests {
         {
             // Select element with largest backward k-dist to evict
             let mut lru_replacer = LrukReplacer::new(3);
-            lru_replacer.record_access(1);
-            lru_replacer.record_access(2);
-            lru_replacer.record_access(3);
-            lru_replacer.record_access(3);
-            lru_replacer.record_access(3);
-            lru_replacer.record_access(2);
-            lru_replacer.record_access(2);
-            lru_replacer.record_access(1);
-            lru_replacer.record_access(1);
-            lru_replacer.record_access(3);
-            lru_replacer.record_access(2);
-            lru_replacer.record_access(1);
+            lru_replacer.record_access(1, AccessType::Unknown);
+            lru_replacer.record_access(2, AccessType::Unknown);
+            lru_replacer.record_access(3, AccessType::Unknown);
+            lru_replacer.record_access(3, AccessType::Unknown);
+            lru_replacer.record_access(3, AccessType::Unknown);
+            lru_replacer.record_access(2, AccessType::Unknown);
+            lru_replacer.record_access(2, AccessType::Unknown);
+            lru_replacer.record_access(1, AccessType::Unknown);
+            lru_replacer.record_access(1, AccessType::Unknown);
+            lru_replacer.record_access(3, AccessType::Unknown);
+            lru_replacer.record_access(2, AccessType::Unknown);
+            lru_replacer.record_access(1, AccessType::Unknown);
             lru_replacer.unpin(2);
             lru_replacer.unpin(1);
             lru_replacer.unpin(3);
@@ -417,17 +423,17 @@ mod tests {
 
         {
             let mut lru_replacer = LrukReplacer::new(3);
-            lru_replacer.record_access(2);
-            lru_replacer.record_access(2);
-            lru_replacer.record_access(2);
-            lru_replacer.record_access(1);
-            lru_replacer.record_access(1);
+            lru_replacer.record_access(2, AccessType::Unknown);
+            lru_replacer.record_access(2, AccessType::Unknown);
+            lru_replacer.record_access(2, AccessType::Unknown);
+            lru_replacer.record_access(1, AccessType::Unknown);
+            lru_replacer.record_access(1, AccessType::Unknown);
             lru_replacer.unpin(2);
             lru_replacer.unpin(1);
 
             assert_eq!(Some(1), lru_replacer.evict());
 
-            lru_replacer.record_access(1);
+            lru_replacer.record_access(1, AccessType::Unknown);
             lru_replacer.unpin(1);
 
             assert_eq!(Some(1), lru_replacer.evict());
@@ -435,23 +441,23 @@ mod tests {
 
         {
             let mut lru_replacer = LrukReplacer::new(3);
-            lru_replacer.record_access(1);
-            lru_replacer.record_access(2);
-            lru_replacer.record_access(3);
-            lru_replacer.record_access(4);
-            lru_replacer.record_access(1);
-            lru_replacer.record_access(2);
-            lru_replacer.record_access(3);
-            lru_replacer.record_access(1);
-            lru_replacer.record_access(2);
+            lru_replacer.record_access(1, AccessType::Unknown);
+            lru_replacer.record_access(2, AccessType::Unknown);
+            lru_replacer.record_access(3, AccessType::Unknown);
+            lru_replacer.record_access(4, AccessType::Unknown);
+            lru_replacer.record_access(1, AccessType::Unknown);
+            lru_replacer.record_access(2, AccessType::Unknown);
+            lru_replacer.record_access(3, AccessType::Unknown);
+            lru_replacer.record_access(1, AccessType::Unknown);
+            lru_replacer.record_access(2, AccessType::Unknown);
             lru_replacer.unpin(1);
             lru_replacer.unpin(2);
             lru_replacer.unpin(3);
             lru_replacer.unpin(4);
 
             assert_eq!(Some(3), lru_replacer.evict());
-            lru_replacer.record_access(4);
-            lru_replacer.record_access(4);
+            lru_replacer.record_access(4, AccessType::Unknown);
+            lru_replacer.record_access(4, AccessType::Unknown);
 
             assert_eq!(Some(1), lru_replacer.evict());
             assert_eq!(Some(2), lru_replacer.evict());
@@ -460,21 +466,21 @@ mod tests {
 
         {
             let mut lru_replacer = LrukReplacer::new(2);
-            lru_replacer.record_access(1);
-            lru_replacer.record_access(2);
-            lru_replacer.record_access(3);
-            lru_replacer.record_access(4);
-            lru_replacer.record_access(1);
-            lru_replacer.record_access(2);
-            lru_replacer.record_access(3);
-            lru_replacer.record_access(4);
+            lru_replacer.record_access(1, AccessType::Unknown);
+            lru_replacer.record_access(2, AccessType::Unknown);
+            lru_replacer.record_access(3, AccessType::Unknown);
+            lru_replacer.record_access(4, AccessType::Unknown);
+            lru_replacer.record_access(1, AccessType::Unknown);
+            lru_replacer.record_access(2, AccessType::Unknown);
+            lru_replacer.record_access(3, AccessType::Unknown);
+            lru_replacer.record_access(4, AccessType::Unknown);
 
             lru_replacer.unpin(2);
             lru_replacer.unpin(1);
 
             assert_eq!(Some(1), lru_replacer.evict());
 
-            lru_replacer.record_access(5);
+            lru_replacer.record_access(5, AccessType::Unknown);
             lru_replacer.unpin(5);
             assert_eq!(Some(5), lru_replacer.evict());
         }
@@ -483,7 +489,7 @@ mod tests {
             let mut lru_replacer = LrukReplacer::new(3);
             for j in 0..4 {
                 for i in (j * 250)..1000 {
-                    lru_replacer.record_access(i);
+                    lru_replacer.record_access(i, AccessType::Unknown);
                     lru_replacer.unpin(i);
                 }
             }
@@ -512,8 +518,8 @@ mod tests {
             assert_eq!(650, lru_replacer.evictable_count());
 
             for i in 600..750 {
-                lru_replacer.record_access(i);
-                lru_replacer.record_access(i);
+                lru_replacer.record_access(i, AccessType::Unknown);
+                lru_replacer.record_access(i, AccessType::Unknown);
             }
             assert_eq!(650, lru_replacer.evictable_count());
 
@@ -534,4 +540,126 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_lruk_replacer_matches_linear_scan() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        // A reference model that evicts by scanning every frame, as the replacer used to.
+        let k = 3;
+        let mut model: HashMap<FrameId, (VecDeque<u64>, bool)> = HashMap::new();
+        let mut timestamp = 0;
+        let mut lru_replacer = LrukReplacer::new(k);
+        let mut rng = StdRng::seed_from_u64(339);
+
+        for _ in 0..10_000 {
+            let frame_id = rng.random_range(0..64);
+            match rng.random_range(0..5) {
+                0 | 1 => {
+                    lru_replacer.record_access(frame_id, AccessType::Unknown);
+                    let (history, _) = model.entry(frame_id).or_default();
+                    history.push_back(timestamp);
+                    if history.len() > k {
+                        history.pop_front();
+                    }
+                    timestamp += 1;
+                }
+                2 => {
+                    lru_replacer.unpin(frame_id);
+                    if let Some((_, evictable)) = model.get_mut(&frame_id) {
+                        *evictable = true;
+                    }
+                }
+                3 => {
+                    lru_replacer.pin(frame_id);
+                    if let Some((_, evictable)) = model.get_mut(&frame_id) {
+                        *evictable = false;
+                    }
+                }
+                _ => {
+                    let expected = model
+                        .iter()
+                        .filter(|(_, (_, evictable))| *evictable)
+                        .min_by_key(|(_, (history, _))| (history.len() >= k, history[0]))
+                        .map(|(frame_id, _)| *frame_id);
+                    assert_eq!(expected, lru_replacer.evict());
+                    if let Some(frame_id) = expected {
+                        model.remove(&frame_id);
+                    }
+                }
+            }
+            let evictable = model.values().filter(|(_, evictable)| *evictable).count();
+            assert_eq!(evictable, lru_replacer.evictable_count());
+        }
+    }
+
+    #[test]
+    fn test_lruk_replacer_correlated_reference_period() {
+        let mut lru_replacer = LrukReplacer::with_correlated_reference_period(2, 2);
+
+        // Frame 1's accesses are correlated and count as one, while frame 2's are not.
+        lru_replacer.record_access(1, AccessType::Unknown);
+        lru_replacer.record_access(1, AccessType::Unknown);
+        lru_replacer.record_access(2, AccessType::Unknown);
+        lru_replacer.record_access(3, AccessType::Unknown);
+        lru_replacer.record_access(2, AccessType::Unknown);
+        lru_replacer.unpin(1);
+        lru_replacer.unpin(2);
+        lru_replacer.unpin(3);
+
+        // Frames 1 and 3 have fewer than k uncorrelated accesses, so they go first.
+        assert_eq!(Some(1), lru_replacer.evict());
+        assert_eq!(Some(3), lru_replacer.evict());
+        assert_eq!(Some(2), lru_replacer.evict());
+
+        // An uncorrelated access shifts the history forward by the length of the correlated
+        // period that preceded it.
+        let mut lru_replacer = LrukReplacer::with_correlated_reference_period(2, 3);
+        lru_replacer.record_access(1, AccessType::Unknown); // t = 0
+        lru_replacer.record_access(2, AccessType::Unknown); // t = 1
+        lru_replacer.record_access(1, AccessType::Unknown); // t = 2, correlated
+        lru_replacer.record_access(3, AccessType::Unknown); // t = 3
+        lru_replacer.record_access(2, AccessType::Unknown); // t = 4, history becomes [1, 4]
+        lru_replacer.record_access(1, AccessType::Unknown); // t = 5, history becomes [0 + 2, 5]
+        lru_replacer.record_access(3, AccessType::Unknown); // t = 6, history becomes [3, 6]
+        lru_replacer.unpin(1);
+        lru_replacer.unpin(2);
+        lru_replacer.unpin(3);
+        assert_eq!(Some(2), lru_replacer.evict());
+        assert_eq!(Some(1), lru_replacer.evict());
+        assert_eq!(Some(3), lru_replacer.evict());
+
+        // An access exactly one period after the previous one is no longer correlated with it.
+        let mut lru_replacer = LrukReplacer::with_correlated_reference_period(2, 2);
+        lru_replacer.record_access(1, AccessType::Unknown); // t = 0
+        lru_replacer.record_access(2, AccessType::Unknown); // t = 1
+        lru_replacer.record_access(1, AccessType::Unknown); // t = 2, uncorrelated
+        lru_replacer.unpin(1);
+        lru_replacer.unpin(2);
+        assert_eq!(Some(2), lru_replacer.evict());
+        assert_eq!(Some(1), lru_replacer.evict());
+    }
+
+    #[test]
+    fn test_lruk_replacer_scan_access() {
+        let mut lru_replacer = LrukReplacer::new(2);
+
+        lru_replacer.record_access(1, AccessType::Lookup);
+        lru_replacer.record_access(2, AccessType::Lookup);
+        lru_replacer.record_access(1, AccessType::Lookup);
+
+        // A scan passing over frame 2 doesn't add to its history...
+        lru_replacer.record_access(2, AccessType::Scan);
+        lru_replacer.record_access(2, AccessType::Scan);
+        lru_replacer.unpin(1);
+        lru_replacer.unpin(2);
+
+        // ...so it still has an infinite backward k-distance and is evicted first.
+        assert_eq!(Some(2), lru_replacer.evict());
+        assert_eq!(Some(1), lru_replacer.evict());
+
+        // A frame first seen by a scan is still tracked.
+        lru_replacer.record_access(3, AccessType::Scan);
+        lru_replacer.unpin(3);
+        assert_eq!(Some(3), lru_replacer.evict());
+    }
 }
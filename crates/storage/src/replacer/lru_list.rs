@@ -0,0 +1,101 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// An ordered set of entries, kept from least to most recently pushed.
+///
+/// Used by the replacers that juggle several recency lists (and ghost lists of evicted pages) at
+/// once. Pushing, removing and popping are all O(log n).
+#[derive(Debug)]
+pub(crate) struct LruList<T> {
+    entries: BTreeMap<u64, T>, // Stamp -> entry, oldest first
+    stamps: HashMap<T, u64>,   // Entry -> stamp
+    next_stamp: u64,
+}
+
+impl<T: Copy + Eq + Hash> LruList<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            stamps: HashMap::new(),
+            next_stamp: 0,
+        }
+    }
+
+    /// Returns the number of entries in the list.
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks if the list has no entries.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Checks if `value` is in the list.
+    pub(crate) fn contains(&self, value: &T) -> bool {
+        self.stamps.contains_key(value)
+    }
+
+    /// Pushes `value` to the most recent end of the list, moving it there if already present.
+    pub(crate) fn push_back(&mut self, value: T) {
+        self.remove(&value);
+        let stamp = self.next_stamp;
+        self.next_stamp += 1;
+        self.entries.insert(stamp, value);
+        self.stamps.insert(value, stamp);
+    }
+
+    /// Removes `value` from the list, returning whether it was present.
+    pub(crate) fn remove(&mut self, value: &T) -> bool {
+        match self.stamps.remove(value) {
+            Some(stamp) => {
+                self.entries.remove(&stamp);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes and returns the least recent entry.
+    pub(crate) fn pop_front(&mut self) -> Option<T> {
+        let (_, value) = self.entries.pop_first()?;
+        self.stamps.remove(&value);
+        Some(value)
+    }
+
+    /// Iterates over the entries from least to most recent.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &T> {
+        self.entries.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_list() {
+        let mut list = LruList::new();
+        assert!(list.is_empty());
+
+        list.push_back(1);
+        list.push_back(2);
+        list.push_back(3);
+        assert_eq!(3, list.len());
+        assert_eq!(vec![1, 2, 3], list.iter().copied().collect::<Vec<_>>());
+
+        // Pushing an existing entry moves it to the back.
+        list.push_back(1);
+        assert_eq!(3, list.len());
+        assert_eq!(vec![2, 3, 1], list.iter().copied().collect::<Vec<_>>());
+
+        assert!(list.remove(&3));
+        assert!(!list.remove(&3));
+        assert!(!list.contains(&3));
+
+        assert_eq!(Some(2), list.pop_front());
+        assert_eq!(Some(1), list.pop_front());
+        assert_eq!(None, list.pop_front());
+        assert!(list.is_empty());
+    }
+}
@@ -1,7 +1,7 @@
 use crate::typedef::FrameId;
 use std::collections::HashMap;
 
-use super::replacer::Replacer;
+use super::replacer::{AccessType, Replacer};
 
 #[derive(Debug)]
 struct LruNode {
@@ -51,7 +51,7 @@ impl Replacer for LruReplacer {
 
     /// Records an access and updates the timestamp.
     /// If the frame_id is new, create a new node.
-    fn record_access(&mut self, frame_id: FrameId) {
+    fn record_access(&mut self, frame_id: FrameId, _access_type: AccessType) {
         todo!("Implement record_access")
     }
 
@@ -74,9 +74,9 @@ mod tests {
     fn test_record_access() {
         let mut lru = LruReplacer::new();
 
-        lru.record_access(1);
-        lru.record_access(2);
-        lru.record_access(3);
+        lru.record_access(1, AccessType::Unknown);
+        lru.record_access(2, AccessType::Unknown);
+        lru.record_access(3, AccessType::Unknown);
 
         assert_eq!(lru.evictable_count(), 3);
     }
@@ -85,9 +85,9 @@ mod tests {
     fn test_evict() {
         let mut lru = LruReplacer::new();
 
-        lru.record_access(1);
-        lru.record_access(2);
-        lru.record_access(3);
+        lru.record_access(1, AccessType::Unknown);
+        lru.record_access(2, AccessType::Unknown);
+        lru.record_access(3, AccessType::Unknown);
 
         assert_eq!(lru.evictable_count(), 3);
 
@@ -101,9 +101,9 @@ mod tests {
     fn test_pin() {
         let mut lru = LruReplacer::new();
 
-        lru.record_access(1);
-        lru.record_access(2);
-        lru.record_access(3);
+        lru.record_access(1, AccessType::Unknown);
+        lru.record_access(2, AccessType::Unknown);
+        lru.record_access(3, AccessType::Unknown);
 
         lru.pin(2);
 
@@ -117,9 +117,9 @@ mod tests {
     fn test_unpin() {
         let mut lru = LruReplacer::new();
 
-        lru.record_access(1);
-        lru.record_access(2);
-        lru.record_access(3);
+        lru.record_access(1, AccessType::Unknown);
+        lru.record_access(2, AccessType::Unknown);
+        lru.record_access(3, AccessType::Unknown);
 
         lru.pin(2);
         assert_eq!(lru.evictable_count(), 2); // 2 is pinned, only 1 & 3 are evictable
@@ -135,9 +135,9 @@ mod tests {
     fn test_remove() {
         let mut lru = LruReplacer::new();
 
-        lru.record_access(1);
-        lru.record_access(2);
-        lru.record_access(3);
+        lru.record_access(1, AccessType::Unknown);
+        lru.record_access(2, AccessType::Unknown);
+        lru.record_access(3, AccessType::Unknown);
 
         lru.remove(2); // Remove frame 2 directly
 
@@ -151,13 +151,13 @@ mod tests {
     fn test_record_access_multiple_times() {
         let mut lru = LruReplacer::new();
 
-        lru.record_access(1);
-        lru.record_access(2);
-        lru.record_access(3);
+        lru.record_access(1, AccessType::Unknown);
+        lru.record_access(2, AccessType::Unknown);
+        lru.record_access(3, AccessType::Unknown);
 
         assert_eq!(lru.evictable_count(), 3);
 
-        lru.record_access(1);
+        lru.record_access(1, AccessType::Unknown);
 
         assert_eq!(lru.evict(), Some(2));
         assert_eq!(lru.evict(), Some(3));
@@ -1,3 +1,7 @@
+pub(crate) mod arc_replacer;
+pub(crate) mod clock_replacer;
 pub(crate) mod lru_k_replacer;
+pub(crate) mod lru_list;
 pub(crate) mod lru_replacer;
 pub(crate) mod replacer;
+pub(crate) mod two_queue_replacer;
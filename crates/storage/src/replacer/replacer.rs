@@ -1,6 +1,16 @@
 use std::fmt::Debug;
 
-use crate::typedef::FrameId;
+use crate::typedef::{FrameId, PageId};
+
+/// The kind of operation a page is being accessed for. Replacers can use this as a hint, e.g. to
+/// keep a sequential scan from flushing out pages that point lookups keep coming back to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessType {
+    Unknown,
+    Lookup,
+    Scan,
+    Index,
+}
 
 pub trait Replacer: Send + Sync + Debug {
     /// Marks a frame as unpinned, making it eligible for eviction.
@@ -9,9 +19,15 @@ pub trait Replacer: Send + Sync + Debug {
     /// Marks a frame as pinned, preventing it from being evicted.
     fn pin(&mut self, frame_id: FrameId);
 
-    /// Record the event that the given frame id is accessed at current timestamp.
-    /// Create a new entry if frame id has not been seen before.
-    fn record_access(&mut self, frame_id: FrameId);
+    /// Record the event that the given frame id is accessed at current timestamp, for the given
+    /// kind of operation. Create a new entry if frame id has not been seen before.
+    fn record_access(&mut self, frame_id: FrameId, access_type: AccessType);
+
+    /// Record that the page `page_id` has just been loaded into the given frame. The buffer pool
+    /// calls this right before the frame's first `record_access`, so policies that remember the
+    /// pages they evicted (e.g. in ghost lists) can recognize them when they come back.
+    /// Policies that only reason about frames can ignore it.
+    fn record_page(&mut self, _frame_id: FrameId, _page_id: PageId) {}
 
     /// Attempts to evict a page in frame based on the replacement policy.
     /// Returns `Some(frame_id)` if a page in frame is evicted, otherwise `None`.
@@ -0,0 +1,235 @@
+use super::lru_list::LruList;
+use super::replacer::{AccessType, Replacer};
+use crate::typedef::{FrameId, PageId};
+use std::collections::HashMap;
+
+/// Represents a frame tracked by the TwoQueueReplacer.
+#[derive(Debug)]
+struct TwoQueueNode {
+    page_id: Option<PageId>, // The page held by the frame, if the buffer pool told us
+    is_evictable: bool,
+    is_loading: bool, // Set until the access that follows `record_page`
+}
+
+impl TwoQueueNode {
+    /// Creates a TwoQueueNode, which is not evictable by default.
+    fn new(page_id: Option<PageId>) -> Self {
+        Self {
+            page_id,
+            is_evictable: false,
+            is_loading: page_id.is_some(),
+        }
+    }
+}
+
+/// Implements the 2Q replacement policy.
+///
+/// Frames holding a page for the first time enter the FIFO queue `a1_in`, and re-accessing them
+/// there does not promote them, so a one-off scan only churns `a1_in`. Pages evicted from `a1_in`
+/// are remembered in the ghost queue `a1_out`; if such a page is loaded again it has proven to
+/// be hot and goes straight into the LRU list `am`.
+#[derive(Debug)]
+pub(crate) struct TwoQueueReplacer {
+    node_store: HashMap<FrameId, TwoQueueNode>,
+    a1_in: LruList<FrameId>,
+    a1_out: LruList<PageId>,
+    am: LruList<FrameId>,
+    max_a1_in: usize, // `Kin`: a1_in is preferred for eviction once larger than this
+    max_a1_out: usize, // `Kout`: the number of evicted pages remembered
+    evictable_size: usize, // Number of evictable nodes
+}
+
+impl TwoQueueReplacer {
+    /// Creates a new 2Q replacer for a buffer pool with `num_frames` frames, using the sizes
+    /// recommended by the paper: a quarter of the pool for `a1_in`, and half of it for `a1_out`.
+    pub(crate) fn new(num_frames: usize) -> Self {
+        TwoQueueReplacer {
+            node_store: HashMap::new(),
+            a1_in: LruList::new(),
+            a1_out: LruList::new(),
+            am: LruList::new(),
+            max_a1_in: (num_frames / 4).max(1),
+            max_a1_out: (num_frames / 2).max(1),
+            evictable_size: 0,
+        }
+    }
+
+    /// Returns the oldest evictable frame in `list`.
+    fn oldest_evictable(&self, list: &LruList<FrameId>) -> Option<FrameId> {
+        list.iter()
+            .find(|frame_id| self.node_store[frame_id].is_evictable)
+            .copied()
+    }
+
+    /// Stops tracking a frame without remembering its page in `a1_out`.
+    fn untrack(&mut self, frame_id: FrameId) {
+        if let Some(node) = self.node_store.remove(&frame_id) {
+            if node.is_evictable {
+                self.evictable_size -= 1;
+            }
+            self.a1_in.remove(&frame_id);
+            self.am.remove(&frame_id);
+        }
+    }
+}
+
+impl Replacer for TwoQueueReplacer {
+    /// Records access to a frame. Only frames in `am` are moved on access.
+    fn record_access(&mut self, frame_id: FrameId, _access_type: AccessType) {
+        match self.node_store.get_mut(&frame_id) {
+            None => {
+                self.node_store.insert(frame_id, TwoQueueNode::new(None));
+                self.a1_in.push_back(frame_id);
+            }
+            // This is the access that loaded the page, which `record_page` already placed.
+            Some(node) if node.is_loading => node.is_loading = false,
+            Some(_) => {
+                if self.am.contains(&frame_id) {
+                    self.am.push_back(frame_id);
+                }
+            }
+        }
+    }
+
+    /// Places a newly loaded page, sending it to `am` if it was recently evicted from `a1_in`.
+    fn record_page(&mut self, frame_id: FrameId, page_id: PageId) {
+        self.untrack(frame_id);
+
+        if self.a1_out.remove(&page_id) {
+            self.am.push_back(frame_id);
+        } else {
+            self.a1_in.push_back(frame_id);
+        }
+
+        self.node_store
+            .insert(frame_id, TwoQueueNode::new(Some(page_id)));
+    }
+
+    /// Pins a frame, making it non-evictable.
+    fn pin(&mut self, frame_id: FrameId) {
+        if let Some(node) = self.node_store.get_mut(&frame_id) {
+            if node.is_evictable {
+                node.is_evictable = false;
+                self.evictable_size -= 1;
+            }
+        }
+    }
+
+    /// Unpins a frame, making it evictable.
+    fn unpin(&mut self, frame_id: FrameId) {
+        if let Some(node) = self.node_store.get_mut(&frame_id) {
+            if !node.is_evictable {
+                node.is_evictable = true;
+                self.evictable_size += 1;
+            }
+        }
+    }
+
+    /// Evicts the oldest frame of `a1_in` if it has outgrown `max_a1_in`, and the least recently
+    /// used frame of `am` otherwise. Falls back to the other queue if the preferred one has no
+    /// evictable frame.
+    fn evict(&mut self) -> Option<FrameId> {
+        let (first, second) = if self.a1_in.len() > self.max_a1_in {
+            (&self.a1_in, &self.am)
+        } else {
+            (&self.am, &self.a1_in)
+        };
+        let frame_id = self
+            .oldest_evictable(first)
+            .or_else(|| self.oldest_evictable(second))?;
+
+        let from_a1_in = self.a1_in.remove(&frame_id);
+        self.am.remove(&frame_id);
+        let node = self.node_store.remove(&frame_id).unwrap();
+        self.evictable_size -= 1;
+
+        if let (true, Some(page_id)) = (from_a1_in, node.page_id) {
+            self.a1_out.push_back(page_id);
+            if self.a1_out.len() > self.max_a1_out {
+                self.a1_out.pop_front();
+            }
+        }
+
+        Some(frame_id)
+    }
+
+    /// Removes a frame from the replacer if it is evictable.
+    fn remove(&mut self, frame_id: FrameId) {
+        if self
+            .node_store
+            .get(&frame_id)
+            .is_some_and(|node| node.is_evictable)
+        {
+            self.untrack(frame_id);
+        }
+    }
+
+    /// Returns the number of evictable frames.
+    fn evictable_count(&self) -> usize {
+        self.evictable_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_two_queue_replacer_fifo() {
+        let mut two_queue_replacer = TwoQueueReplacer::new(4);
+
+        two_queue_replacer.record_access(1, AccessType::Unknown);
+        two_queue_replacer.record_access(2, AccessType::Unknown);
+        two_queue_replacer.record_access(3, AccessType::Unknown);
+        two_queue_replacer.unpin(1);
+        two_queue_replacer.unpin(2);
+        two_queue_replacer.unpin(3);
+        assert_eq!(3, two_queue_replacer.evictable_count());
+
+        // Re-accessing a frame in a1_in does not promote it; a1_in is a plain FIFO.
+        two_queue_replacer.record_access(1, AccessType::Unknown);
+        assert_eq!(Some(1), two_queue_replacer.evict());
+
+        two_queue_replacer.pin(2);
+        assert_eq!(Some(3), two_queue_replacer.evict());
+        assert_eq!(None, two_queue_replacer.evict());
+
+        two_queue_replacer.unpin(2);
+        two_queue_replacer.remove(2);
+        assert_eq!(0, two_queue_replacer.evictable_count());
+        assert_eq!(None, two_queue_replacer.evict());
+    }
+
+    #[test]
+    fn test_two_queue_replacer_scan_resistance() {
+        let mut two_queue_replacer = TwoQueueReplacer::new(4);
+
+        // Page 10 is loaded, evicted, and loaded again, which marks it as hot.
+        two_queue_replacer.record_page(0, 10);
+        two_queue_replacer.record_access(0, AccessType::Unknown);
+        two_queue_replacer.unpin(0);
+        assert_eq!(Some(0), two_queue_replacer.evict());
+        two_queue_replacer.record_page(0, 10);
+        two_queue_replacer.record_access(0, AccessType::Unknown);
+        two_queue_replacer.unpin(0);
+        assert!(two_queue_replacer.am.contains(&0));
+
+        // A scan streams pages 20, 21 and 22 through the remaining frames.
+        for (frame_id, page_id) in [(1, 20), (2, 21), (3, 22)] {
+            two_queue_replacer.record_page(frame_id, page_id);
+            two_queue_replacer.record_access(frame_id, AccessType::Unknown);
+            two_queue_replacer.unpin(frame_id);
+        }
+
+        // The scanned pages are evicted before the hot page.
+        assert_eq!(Some(1), two_queue_replacer.evict());
+        assert_eq!(Some(2), two_queue_replacer.evict());
+        assert_eq!(Some(0), two_queue_replacer.evict());
+        assert_eq!(Some(3), two_queue_replacer.evict());
+
+        // Only pages evicted from a1_in are remembered, and at most `max_a1_out` of them.
+        assert_eq!(2, two_queue_replacer.a1_out.len());
+        assert!(two_queue_replacer.a1_out.contains(&21));
+        assert!(two_queue_replacer.a1_out.contains(&22));
+    }
+}
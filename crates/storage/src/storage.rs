@@ -1,105 +1,952 @@
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::{Arc, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 use crate::{
     buffer_pool::BufferPoolManager,
-    heap::{table_heap::TableHeap, table_tuple_iterator::TableTupleIterator},
+    directory::{TableDirectory, TableEntry},
+    heap::{
+        column_iterator::{ColumnBatchIterator, ColumnTupleIterator},
+        column_store::{self, ColumnStore},
+        lsm_iterator::LsmIterator,
+        lsm_tree::{LsmTree, FIRST_RECORD_ID},
+        pax_heap::PaxTableHeap,
+        pax_tuple_iterator::PaxTupleIterator,
+        sampler::Sampler,
+        scan_filter::ScanFilter,
+        scan_lock::lock_for_write,
+        table_heap::TableHeap,
+        table_tuple_iterator::TableTupleIterator,
+    },
+    page::{pax_page::PaxPageRef, table_page::TablePageRef, INVALID_PAGE_ID},
+    record_id::RecordId,
+    replacer::replacer::AccessType,
+    typedef::PageId,
     Result,
 };
 use rustdb_catalog::{
-    catalog::{self, StorageApi},
-    schema,
+    catalog::{self, SampleMethod, StorageApi, TableInfo, TableStats},
+    predicate::Predicate,
+    schema::{self, Schema},
     tuple::Tuple,
 };
 use rustdb_error::Error;
 
-/// A storage engine that implements StorageApi using a table heap.
+/// How a table lays its tuples out in its pages, chosen when the table is created.
+#[derive(Debug, Clone)]
+pub enum TableLayout {
+    /// Each tuple's bytes are stored together in a slotted page.
+    Row,
+    /// Each column's values are stored together within a page (PAX), so scans can read a subset
+    /// of the columns. The heap needs the table's schema to split tuples into columns.
+    Pax(Schema),
+    /// Tuples are written to a log-structured merge tree (see [`LsmTree`]) instead of a heap, so
+    /// that inserts, updates and deletes only ever append. Suits tables that are written to much
+    /// more than they are read.
+    Lsm,
+    /// Each column is stored in pages of its own, compressed (see [`ColumnStore`]), so scans only
+    /// read the columns they need and can emit them in batches (see
+    /// [`StorageEngine::scan_batches`]). Suits analytic tables that are mostly appended to. The
+    /// store needs the table's schema to split tuples into columns.
+    Columnar(Schema),
+}
+
+/// The heap backing a table, depending on its layout.
+#[derive(Clone)]
+enum TableStorage {
+    Row(Arc<RwLock<TableHeap>>),
+    Pax(Arc<RwLock<PaxTableHeap>>),
+    Lsm(Arc<RwLock<LsmTree>>),
+    Columnar(Arc<RwLock<ColumnStore>>),
+}
+
+impl TableStorage {
+    /// Whether both are the same heap, rather than heaps of the same layout.
+    fn is(&self, other: &TableStorage) -> bool {
+        match (self, other) {
+            (TableStorage::Row(a), TableStorage::Row(b)) => Arc::ptr_eq(a, b),
+            (TableStorage::Pax(a), TableStorage::Pax(b)) => Arc::ptr_eq(a, b),
+            (TableStorage::Lsm(a), TableStorage::Lsm(b)) => Arc::ptr_eq(a, b),
+            (TableStorage::Columnar(a), TableStorage::Columnar(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// How a scan interacts with writes to the table it is scanning.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScanConsistency {
+    /// Writers aren't blocked. The scan sees a write if it hasn't gone past the page that was
+    /// written to yet, so it may or may not return tuples inserted after it started. This is how
+    /// [`StorageApi::scan`] behaves.
+    #[default]
+    Concurrent,
+    /// Writers are blocked until the scan is exhausted or dropped, so it returns the tuples the
+    /// table held when it started, and only those. The thread running the scan must not write to
+    /// the table until then, or it will wait for itself.
+    Stable,
+}
+
+/// What [`StorageEngine::vacuum`] may do to reclaim space, besides compacting pages and freeing
+/// the ones left empty.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VacuumOptions {
+    /// Move the tuples of a page into the page before it when they all fit there, so that the
+    /// page can be freed. Only pages holding nothing but tuples that updates relocated are
+    /// merged, as their record ids belong to the slots forwarding to them.
+    pub merge_pages: bool,
+}
+
+/// What a vacuum reclaimed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VacuumReport {
+    /// Number of pages unlinked from the table's page chain and freed.
+    pub pages_reclaimed: usize,
+    /// Number of bytes compaction freed up within pages, including pages that were freed after.
+    pub bytes_reclaimed: usize,
+}
+
+impl VacuumReport {
+    fn add(&mut self, other: VacuumReport) {
+        self.pages_reclaimed += other.pages_reclaimed;
+        self.bytes_reclaimed += other.bytes_reclaimed;
+    }
+}
+
+/// Vacuums a table in a background thread at a fixed interval, until it is stopped or dropped.
+/// Created by [`StorageEngine::start_vacuum_worker`].
+pub struct VacuumWorker {
+    stop: mpsc::Sender<()>,
+    handle: Option<JoinHandle<Result<VacuumReport>>>,
+}
+
+impl VacuumWorker {
+    /// Stops the worker, waiting for the vacuum it is running, if any, to finish. Returns what
+    /// its vacuums reclaimed altogether, or the error that made it stop early.
+    pub fn stop(mut self) -> Result<VacuumReport> {
+        self.join()
+    }
+
+    fn join(&mut self) -> Result<VacuumReport> {
+        // This fails if the worker already stopped on an error, which `join` then returns.
+        let _ = self.stop.send(());
+        match self.handle.take() {
+            Some(handle) => handle.join().unwrap(),
+            None => Ok(VacuumReport::default()),
+        }
+    }
+}
+
+impl Drop for VacuumWorker {
+    fn drop(&mut self) {
+        let _ = self.join();
+    }
+}
+
+/// An iterator over the tuples of a table, whichever its layout.
+pub enum TableScanIterator {
+    Row(TableTupleIterator),
+    Pax(PaxTupleIterator),
+    Lsm(LsmIterator),
+    Columnar(ColumnTupleIterator),
+}
+
+impl Iterator for TableScanIterator {
+    type Item = Result<(schema::RecordId, Tuple)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            TableScanIterator::Row(iter) => iter.next(),
+            TableScanIterator::Pax(iter) => iter.next(),
+            TableScanIterator::Lsm(iter) => iter.next(),
+            TableScanIterator::Columnar(iter) => iter.next(),
+        }
+    }
+}
+
+impl TableScanIterator {
+    /// Returns where the scan currently is, so that a later scan can pick up from there with
+    /// [`StorageEngine::scan_from`]. Tuples already emitted aren't emitted again.
+    pub fn cursor(&self) -> ScanCursor {
+        let position = match self {
+            TableScanIterator::Row(iter) => iter.position(),
+            TableScanIterator::Pax(iter) => iter.position(),
+            TableScanIterator::Lsm(iter) => iter.position(),
+            TableScanIterator::Columnar(iter) => iter.position(),
+        };
+        ScanCursor {
+            position: position.into(),
+        }
+    }
+}
+
+/// Current version of the [`ScanCursor`] encoding.
+const SCAN_CURSOR_VERSION: u8 = 1;
+
+/// A position in a table scan. It converts to and from bytes, so that it can be handed out, e.g.
+/// as a pagination token, and used to resume the scan later, even after a restart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanCursor {
+    /// The record id of the next slot the scan looks at.
+    position: schema::RecordId,
+}
+
+impl ScanCursor {
+    /// The record id to pass to [`StorageEngine::scan_from`] to resume the scan.
+    pub fn record_id(&self) -> schema::RecordId {
+        self.position
+    }
+
+    /// Whether the scan had emitted all of its tuples.
+    pub fn is_exhausted(&self) -> bool {
+        RecordId::from(self.position).page_id() == INVALID_PAGE_ID
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![SCAN_CURSOR_VERSION];
+        bytes.extend_from_slice(&self.position.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        match bytes {
+            [SCAN_CURSOR_VERSION, position @ ..] if position.len() == 8 => Ok(Self {
+                position: u64::from_le_bytes(position.try_into().unwrap()),
+            }),
+            _ => Err(Error::InvalidInput("Invalid scan cursor".to_string())),
+        }
+    }
+}
+
+/// A storage engine that implements StorageApi using table heaps.
 /// It maintains a mapping from table IDs to table heaps (each wrapped in an RwLock).
 pub struct StorageEngine {
     bpm: Arc<RwLock<BufferPoolManager>>,
     // Each table heap is now wrapped in an RwLock for internal synchronization.
-    tables: RwLock<HashMap<catalog::TableId, Arc<RwLock<TableHeap>>>>,
+    tables: RwLock<HashMap<catalog::TableId, TableStorage>>,
+    // Persists which tables exist and where their heaps are, so they can be reattached.
+    directory: TableDirectory,
+    // Maps table id -> the metadata of the table, handed out by `create_table`.
+    infos: RwLock<HashMap<catalog::TableId, Arc<TableInfo>>>,
 }
 
 impl StorageEngine {
-    /// Creates a new StorageEngine given a BufferPoolManager.
+    /// Creates a new StorageEngine given a BufferPoolManager. The engine starts out with no
+    /// tables, and allocates a table directory whose page id [`StorageEngine::open`] needs.
     pub fn new(bpm: Arc<RwLock<BufferPoolManager>>) -> Self {
         Self {
             bpm: Arc::clone(&bpm),
             tables: RwLock::new(HashMap::new()),
+            directory: TableDirectory::new(bpm),
+            infos: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Reopens the storage engine whose table directory is at `directory_page_id`, reattaching
+    /// every table it created. What the engine wrote is only found if it was flushed or dropped
+    /// (see [`StorageEngine::flush`]).
+    pub fn open(bpm: Arc<RwLock<BufferPoolManager>>, directory_page_id: PageId) -> Result<Self> {
+        let directory = TableDirectory::open(directory_page_id, bpm.clone())?;
+        let mut tables = HashMap::new();
+        let mut infos = HashMap::new();
+        for (_, entry) in directory.entries()? {
+            infos.insert(entry.table_id, table_info(&entry));
+            let table = match entry.layout {
+                TableLayout::Row => TableStorage::Row(Arc::new(RwLock::new(TableHeap::open(
+                    entry.header_page_id,
+                    bpm.clone(),
+                )?))),
+                TableLayout::Pax(schema) => TableStorage::Pax(Arc::new(RwLock::new(
+                    PaxTableHeap::open(entry.header_page_id, schema, bpm.clone())?,
+                ))),
+                TableLayout::Lsm => TableStorage::Lsm(Arc::new(RwLock::new(LsmTree::open(
+                    entry.header_page_id,
+                    bpm.clone(),
+                )?))),
+                TableLayout::Columnar(schema) => TableStorage::Columnar(Arc::new(RwLock::new(
+                    ColumnStore::open(entry.header_page_id, schema, bpm.clone())?,
+                ))),
+            };
+            tables.insert(entry.table_id, table);
+        }
+        Ok(Self {
+            bpm,
+            tables: RwLock::new(tables),
+            directory,
+            infos: RwLock::new(infos),
+        })
+    }
+
+    /// Returns the page id to pass to [`StorageEngine::open`] to reopen this engine.
+    pub fn directory_page_id(&self) -> PageId {
+        self.directory.header_page_id()
+    }
+
+    /// Writes everything the engine holds in memory to disk: the memtables of LSM tables, then
+    /// every dirty page in the buffer pool. Once flushed, the engine can be reopened from
+    /// another buffer pool, as after a restart. Dropping the engine flushes it too.
+    pub fn flush(&self) -> Result<()> {
+        // Tables are locked after the table map is released, as when dropping them.
+        let tables: Vec<TableStorage> = self.tables.read().unwrap().values().cloned().collect();
+        for table in tables {
+            if let TableStorage::Lsm(tree) = table {
+                tree.write().unwrap().flush()?;
+            }
+        }
+        self.bpm.write()?.flush_all()
+    }
+
+    /// Starts recording every page the engine's buffer pool pins and unpins to a trace file at
+    /// `path`, replacing any trace being recorded. The `replay_trace` binary replays such traces
+    /// against other replacement policies and pool sizes.
+    pub fn start_trace(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.bpm.write()?.start_trace(path)
+    }
+
+    /// Stops recording the access trace started by [`StorageEngine::start_trace`] and writes out
+    /// what is left of it.
+    pub fn stop_trace(&self) -> Result<()> {
+        self.bpm.write()?.stop_trace()
+    }
+
+    /// Creates a new table whose pages use the given layout.
+    pub fn create_table_with_layout(
+        &self,
+        table_id: catalog::TableId,
+        name: &str,
+        layout: TableLayout,
+    ) -> Result<()> {
+        let mut tables = self.tables.write().unwrap();
+        if tables.contains_key(&table_id) {
+            return Err(Error::InvalidInput("Table already exists".to_string()));
+        }
+        let (table, header_page_id) = match &layout {
+            TableLayout::Row => {
+                let table_heap = TableHeap::new(name, self.bpm.clone());
+                let header_page_id = table_heap.header_page_id();
+                (
+                    TableStorage::Row(Arc::new(RwLock::new(table_heap))),
+                    header_page_id,
+                )
+            }
+            TableLayout::Pax(schema) => {
+                let table_heap = PaxTableHeap::new(name, schema.clone(), self.bpm.clone());
+                let header_page_id = table_heap.header_page_id();
+                (
+                    TableStorage::Pax(Arc::new(RwLock::new(table_heap))),
+                    header_page_id,
+                )
+            }
+            TableLayout::Lsm => {
+                let tree = LsmTree::new(self.bpm.clone());
+                let header_page_id = tree.header_page_id();
+                (
+                    TableStorage::Lsm(Arc::new(RwLock::new(tree))),
+                    header_page_id,
+                )
+            }
+            TableLayout::Columnar(schema) => {
+                let store = ColumnStore::new(schema.clone(), self.bpm.clone());
+                let header_page_id = store.header_page_id();
+                (
+                    TableStorage::Columnar(Arc::new(RwLock::new(store))),
+                    header_page_id,
+                )
+            }
+        };
+        let entry = TableEntry {
+            table_id,
+            name: name.to_string(),
+            layout,
+            header_page_id,
+        };
+        self.directory.insert(&entry)?;
+        self.infos.write().unwrap().insert(table_id, table_info(&entry));
+        tables.insert(table_id, table);
+        Ok(())
+    }
+
+    /// Returns the metadata of the table with id `table_id`. Tables whose layout doesn't know
+    /// their schema have one without columns.
+    pub fn table_info(&self, table_id: catalog::TableId) -> Result<Arc<TableInfo>> {
+        self.infos
+            .read()
+            .unwrap()
+            .get(&table_id)
+            .cloned()
+            .ok_or_else(|| Error::InvalidInput("Table not found".to_string()))
+    }
+
+    /// Returns an iterator over all tuples in the specified table, with the given consistency.
+    pub fn scan_with(
+        &self,
+        table_id: catalog::TableId,
+        consistency: ScanConsistency,
+    ) -> Result<TableScanIterator> {
+        Ok(match (self.table(table_id)?, consistency) {
+            (TableStorage::Row(table_heap), ScanConsistency::Concurrent) => {
+                TableScanIterator::Row(TableTupleIterator::new(self.bpm.clone(), table_heap))
+            }
+            (TableStorage::Row(table_heap), ScanConsistency::Stable) => {
+                TableScanIterator::Row(TableTupleIterator::stable(self.bpm.clone(), table_heap))
+            }
+            (TableStorage::Pax(table_heap), ScanConsistency::Concurrent) => {
+                TableScanIterator::Pax(PaxTupleIterator::new(self.bpm.clone(), table_heap))
+            }
+            (TableStorage::Pax(table_heap), ScanConsistency::Stable) => {
+                TableScanIterator::Pax(PaxTupleIterator::stable(self.bpm.clone(), table_heap))
+            }
+            // LSM scans read a snapshot, which is as stable as it gets.
+            (TableStorage::Lsm(tree), _) => TableScanIterator::Lsm(tree.read().unwrap().scan()?),
+            (TableStorage::Columnar(store), ScanConsistency::Concurrent) => {
+                let columns = (0..store.read().unwrap().schema().num_columns()).collect();
+                let batches = ColumnBatchIterator::new(store, columns)?;
+                TableScanIterator::Columnar(ColumnTupleIterator::new(batches))
+            }
+            (TableStorage::Columnar(store), ScanConsistency::Stable) => {
+                let batches = ColumnBatchIterator::stable(store);
+                TableScanIterator::Columnar(ColumnTupleIterator::new(batches))
+            }
+        })
+    }
+
+    /// Returns an iterator over the given columns of every tuple in the specified table, which
+    /// must use the PAX or the columnar layout. Emitted tuples only hold those columns, in the
+    /// given order.
+    pub fn scan_columns(
+        &self,
+        table_id: catalog::TableId,
+        columns: Vec<usize>,
+    ) -> Result<TableScanIterator> {
+        match self.table(table_id)? {
+            TableStorage::Pax(table_heap) => Ok(TableScanIterator::Pax(
+                PaxTupleIterator::with_columns(self.bpm.clone(), table_heap, columns)?,
+            )),
+            TableStorage::Columnar(store) => {
+                let batches = ColumnBatchIterator::new(store, columns)?;
+                Ok(TableScanIterator::Columnar(ColumnTupleIterator::new(batches)))
+            }
+            TableStorage::Row(_) | TableStorage::Lsm(_) => Err(Error::InvalidInput(
+                "Column scans require a PAX or columnar table".to_string(),
+            )),
         }
     }
+
+    /// Returns an iterator over the given columns of the specified table, which must use the
+    /// columnar layout, emitting their values in batches of consecutive rows instead of as
+    /// tuples. Rows that don't satisfy `predicate`, if there is one, are left out.
+    pub fn scan_batches(
+        &self,
+        table_id: catalog::TableId,
+        columns: Vec<usize>,
+        predicate: Option<Predicate>,
+    ) -> Result<ColumnBatchIterator> {
+        let TableStorage::Columnar(store) = self.table(table_id)? else {
+            return Err(Error::InvalidInput(
+                "Batch scans require a columnar table".to_string(),
+            ));
+        };
+        let iter = ColumnBatchIterator::new(store, columns)?;
+        match predicate {
+            Some(predicate) => iter.with_predicate(predicate),
+            None => Ok(iter),
+        }
+    }
+
+    /// Returns an iterator over the tuples of the specified table, starting with the one at
+    /// `rid` (if it wasn't deleted) and continuing in scan order. `rid` may also come from
+    /// [`ScanCursor::record_id`] to resume an earlier scan, and need not hold a tuple.
+    pub fn scan_from(
+        &self,
+        table_id: catalog::TableId,
+        rid: schema::RecordId,
+    ) -> Result<TableScanIterator> {
+        let rid = RecordId::from(rid);
+        let page_id = rid.page_id();
+        let table = self.table(table_id)?;
+        // Make sure the scan starts on a page of the right kind, if it isn't already over.
+        if page_id != INVALID_PAGE_ID {
+            let fetch =
+                || BufferPoolManager::fetch_page_handle(&self.bpm, page_id, AccessType::Scan);
+            match table {
+                TableStorage::Row(_) => drop(TablePageRef::try_from(fetch()?)?),
+                TableStorage::Pax(_) => drop(PaxPageRef::try_from(fetch()?)?),
+                // LSM and column store record ids don't point at pages.
+                TableStorage::Lsm(_) | TableStorage::Columnar(_) => {}
+            }
+        }
+        Ok(match table {
+            TableStorage::Row(_) => TableScanIterator::Row(
+                TableTupleIterator::with_range(self.bpm.clone(), page_id, INVALID_PAGE_ID)
+                    .starting_at_slot(rid.slot_id()),
+            ),
+            TableStorage::Pax(table_heap) => {
+                let columns = (0..table_heap.read().unwrap().schema().num_columns()).collect();
+                let iter = PaxTupleIterator::with_range(
+                    self.bpm.clone(),
+                    table_heap,
+                    columns,
+                    page_id,
+                    INVALID_PAGE_ID,
+                )?;
+                TableScanIterator::Pax(iter.starting_at_slot(rid.slot_id()))
+            }
+            TableStorage::Lsm(tree) => {
+                // LSM tables are scanned in record id order. Nothing comes after the end of a scan.
+                let start = u64::from(rid);
+                let stop = (page_id == INVALID_PAGE_ID).then_some(start);
+                TableScanIterator::Lsm(tree.read().unwrap().scan_range(start, stop)?)
+            }
+            TableStorage::Columnar(store) => {
+                let columns = (0..store.read().unwrap().schema().num_columns()).collect();
+                let start = column_store::row_of(rid.into());
+                let stop = (page_id == INVALID_PAGE_ID).then_some(start);
+                let batches = ColumnBatchIterator::with_range(store, columns, start, stop)?;
+                TableScanIterator::Columnar(ColumnTupleIterator::new(batches))
+            }
+        })
+    }
+
+    /// Returns `n` iterators that together emit every tuple of the specified table exactly once,
+    /// so that a scan can be split across threads.
+    ///
+    /// The table's page chain is split into `n` contiguous ranges of about the same number of
+    /// pages, and each iterator scans one of them. Pages appended to the table after this call
+    /// are scanned by the last iterator. Some iterators are empty if the table has fewer than `n`
+    /// pages.
+    pub fn scan_partitions(
+        &self,
+        table_id: catalog::TableId,
+        n: usize,
+    ) -> Result<Vec<TableScanIterator>> {
+        if n == 0 {
+            return Err(Error::InvalidInput(
+                "Cannot split a scan into 0 partitions".to_string(),
+            ));
+        }
+        match self.table(table_id)? {
+            TableStorage::Row(table_heap) => {
+                let page_ids = table_heap.read().unwrap().chain_page_ids()?;
+                Ok(partition_chain(&page_ids, n)
+                    .into_iter()
+                    .map(|(start, stop)| {
+                        let iter = TableTupleIterator::with_range(self.bpm.clone(), start, stop);
+                        TableScanIterator::Row(iter)
+                    })
+                    .collect())
+            }
+            TableStorage::Pax(table_heap) => {
+                let (page_ids, columns) = {
+                    let table_heap = table_heap.read().unwrap();
+                    let columns: Vec<usize> = (0..table_heap.schema().num_columns()).collect();
+                    (table_heap.chain_page_ids()?, columns)
+                };
+                partition_chain(&page_ids, n)
+                    .into_iter()
+                    .map(|(start, stop)| {
+                        let iter = PaxTupleIterator::with_range(
+                            self.bpm.clone(),
+                            table_heap.clone(),
+                            columns.clone(),
+                            start,
+                            stop,
+                        )?;
+                        Ok(TableScanIterator::Pax(iter))
+                    })
+                    .collect()
+            }
+            TableStorage::Lsm(tree) => {
+                // Split the record ids handed out so far instead, which are as dense as pages.
+                let tree = tree.read().unwrap();
+                let len = tree.next_record_id() - FIRST_RECORD_ID;
+                let bound = |i: u64| FIRST_RECORD_ID + i * len / n as u64;
+                (0..n as u64)
+                    .map(|i| {
+                        let stop = (i + 1 < n as u64).then(|| bound(i + 1));
+                        Ok(TableScanIterator::Lsm(tree.scan_range(bound(i), stop)?))
+                    })
+                    .collect()
+            }
+            TableStorage::Columnar(store) => {
+                // Rows are split instead, the last range taking the rows inserted from now on.
+                let (row_cnt, columns): (_, Vec<usize>) = {
+                    let store = store.read().unwrap();
+                    (store.row_count(), (0..store.schema().num_columns()).collect())
+                };
+                let bound = |i: u64| i * row_cnt / n as u64;
+                (0..n as u64)
+                    .map(|i| {
+                        let stop = (i + 1 < n as u64).then(|| bound(i + 1));
+                        let batches = ColumnBatchIterator::with_range(
+                            store.clone(),
+                            columns.clone(),
+                            bound(i),
+                            stop,
+                        )?;
+                        Ok(TableScanIterator::Columnar(ColumnTupleIterator::new(batches)))
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Reclaims the space of the specified table's deleted tuples (see [`TableHeap::vacuum`]).
+    /// Only tables with the row layout can be vacuumed.
+    ///
+    /// Like other writes, the vacuum waits for stable scans of the table to finish. Concurrent
+    /// scans fail if the page they are about to read is freed, and may miss or repeat the tuples
+    /// moved to merge pages.
+    pub fn vacuum(
+        &self,
+        table_id: catalog::TableId,
+        options: VacuumOptions,
+    ) -> Result<VacuumReport> {
+        lock_for_write(&*self.row_table(table_id)?).vacuum(options)
+    }
+
+    /// Starts a worker thread vacuuming the specified table with `options` every `interval`,
+    /// until the returned worker is stopped or dropped. The worker also stops as soon as a vacuum
+    /// fails, e.g. because the table was dropped.
+    pub fn start_vacuum_worker(
+        &self,
+        table_id: catalog::TableId,
+        options: VacuumOptions,
+        interval: Duration,
+    ) -> Result<VacuumWorker> {
+        let table_heap = self.row_table(table_id)?;
+        let (stop, stopped) = mpsc::channel();
+        let handle = thread::spawn(move || {
+            let mut report = VacuumReport::default();
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                report.add(lock_for_write(&table_heap).vacuum(options)?);
+            }
+            Ok(report)
+        });
+        Ok(VacuumWorker {
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// Looks up the heap backing the specified table, which must use the row layout.
+    fn row_table(&self, table_id: catalog::TableId) -> Result<Arc<RwLock<TableHeap>>> {
+        match self.table(table_id)? {
+            TableStorage::Row(table_heap) => Ok(table_heap),
+            TableStorage::Pax(_) | TableStorage::Lsm(_) | TableStorage::Columnar(_) => Err(
+                Error::InvalidInput("Vacuuming requires a row table".to_string()),
+            ),
+        }
+    }
+
+    /// Deletes the pages `page_ids` returns and forgets the table, unless another thread dropped
+    /// it first. The caller keeps the table's heap write-locked throughout, so that no writer
+    /// adds pages to it after they are listed.
+    fn remove_table(
+        &self,
+        table_id: catalog::TableId,
+        table: &TableStorage,
+        page_ids: impl FnOnce() -> Result<Vec<PageId>>,
+    ) -> Result<()> {
+        let mut tables = self.tables.write().unwrap();
+        if !tables.get(&table_id).is_some_and(|current| current.is(table)) {
+            return Err(Error::InvalidInput("Table not found".to_string()));
+        }
+        // Listing the pages reads them through the buffer pool, so it's done before locking it.
+        let page_ids = page_ids()?;
+        self.bpm.write().unwrap().delete_pages(&page_ids)?;
+        self.directory.remove(table_id)?;
+        self.infos.write().unwrap().remove(&table_id);
+        tables.remove(&table_id);
+        Ok(())
+    }
+
+    /// Looks up the heap backing the specified table.
+    fn table(&self, table_id: catalog::TableId) -> Result<TableStorage> {
+        let tables = self.tables.read().unwrap();
+        tables
+            .get(&table_id)
+            .cloned()
+            .ok_or_else(|| Error::InvalidInput("Table not found".to_string()))
+    }
+}
+
+impl Drop for StorageEngine {
+    fn drop(&mut self) {
+        // There's nobody to report an error to; what couldn't be written is lost.
+        let _ = self.flush();
+    }
+}
+
+/// Returns the metadata of the table `entry` describes. Only the layouts that store tuples by
+/// column know the table's schema.
+fn table_info(entry: &TableEntry) -> Arc<TableInfo> {
+    let schema = match &entry.layout {
+        TableLayout::Pax(schema) | TableLayout::Columnar(schema) => schema.clone(),
+        TableLayout::Row | TableLayout::Lsm => Schema::new(&[]),
+    };
+    Arc::new(TableInfo::new(entry.table_id, entry.name.clone(), schema))
+}
+
+/// Splits a page chain into `n` contiguous ranges of about the same length. Each range is given
+/// by its first page and the page it stops before, which is `INVALID_PAGE_ID` for the last one.
+/// Ranges with no pages start at `INVALID_PAGE_ID`.
+fn partition_chain(page_ids: &[PageId], n: usize) -> Vec<(PageId, PageId)> {
+    let page_at = |i: usize| page_ids.get(i).copied().unwrap_or(INVALID_PAGE_ID);
+    (0..n)
+        .map(|i| {
+            let start = i * page_ids.len() / n;
+            let stop = (i + 1) * page_ids.len() / n;
+            if start == stop {
+                (INVALID_PAGE_ID, INVALID_PAGE_ID)
+            } else {
+                (page_at(start), page_at(stop))
+            }
+        })
+        .collect()
 }
 
 impl StorageApi for StorageEngine {
     /// The iterator type for scanning tuples in a table.
-    type ScanIterator = TableTupleIterator;
+    type ScanIterator = TableScanIterator;
 
     /// Creates a new table.
     ///
     /// In a full system this would create a new table heap and a catalog entry.
     /// Here we simply create a new TableHeap, wrap it in an RwLock, and store it in our map.
-    fn create_table(&self, table_id: catalog::TableId, name: &str) -> Result<&catalog::TableInfo> {
-        let mut tables = self.tables.write().unwrap();
-        if tables.contains_key(&table_id) {
-            return Err(Error::InvalidInput("Table already exists".to_string()));
-        }
-        let table_heap = TableHeap::new(name, self.bpm.clone());
-        // Wrap the TableHeap in an RwLock.
-        tables.insert(table_id, Arc::new(RwLock::new(table_heap)));
-        todo!("Return a reference to the newly created TableInfo")
+    /// The engine isn't told the table's schema, so the returned metadata has one without
+    /// columns; see [`StorageEngine::create_table_with_layout`].
+    fn create_table(&self, table_id: catalog::TableId, name: &str) -> Result<Arc<TableInfo>> {
+        self.create_table_with_layout(table_id, name, TableLayout::Row)?;
+        self.table_info(table_id)
     }
 
     /// Retrieves a tuple given its record id.
     fn get_tuple(&self, table_id: catalog::TableId, rid: schema::RecordId) -> Result<Tuple> {
-        let tables = self.tables.read().unwrap();
-        let table_heap_lock = tables
-            .get(&table_id)
-            .ok_or_else(|| Error::InvalidInput("Table not found".to_string()))?;
-        // Acquire a read lock on the table heap.
-        let table_heap = table_heap_lock.read().unwrap();
-        // TableHeap::get_tuple returns a (TupleMetadata, Tuple) pair.
-        let (_meta, tuple) = table_heap.get_tuple(&rid.into())?;
-        Ok(tuple)
+        match self.table(table_id)? {
+            TableStorage::Row(table_heap) => {
+                // Acquire a read lock on the table heap.
+                let table_heap = table_heap.read().unwrap();
+                // TableHeap::get_tuple returns a (TupleMetadata, Tuple) pair.
+                let (_meta, tuple) = table_heap.get_tuple(&rid.into())?;
+                Ok(tuple)
+            }
+            TableStorage::Pax(table_heap) => table_heap.read().unwrap().get_tuple(&rid.into()),
+            TableStorage::Lsm(tree) => tree.read().unwrap().get_tuple(rid),
+            TableStorage::Columnar(store) => store.read().unwrap().get_tuple(rid),
+        }
     }
 
     /// Deletes a tuple given its record id.
     fn delete_tuple(&self, table_id: catalog::TableId, rid: schema::RecordId) -> Result<()> {
-        let tables = self.tables.read().unwrap();
-        let table_heap_lock = tables
-            .get(&table_id)
-            .ok_or_else(|| Error::InvalidInput("Table not found".to_string()))?;
         // Acquire a write lock to modify the table heap.
-        let table_heap = table_heap_lock.write().unwrap();
-        table_heap.delete_tuple(&rid.into())?;
+        match self.table(table_id)? {
+            TableStorage::Row(table_heap) => {
+                lock_for_write(&table_heap).delete_tuple(&rid.into())?;
+            }
+            TableStorage::Pax(table_heap) => {
+                lock_for_write(&table_heap).delete_tuple(&rid.into())?;
+            }
+            TableStorage::Lsm(tree) => lock_for_write(&tree).delete_tuple(rid)?,
+            TableStorage::Columnar(store) => lock_for_write(&store).delete_tuple(rid)?,
+        }
         Ok(())
     }
 
     /// Inserts a tuple into the specified table.
     fn insert_tuple(&self, table_id: catalog::TableId, tuple: &Tuple) -> Result<schema::RecordId> {
-        let mut tables = self.tables.write().unwrap();
-        let table_heap_lock = tables
-            .get_mut(&table_id)
-            .ok_or_else(|| Error::InvalidInput("Table not found".to_string()))?;
         // Acquire a write lock for insertion.
-        let mut table_heap = table_heap_lock.write().unwrap();
-        let rid = table_heap.insert_tuple(tuple)?;
+        let rid = match self.table(table_id)? {
+            TableStorage::Row(table_heap) => lock_for_write(&table_heap).insert_tuple(tuple)?,
+            TableStorage::Pax(table_heap) => lock_for_write(&table_heap).insert_tuple(tuple)?,
+            TableStorage::Lsm(tree) => return lock_for_write(&tree).insert_tuple(tuple),
+            TableStorage::Columnar(store) => return lock_for_write(&store).insert_tuple(tuple),
+        };
         Ok(rid.into())
     }
 
+    /// Inserts many tuples into the specified table, looking it up and locking it only once.
+    fn insert_tuples(
+        &self,
+        table_id: catalog::TableId,
+        tuples: &[Tuple],
+    ) -> Result<Vec<schema::RecordId>> {
+        let rids = match self.table(table_id)? {
+            TableStorage::Row(table_heap) => lock_for_write(&table_heap).insert_tuples(tuples)?,
+            TableStorage::Pax(table_heap) => {
+                let mut table_heap = lock_for_write(&table_heap);
+                tuples
+                    .iter()
+                    .map(|tuple| table_heap.insert_tuple(tuple))
+                    .collect::<Result<Vec<_>>>()?
+            }
+            TableStorage::Lsm(tree) => {
+                let mut tree = lock_for_write(&tree);
+                return tuples
+                    .iter()
+                    .map(|tuple| tree.insert_tuple(tuple))
+                    .collect();
+            }
+            TableStorage::Columnar(store) => return lock_for_write(&store).insert_tuples(tuples),
+        };
+        Ok(rids.into_iter().map(u64::from).collect())
+    }
+
+    /// Drops the specified table. Every page of its heap is deleted from the buffer pool and
+    /// deallocated on disk, and the table is removed from the directory.
+    ///
+    /// Operations already running on the table, stable scans included, are waited for, so a
+    /// thread must not drop a table while it is running a stable scan of it itself. Other tables
+    /// stay usable in the meantime. If any of the table's pages is still pinned, e.g. by an
+    /// iterator that is in the middle of reading it, the drop is rejected with
+    /// `Error::PagePinned` and the table is left as it was.
+    fn drop_table(&self, table_id: catalog::TableId) -> Result<()> {
+        // Scans are waited for without holding the table map, as they may need it to finish.
+        let table = self.table(table_id)?;
+        match &table {
+            TableStorage::Row(table_heap) => {
+                let table_heap = lock_for_write(table_heap);
+                self.remove_table(table_id, &table, || table_heap.page_ids())
+            }
+            TableStorage::Pax(table_heap) => {
+                let table_heap = lock_for_write(table_heap);
+                self.remove_table(table_id, &table, || table_heap.page_ids())
+            }
+            TableStorage::Lsm(tree) => {
+                let mut tree = lock_for_write(tree);
+                self.remove_table(table_id, &table, || Ok(tree.page_ids()))?;
+                tree.discard();
+                Ok(())
+            }
+            TableStorage::Columnar(store) => {
+                let store = lock_for_write(store);
+                self.remove_table(table_id, &table, || Ok(store.page_ids()))
+            }
+        }
+    }
+
+    /// Removes every tuple from the specified table. Its pages are reclaimed as when dropping it,
+    /// except for the header page the directory refers to, and pinned pages are rejected the same
+    /// way.
+    fn truncate_table(&self, table_id: catalog::TableId) -> Result<()> {
+        match self.table(table_id)? {
+            TableStorage::Row(table_heap) => lock_for_write(&table_heap).truncate(),
+            TableStorage::Pax(table_heap) => lock_for_write(&table_heap).truncate(),
+            TableStorage::Lsm(tree) => lock_for_write(&tree).truncate(),
+            TableStorage::Columnar(store) => lock_for_write(&store).truncate(),
+        }
+    }
+
+    /// Updates a tuple in the specified table, keeping its record id.
+    fn update_tuple(
+        &self,
+        table_id: catalog::TableId,
+        rid: schema::RecordId,
+        tuple: &Tuple,
+    ) -> Result<()> {
+        // Acquire a write lock, as the update may need to allocate a page.
+        match self.table(table_id)? {
+            TableStorage::Row(table_heap) => {
+                lock_for_write(&table_heap).update_tuple(&rid.into(), tuple)
+            }
+            TableStorage::Pax(table_heap) => {
+                lock_for_write(&table_heap).update_tuple(&rid.into(), tuple)
+            }
+            TableStorage::Lsm(tree) => lock_for_write(&tree).update_tuple(rid, tuple),
+            TableStorage::Columnar(store) => lock_for_write(&store).update_tuple(rid, tuple),
+        }
+    }
+
     /// Returns an iterator over all tuples in the specified table.
     fn scan(&self, table_id: catalog::TableId) -> Result<Self::ScanIterator>
     where
         Self: Sized,
     {
-        let tables = self.tables.read().unwrap();
-        let table_heap_lock = tables
-            .get(&table_id)
-            .ok_or_else(|| Error::InvalidInput("Table not found".to_string()))?;
-        Ok(TableTupleIterator::new(
-            self.bpm.clone(),
-            table_heap_lock.clone(),
-        ))
+        self.scan_with(table_id, ScanConsistency::Concurrent)
+    }
+
+    /// Returns an iterator over the tuples of the specified table satisfying `predicate`, holding
+    /// only `columns`. Row tables check each tuple while it is still in its page, decoding only
+    /// the fields the predicate needs, and copy out just the requested fields of the tuples that
+    /// match. PAX and columnar tables read the predicate's and projection's columns from their
+    /// minipages or column pages, and check tuples against the schema they were created with
+    /// rather than `schema`.
+    fn scan_filtered(
+        &self,
+        table_id: catalog::TableId,
+        schema: &Schema,
+        columns: &[usize],
+        predicate: Option<Predicate>,
+    ) -> Result<Box<dyn catalog::ScanIterator>> {
+        let iter = match self.table(table_id)? {
+            TableStorage::Row(table_heap) => {
+                let filter = ScanFilter::new(schema.clone(), columns.to_vec(), predicate)?;
+                let iter = TableTupleIterator::new(self.bpm.clone(), table_heap);
+                TableScanIterator::Row(iter.with_filter(filter))
+            }
+            TableStorage::Pax(table_heap) => {
+                let mut iter =
+                    PaxTupleIterator::with_columns(self.bpm.clone(), table_heap, columns.to_vec())?;
+                if let Some(predicate) = predicate {
+                    iter = iter.with_predicate(predicate)?;
+                }
+                TableScanIterator::Pax(iter)
+            }
+            TableStorage::Lsm(tree) => {
+                let filter = ScanFilter::new(schema.clone(), columns.to_vec(), predicate)?;
+                TableScanIterator::Lsm(tree.read().unwrap().scan()?.with_filter(filter))
+            }
+            TableStorage::Columnar(store) => {
+                let mut batches = ColumnBatchIterator::new(store, columns.to_vec())?;
+                if let Some(predicate) = predicate {
+                    batches = batches.with_predicate(predicate)?;
+                }
+                TableScanIterator::Columnar(ColumnTupleIterator::new(batches))
+            }
+        };
+        Ok(Box::new(iter))
+    }
+
+    /// Returns an iterator over a random sample of the tuples of the specified table. Pages left
+    /// out of a [`SampleMethod::System`] sample aren't read past their header, and tuples left out
+    /// of either kind of sample aren't copied out of their page.
+    fn sample(
+        &self,
+        table_id: catalog::TableId,
+        method: SampleMethod,
+        seed: u64,
+    ) -> Result<Box<dyn catalog::ScanIterator>> {
+        let sampler = Sampler::new(method, seed)?;
+        let iter = match self.table(table_id)? {
+            TableStorage::Row(table_heap) => TableScanIterator::Row(
+                TableTupleIterator::new(self.bpm.clone(), table_heap).with_sampler(sampler),
+            ),
+            TableStorage::Pax(table_heap) => TableScanIterator::Pax(
+                PaxTupleIterator::new(self.bpm.clone(), table_heap).with_sampler(sampler),
+            ),
+            TableStorage::Lsm(tree) => {
+                TableScanIterator::Lsm(tree.read().unwrap().scan()?.with_sampler(sampler))
+            }
+            TableStorage::Columnar(store) => {
+                let columns = (0..store.read().unwrap().schema().num_columns()).collect();
+                let batches = ColumnBatchIterator::new(store, columns)?.with_sampler(sampler);
+                TableScanIterator::Columnar(ColumnTupleIterator::new(batches))
+            }
+        };
+        Ok(Box::new(iter))
+    }
+
+    /// Returns the statistics of the specified table, which its heap keeps up to date as it is
+    /// written to. Reading them doesn't touch any page.
+    fn table_stats(&self, table_id: catalog::TableId) -> Result<TableStats> {
+        Ok(match self.table(table_id)? {
+            TableStorage::Row(table_heap) => table_heap.read().unwrap().stats(),
+            TableStorage::Pax(table_heap) => table_heap.read().unwrap().stats(),
+            TableStorage::Lsm(tree) => tree.read().unwrap().stats(),
+            TableStorage::Columnar(store) => store.read().unwrap().stats(),
+        })
     }
 
     /// Returns a dynamic iterator over all tuples in the specified table.
@@ -107,3 +954,810 @@ impl StorageApi for StorageEngine {
         Ok(Box::new(self.scan(table_id)?))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use rustdb_catalog::column::Column;
+    use rustdb_catalog::field::Field;
+    use rustdb_catalog::predicate::CompareOp;
+    use rustdb_catalog::serde::Serde;
+    use rustdb_catalog::types::Type;
+    use serial_test::serial;
+
+    use super::*;
+    use crate::test_utils::{get_bpm_arc, get_disk_manager};
+    use crate::trace::access_trace::{read_trace, TraceEvent};
+    use crate::trace::replay::{replay_policy, Policy};
+
+    fn page_ids(engine: &StorageEngine, table_id: catalog::TableId) -> Result<Vec<PageId>> {
+        match engine.table(table_id)? {
+            TableStorage::Row(table_heap) => table_heap.read().unwrap().page_ids(),
+            TableStorage::Pax(table_heap) => table_heap.read().unwrap().page_ids(),
+            TableStorage::Lsm(tree) => Ok(tree.read().unwrap().page_ids()),
+            TableStorage::Columnar(store) => Ok(store.read().unwrap().page_ids()),
+        }
+    }
+
+    fn scan_all(engine: &StorageEngine, table_id: catalog::TableId) -> Result<Vec<Vec<u8>>> {
+        engine
+            .scan(table_id)?
+            .map(|item| Ok(item?.1.data().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    #[serial]
+    fn test_storage_engine_reopen() -> Result<()> {
+        let disk_manager = get_disk_manager();
+        let schema = Schema::new(&[Column::new("a".to_string(), Type::Integer)]);
+
+        let (directory_page_id, rows, pax_rows) = {
+            let engine = StorageEngine::new(get_bpm_arc(disk_manager.clone()));
+            let info = engine.create_table(0, "rows")?;
+            assert!(Arc::ptr_eq(&info, &engine.table_info(0)?));
+            engine.create_table_with_layout(1, "columns", TableLayout::Pax(schema))?;
+            assert!(engine
+                .create_table_with_layout(1, "again", TableLayout::Row)
+                .is_err());
+            assert!(engine.create_table(1, "again").is_err());
+
+            let tuples: Vec<Tuple> = (0..300_i32)
+                .map(|i| Tuple::new(i.to_le_bytes().repeat(20).into()))
+                .collect();
+            engine.insert_tuples(0, &tuples)?;
+            for i in 0..300_i32 {
+                engine.insert_tuple(1, &Tuple::new(i.to_le_bytes().to_vec().into()))?;
+            }
+            (
+                engine.directory_page_id(),
+                scan_all(&engine, 0)?,
+                scan_all(&engine, 1)?,
+            )
+        };
+        assert_eq!(300, rows.len());
+        assert_eq!(300, pax_rows.len());
+
+        // A new buffer pool over the same disk has nothing cached, like after a restart.
+        let engine = StorageEngine::open(get_bpm_arc(disk_manager.clone()), directory_page_id)?;
+        assert_eq!(rows, scan_all(&engine, 0)?);
+        assert_eq!(pax_rows, scan_all(&engine, 1)?);
+        assert!(engine.scan(2).is_err());
+        assert!(engine.table_info(1).is_ok());
+        assert!(engine.table_info(2).is_err());
+
+        // Reattached tables keep growing where they left off.
+        let rid = engine.insert_tuple(0, &Tuple::new(vec![1; 80].into()))?;
+        assert_eq!(vec![1; 80], engine.get_tuple(0, rid)?.data().to_vec());
+        assert_eq!(301, scan_all(&engine, 0)?.len());
+
+        // A flushed engine can be reopened while it's still open, e.g. by a reader.
+        engine.create_table_with_layout(2, "small", TableLayout::Row)?;
+        engine.insert_tuple(2, &Tuple::new(vec![2; 8].into()))?;
+        engine.flush()?;
+        let reader = StorageEngine::open(get_bpm_arc(disk_manager), directory_page_id)?;
+        assert_eq!(scan_all(&engine, 0)?, scan_all(&reader, 0)?);
+        assert_eq!(vec![vec![2; 8]], scan_all(&reader, 2)?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_storage_engine_drop_and_truncate() -> Result<()> {
+        let disk_manager = get_disk_manager();
+        let engine = StorageEngine::new(get_bpm_arc(disk_manager.clone()));
+        let schema = Schema::new(&[Column::new("a".to_string(), Type::Integer)]);
+        engine.create_table_with_layout(0, "rows", TableLayout::Row)?;
+        engine.create_table_with_layout(1, "columns", TableLayout::Pax(schema))?;
+
+        // Include tuples spilled to overflow pages, which have to be reclaimed too.
+        let tuples: Vec<Tuple> = (0..50_u8)
+            .map(|i| Tuple::new(vec![i; if i % 10 == 0 { 10_000 } else { 500 }].into()))
+            .collect();
+        engine.insert_tuples(0, &tuples)?;
+        for i in 0..2000_i32 {
+            engine.insert_tuple(1, &Tuple::new(i.to_le_bytes().to_vec().into()))?;
+        }
+        let is_allocated = |page_id: &PageId| disk_manager.lock().unwrap().contains_page(*page_id);
+
+        let row_pages = page_ids(&engine, 0)?;
+        assert!(row_pages.len() > 20);
+        engine.truncate_table(0)?;
+        assert!(!row_pages[1..].iter().any(is_allocated));
+        assert!(scan_all(&engine, 0)?.is_empty());
+        engine.insert_tuples(0, &tuples)?;
+        assert_eq!(50, scan_all(&engine, 0)?.len());
+
+        let row_pages = page_ids(&engine, 0)?;
+        engine.drop_table(0)?;
+        assert!(!row_pages.iter().any(is_allocated));
+        assert!(engine.scan(0).is_err());
+        assert!(engine.table_info(0).is_err());
+        assert!(engine.drop_table(0).is_err());
+        assert!(engine.truncate_table(0).is_err());
+
+        // A pinned page keeps the table from being dropped or truncated.
+        let pax_pages = page_ids(&engine, 1)?;
+        let handle =
+            BufferPoolManager::fetch_page_handle(&engine.bpm, pax_pages[1], AccessType::Scan)?;
+        assert_eq!(Err(Error::PagePinned(pax_pages[1])), engine.drop_table(1));
+        assert_eq!(
+            Err(Error::PagePinned(pax_pages[1])),
+            engine.truncate_table(1)
+        );
+        drop(handle);
+        assert!(pax_pages.iter().all(is_allocated));
+        assert_eq!(2000, scan_all(&engine, 1)?.len());
+
+        engine.truncate_table(1)?;
+        assert!(scan_all(&engine, 1)?.is_empty());
+        assert!(!pax_pages[1..].iter().any(is_allocated));
+
+        // The directory no longer lists the dropped table, but still has the truncated one.
+        let engine = StorageEngine::open(engine.bpm.clone(), engine.directory_page_id())?;
+        assert!(engine.scan(0).is_err());
+        assert!(scan_all(&engine, 1)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_storage_engine_scan_partitions() -> Result<()> {
+        let disk_manager = get_disk_manager();
+        let engine = StorageEngine::new(get_bpm_arc(disk_manager));
+        let schema = Schema::new(&[Column::new("a".to_string(), Type::Integer)]);
+        engine.create_table_with_layout(0, "rows", TableLayout::Row)?;
+        engine.create_table_with_layout(1, "columns", TableLayout::Pax(schema))?;
+
+        let tuples: Vec<Tuple> = (0..400_i32)
+            .map(|i| Tuple::new(i.to_le_bytes().repeat(25).into()))
+            .collect();
+        let rids = engine.insert_tuples(0, &tuples)?;
+        for i in 0..3000_i32 {
+            engine.insert_tuple(1, &Tuple::new(i.to_le_bytes().to_vec().into()))?;
+        }
+        // Deleted tuples aren't emitted, and moved tuples are only emitted once.
+        engine.delete_tuple(0, rids[3])?;
+        for &rid in &rids[100..110] {
+            engine.update_tuple(0, rid, &Tuple::new(vec![7; 1000].into()))?;
+        }
+
+        for table_id in [0, 1] {
+            let mut expected: Vec<_> = engine.scan(table_id)?.collect::<Result<_>>()?;
+            expected.sort_by_key(|(rid, _)| *rid);
+            for n in [1, 3, 4, 100] {
+                let partitions = engine.scan_partitions(table_id, n)?;
+                assert_eq!(n, partitions.len());
+                let mut scanned: Vec<_> = std::thread::scope(|s| {
+                    let handles: Vec<_> = partitions
+                        .into_iter()
+                        .map(|partition| s.spawn(move || partition.collect::<Result<Vec<_>>>()))
+                        .collect();
+                    handles
+                        .into_iter()
+                        .map(|handle| handle.join().unwrap())
+                        .collect::<Result<Vec<_>>>()
+                })?
+                .into_iter()
+                .flatten()
+                .collect();
+                scanned.sort_by_key(|(rid, _)| *rid);
+                assert_eq!(expected.len(), scanned.len());
+                assert!(expected
+                    .iter()
+                    .zip(&scanned)
+                    .all(|(a, b)| a.0 == b.0 && a.1.data() == b.1.data()));
+            }
+        }
+        assert!(engine.scan_partitions(0, 0).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_storage_engine_scan_filtered() -> Result<()> {
+        let disk_manager = get_disk_manager();
+        let engine = StorageEngine::new(get_bpm_arc(disk_manager));
+        let schema = Schema::new(&[
+            Column::new("id".to_string(), Type::Integer),
+            Column::new("name".to_string(), Type::Varchar),
+            Column::new("even".to_string(), Type::Boolean),
+        ]);
+        engine.create_table_with_layout(0, "rows", TableLayout::Row)?;
+        engine.create_table_with_layout(1, "columns", TableLayout::Pax(schema.clone()))?;
+
+        let row = |i: i32, name: String| {
+            Tuple::new(
+                Serde::serialize(&[i.into(), name.as_str().into(), (i % 2 == 0).into()]).into(),
+            )
+        };
+        let mut rids = Vec::new();
+        for i in 0..500 {
+            rids.push(engine.insert_tuple(0, &row(i, format!("name {}", i)))?);
+            engine.insert_tuple(1, &row(i, format!("name {}", i)))?;
+        }
+        // Tuples that moved to another page or spilled to overflow pages are filtered too.
+        engine.update_tuple(0, rids[10], &row(10, "x".repeat(300)))?;
+        engine.update_tuple(0, rids[11], &row(11, "y".repeat(10_000)))?;
+
+        let predicate = Predicate::compare(0, CompareOp::Lt, 20)
+            .and(Predicate::compare(2, CompareOp::Eq, false))
+            .or(Predicate::compare(1, CompareOp::Eq, "name 300"));
+        let projected_schema =
+            Schema::new(&[schema.column_at(2)?.clone(), schema.column_at(0)?.clone()]);
+        for table_id in [0, 1] {
+            let fields: Vec<Vec<Field>> = engine
+                .scan_filtered(table_id, &schema, &[2, 0], Some(predicate.clone()))?
+                .map(|item| Ok(Serde::deserialize(&item?.1.data(), &projected_schema)))
+                .collect::<Result<_>>()?;
+            let mut expected: Vec<Vec<Field>> = (1..20)
+                .step_by(2)
+                .map(|i| vec![Field::Boolean(false), Field::Integer(i)])
+                .collect();
+            expected.push(vec![Field::Boolean(true), Field::Integer(300)]);
+            assert_eq!(expected, fields);
+
+            // Without a predicate, only the projection applies.
+            assert_eq!(
+                500,
+                engine.scan_filtered(table_id, &schema, &[1], None)?.count()
+            );
+            assert!(engine.scan_filtered(table_id, &schema, &[3], None).is_err());
+            let bad_predicate = Predicate::compare(0, CompareOp::Eq, "x");
+            assert!(engine
+                .scan_filtered(table_id, &schema, &[0], Some(bad_predicate))
+                .is_err());
+        }
+
+        // The overflowed tuple's varchar is read back in full.
+        let predicate = Predicate::compare(0, CompareOp::Eq, 11);
+        let (_, tuple) = engine
+            .scan_filtered(0, &schema, &[1], Some(predicate))?
+            .next()
+            .unwrap()?;
+        let name_schema = Schema::new(&[schema.column_at(1)?.clone()]);
+        assert_eq!(
+            vec![Field::Varchar("y".repeat(10_000))],
+            Serde::deserialize(&tuple.data(), &name_schema)
+        );
+
+        // A row tuple that doesn't match the schema is an error, not a panic.
+        let mut bad_row = Serde::serialize(&[5.into(), "bad".into(), true.into()]);
+        bad_row[4..12].copy_from_slice(&usize::MAX.to_le_bytes());
+        engine.update_tuple(0, rids[5], &Tuple::new(bad_row.into()))?;
+        let predicate = Predicate::compare(1, CompareOp::Eq, "name 6");
+        let results: Vec<_> = engine
+            .scan_filtered(0, &schema, &[0], Some(predicate))?
+            .collect();
+        assert!(results.iter().any(|item| item.is_err()));
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_storage_engine_scan_from() -> Result<()> {
+        let disk_manager = get_disk_manager();
+        let engine = StorageEngine::new(get_bpm_arc(disk_manager));
+        let schema = Schema::new(&[Column::new("a".to_string(), Type::Integer)]);
+        engine.create_table_with_layout(0, "rows", TableLayout::Row)?;
+        engine.create_table_with_layout(1, "columns", TableLayout::Pax(schema))?;
+        for i in 0..1500_i32 {
+            engine.insert_tuple(0, &Tuple::new(i.to_le_bytes().repeat(10).into()))?;
+            engine.insert_tuple(1, &Tuple::new(i.to_le_bytes().to_vec().into()))?;
+        }
+
+        for table_id in [0, 1] {
+            let expected: Vec<_> = engine.scan(table_id)?.collect::<Result<_>>()?;
+            engine.delete_tuple(table_id, expected[700].0)?;
+
+            // Page through the table 100 tuples at a time, passing the cursor around as bytes.
+            let mut scan = engine.scan(table_id)?;
+            let mut paged = Vec::new();
+            loop {
+                paged.extend(scan.by_ref().take(100).collect::<Result<Vec<_>>>()?);
+                let token = scan.cursor().to_bytes();
+                let cursor = ScanCursor::from_bytes(&token)?;
+                if cursor.is_exhausted() {
+                    break;
+                }
+                scan = engine.scan_from(table_id, cursor.record_id())?;
+            }
+            assert_eq!(expected.len() - 1, paged.len());
+            let expected = expected.iter().filter(|(rid, _)| *rid != expected[700].0);
+            assert!(expected
+                .zip(&paged)
+                .all(|(a, b)| a.0 == b.0 && a.1.data() == b.1.data()));
+
+            // Starting from a record id includes its tuple.
+            let (rid, tuple) = engine.scan(table_id)?.nth(500).unwrap()?;
+            let (first_rid, first_tuple) = engine.scan_from(table_id, rid)?.next().unwrap()?;
+            assert_eq!(rid, first_rid);
+            assert_eq!(tuple.data(), first_tuple.data());
+        }
+
+        // Record ids have to point into a page of the table's kind.
+        let pax_rid = engine.scan(1)?.next().unwrap()?.0;
+        assert!(engine.scan_from(0, pax_rid).is_err());
+        assert!(ScanCursor::from_bytes(&[1, 2, 3]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_storage_engine_stable_scan() -> Result<()> {
+        let disk_manager = get_disk_manager();
+        let engine = StorageEngine::new(get_bpm_arc(disk_manager));
+        let schema = Schema::new(&[Column::new("a".to_string(), Type::Integer)]);
+        engine.create_table_with_layout(0, "rows", TableLayout::Row)?;
+        engine.create_table_with_layout(1, "columns", TableLayout::Pax(schema))?;
+        let tuple = |i: i32| Tuple::new(i.to_le_bytes().to_vec().into());
+        for i in 0..1000 {
+            engine.insert_tuple(0, &tuple(i))?;
+            engine.insert_tuple(1, &tuple(i))?;
+        }
+
+        for table_id in [0, 1] {
+            let first_rid = engine.scan(table_id)?.next().unwrap()?.0;
+            let mut scan = engine.scan_with(table_id, ScanConsistency::Stable)?;
+            let inserted = AtomicBool::new(false);
+            let scanned = std::thread::scope(|s| {
+                // The writer has to wait for the scan, even for pages it already went past.
+                let writer = s.spawn(|| {
+                    engine.delete_tuple(table_id, first_rid)?;
+                    engine.insert_tuple(table_id, &tuple(-1))?;
+                    inserted.store(true, Ordering::SeqCst);
+                    Result::Ok(())
+                });
+                let first = scan.next();
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                let rest: Vec<_> = scan.by_ref().collect::<Result<_>>()?;
+                assert!(!inserted.load(Ordering::SeqCst));
+                // Writers are let in once the scan is exhausted, without dropping it.
+                writer.join().unwrap()?;
+                Result::Ok(first.into_iter().count() + rest.len())
+            })?;
+            assert_eq!(1000, scanned);
+            assert!(inserted.load(Ordering::SeqCst));
+            drop(scan);
+
+            // Both changes are visible to the next scan.
+            let values = scan_all(&engine, table_id)?;
+            assert_eq!(1000, values.len());
+            assert!(!values.contains(&0_i32.to_le_bytes().to_vec()));
+            assert!(values.contains(&(-1_i32).to_le_bytes().to_vec()));
+        }
+
+        // Dropping a table waits for its stable scans, which can use the rest of the engine
+        // meanwhile.
+        let mut scan = engine.scan_with(0, ScanConsistency::Stable)?;
+        let dropped = AtomicBool::new(false);
+        std::thread::scope(|s| {
+            let dropper = s.spawn(|| {
+                engine.drop_table(0)?;
+                dropped.store(true, Ordering::SeqCst);
+                Result::Ok(())
+            });
+            scan.next().unwrap()?;
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            engine.insert_tuple(1, &tuple(-2))?;
+            assert_eq!(1001, engine.scan(1)?.count());
+            assert!(!dropped.load(Ordering::SeqCst));
+            assert_eq!(999, scan.by_ref().count());
+            dropper.join().unwrap()
+        })?;
+        assert!(engine.scan(0).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_storage_engine_trace() -> Result<()> {
+        let engine = StorageEngine::new(get_bpm_arc(get_disk_manager()));
+        engine.create_table_with_layout(0, "rows", TableLayout::Row)?;
+        let trace_file = tempfile::NamedTempFile::new().unwrap();
+        engine.start_trace(trace_file.path())?;
+        for i in 0..500_i32 {
+            engine.insert_tuple(0, &Tuple::new(i.to_le_bytes().repeat(25).into()))?;
+        }
+        assert_eq!(500, engine.scan(0)?.count());
+        engine.stop_trace()?;
+
+        // Accesses after the trace is stopped aren't recorded.
+        assert_eq!(500, engine.scan(0)?.count());
+
+        // Every pin is matched by an unpin.
+        let trace = read_trace(trace_file.path())?;
+        let pins: Vec<PageId> = trace
+            .iter()
+            .filter_map(|event| match event {
+                TraceEvent::Pin { page_id, .. } => Some(*page_id),
+                TraceEvent::Unpin { .. } => None,
+            })
+            .collect();
+        assert!(pins.len() > 500);
+        assert_eq!(trace.len(), 2 * pins.len());
+
+        // Replaying the trace serves every pin, and a pool holding every page only misses once
+        // per page.
+        let mut pages = pins.clone();
+        pages.sort();
+        pages.dedup();
+        let stats = replay_policy(&trace, Policy::LruK(2), &[10, pages.len()]);
+        for stats in &stats {
+            assert_eq!(0, stats.failed_pins);
+            assert_eq!(pins.len(), stats.hits + stats.misses);
+        }
+        assert_eq!(pages.len(), stats[1].misses);
+        assert!(stats[0].hit_ratio() <= stats[1].hit_ratio());
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_storage_engine_sample() -> Result<()> {
+        let disk_manager = get_disk_manager();
+        let engine = StorageEngine::new(get_bpm_arc(disk_manager));
+        let schema = Schema::new(&[Column::new("a".to_string(), Type::Integer)]);
+        engine.create_table_with_layout(0, "rows", TableLayout::Row)?;
+        engine.create_table_with_layout(1, "columns", TableLayout::Pax(schema))?;
+        for i in 0..2000_i32 {
+            engine.insert_tuple(0, &Tuple::new(i.to_le_bytes().repeat(25).into()))?;
+            engine.insert_tuple(1, &Tuple::new(i.to_le_bytes().to_vec().into()))?;
+        }
+
+        let sample = |table_id, method, seed| -> Result<Vec<schema::RecordId>> {
+            engine
+                .sample(table_id, method, seed)?
+                .map(|item| Ok(item?.0))
+                .collect()
+        };
+        let tuples_per_page = |rids: &[schema::RecordId]| {
+            let mut counts = HashMap::new();
+            for &rid in rids {
+                *counts.entry(RecordId::from(rid).page_id()).or_insert(0) += 1;
+            }
+            counts
+        };
+
+        for table_id in [0, 1] {
+            let all: Vec<_> = engine
+                .scan(table_id)?
+                .map(|item| Ok(item?.0))
+                .collect::<Result<_>>()?;
+            let pages = tuples_per_page(&all);
+
+            // A sample is reproducible from its seed.
+            for method in [SampleMethod::System(0.5), SampleMethod::Bernoulli(0.5)] {
+                assert_eq!(sample(table_id, method, 7)?, sample(table_id, method, 7)?);
+                assert_ne!(sample(table_id, method, 7)?, sample(table_id, method, 8)?);
+                assert_eq!(all, sample(table_id, method_with(method, 1.0), 7)?);
+                assert!(sample(table_id, method_with(method, 0.0), 7)?.is_empty());
+            }
+
+            // Page-level samples hold every tuple of the pages they picked, and nothing else.
+            let system = tuples_per_page(&sample(table_id, SampleMethod::System(0.5), 1)?);
+            assert!(!system.is_empty() && system.len() < pages.len());
+            assert!(system
+                .iter()
+                .all(|(page_id, &count)| pages[page_id] == count));
+
+            // Row-level samples pick tuples from (almost) every page.
+            let bernoulli = sample(table_id, SampleMethod::Bernoulli(0.3), 1)?;
+            assert!((400..800).contains(&bernoulli.len()));
+            assert!(tuples_per_page(&bernoulli).len() * 10 >= pages.len() * 9);
+        }
+
+        assert!(engine.sample(0, SampleMethod::System(1.5), 0).is_err());
+        assert!(engine.sample(1, SampleMethod::Bernoulli(-0.1), 0).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_storage_engine_vacuum_worker() -> Result<()> {
+        let disk_manager = get_disk_manager();
+        let engine = StorageEngine::new(get_bpm_arc(disk_manager));
+        let schema = Schema::new(&[Column::new("a".to_string(), Type::Integer)]);
+        engine.create_table_with_layout(0, "rows", TableLayout::Row)?;
+        engine.create_table_with_layout(1, "columns", TableLayout::Pax(schema))?;
+        assert!(engine.vacuum(1, VacuumOptions::default()).is_err());
+
+        let tuples: Vec<Tuple> = (0..1000_i32)
+            .map(|i| Tuple::new(i.to_le_bytes().repeat(25).into()))
+            .collect();
+        let rids = engine.insert_tuples(0, &tuples)?;
+        let page_cnt = page_ids(&engine, 0)?.len();
+
+        let interval = Duration::from_millis(5);
+        let worker = engine.start_vacuum_worker(0, VacuumOptions::default(), interval)?;
+        for &rid in &rids[100..900] {
+            engine.delete_tuple(0, rid)?;
+        }
+        // Wait for the worker to free the pages the deletes emptied.
+        for _ in 0..1000 {
+            if page_ids(&engine, 0)?.len() < page_cnt {
+                break;
+            }
+            thread::sleep(interval);
+        }
+        let report = worker.stop()?;
+        assert!(report.pages_reclaimed > 0);
+        assert_eq!(
+            page_cnt - report.pages_reclaimed,
+            page_ids(&engine, 0)?.len()
+        );
+        assert_eq!(200, scan_all(&engine, 0)?.len());
+
+        // A worker stops with an error once its table is gone.
+        let worker = engine.start_vacuum_worker(0, VacuumOptions::default(), interval)?;
+        engine.drop_table(0)?;
+        thread::sleep(interval * 10);
+        assert!(worker.stop().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_storage_engine_table_stats() -> Result<()> {
+        let disk_manager = get_disk_manager();
+        let engine = StorageEngine::new(get_bpm_arc(disk_manager));
+        let schema = Schema::new(&[
+            Column::new("a".to_string(), Type::Integer),
+            Column::new("b".to_string(), Type::Varchar),
+        ]);
+        engine.create_table_with_layout(0, "rows", TableLayout::Row)?;
+        engine.create_table_with_layout(1, "columns", TableLayout::Pax(schema))?;
+        assert_eq!(
+            TableStats::default().row_count,
+            engine.table_stats(0)?.row_count
+        );
+        assert_eq!(1, engine.table_stats(1)?.page_count);
+
+        for table_id in [0, 1] {
+            let rids = (0..2000)
+                .map(|i| {
+                    let fields = [
+                        Field::Integer(i),
+                        Field::Varchar("x".repeat(i as usize % 10)),
+                    ];
+                    engine.insert_tuple(table_id, &Tuple::new(Serde::serialize(&fields).into()))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            for &rid in &rids[..500] {
+                engine.delete_tuple(table_id, rid)?;
+            }
+            let fields = [Field::Integer(0), Field::Varchar("y".repeat(20))];
+            engine.update_tuple(
+                table_id,
+                rids[500],
+                &Tuple::new(Serde::serialize(&fields).into()),
+            )?;
+
+            let stats = engine.table_stats(table_id)?;
+            let tuples = scan_all(&engine, table_id)?;
+            assert_eq!(tuples.len() as u64, stats.row_count);
+            let tuple_bytes: usize = tuples.iter().map(Vec::len).sum();
+            assert_eq!(tuple_bytes as u64, stats.tuple_bytes);
+            assert_eq!(tuple_bytes as f64 / 1500.0, stats.avg_tuple_size());
+            let chain_page_ids = match engine.table(table_id)? {
+                TableStorage::Row(table_heap) => table_heap.read().unwrap().chain_page_ids()?,
+                TableStorage::Pax(table_heap) => table_heap.read().unwrap().chain_page_ids()?,
+                TableStorage::Lsm(_) | TableStorage::Columnar(_) => unreachable!(),
+            };
+            assert_eq!(chain_page_ids.len() as u64, stats.page_count);
+            assert!(stats.deleted_ratio() > 0.0);
+        }
+        // Row tables reclaim the space of deleted tuples, PAX tables don't.
+        assert!(engine.table_stats(0)?.deleted_count < 500);
+        assert_eq!(500, engine.table_stats(1)?.deleted_count);
+        assert!(engine.table_stats(2).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_storage_engine_lsm() -> Result<()> {
+        let disk_manager = get_disk_manager();
+        let schema = Schema::new(&[
+            Column::new("a".to_string(), Type::Integer),
+            Column::new("b".to_string(), Type::Varchar),
+        ]);
+        let tuple = |i: i32, b: &str| {
+            let fields = [Field::Integer(i), Field::Varchar(b.to_string())];
+            Tuple::new(Serde::serialize(&fields).into())
+        };
+
+        let (directory_page_id, expected) = {
+            let engine = StorageEngine::new(get_bpm_arc(disk_manager.clone()));
+            engine.create_table_with_layout(0, "log", TableLayout::Lsm)?;
+            // Enough tuples for the memtable to be flushed and runs to be merged.
+            let tuples: Vec<_> = (0..5000).map(|i| tuple(i, &"x".repeat(50))).collect();
+            let rids = engine.insert_tuples(0, &tuples)?;
+            assert!(engine.table_stats(0)?.page_count > 0);
+            for &rid in rids.iter().step_by(4) {
+                engine.delete_tuple(0, rid)?;
+            }
+            engine.update_tuple(0, rids[1], &tuple(-1, "updated"))?;
+            assert_eq!(
+                tuple(-1, "updated").data(),
+                engine.get_tuple(0, rids[1])?.data()
+            );
+
+            // Tuples come out in record id order, which is insertion order.
+            let tuples: Vec<_> = engine.scan(0)?.collect::<Result<_>>()?;
+            assert_eq!(3750, tuples.len());
+            assert!(tuples.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+            // Scans can be resumed from a cursor, and split into partitions.
+            let mut scan = engine.scan(0)?;
+            let mut paged = Vec::new();
+            loop {
+                paged.extend(scan.by_ref().take(1000).collect::<Result<Vec<_>>>()?);
+                let cursor = ScanCursor::from_bytes(&scan.cursor().to_bytes())?;
+                if cursor.is_exhausted() {
+                    break;
+                }
+                scan = engine.scan_from(0, cursor.record_id())?;
+            }
+            let rids: Vec<_> = tuples.iter().map(|(rid, _)| *rid).collect();
+            assert_eq!(rids, paged.iter().map(|(rid, _)| *rid).collect::<Vec<_>>());
+            let partitioned: Vec<_> = engine
+                .scan_partitions(0, 3)?
+                .into_iter()
+                .flatten()
+                .map(|item| item.map(|(rid, _)| rid))
+                .collect::<Result<_>>()?;
+            assert_eq!(rids, partitioned);
+
+            // Filters are applied as the tuples are merged.
+            let predicate = Predicate::compare(0, CompareOp::Lt, 100);
+            let filtered = engine.scan_filtered(0, &schema, &[0], Some(predicate))?;
+            assert_eq!(75, filtered.count());
+
+            assert!(engine.vacuum(0, VacuumOptions::default()).is_err());
+            assert!(engine.scan_columns(0, vec![0]).is_err());
+            (engine.directory_page_id(), scan_all(&engine, 0)?)
+        };
+
+        // Dropping the engine flushed the memtable, so nothing is lost on reopening it.
+        let engine = StorageEngine::open(get_bpm_arc(disk_manager), directory_page_id)?;
+        assert_eq!(expected, scan_all(&engine, 0)?);
+        assert_eq!(3750, engine.table_stats(0)?.row_count);
+        let rid = engine.insert_tuple(0, &tuple(5000, "after"))?;
+        assert_eq!(
+            tuple(5000, "after").data(),
+            engine.get_tuple(0, rid)?.data()
+        );
+
+        let page_ids = page_ids(&engine, 0)?;
+        engine.drop_table(0)?;
+        assert!(engine.scan(0).is_err());
+        assert!(page_ids
+            .iter()
+            .all(|&page_id| BufferPoolManager::fetch_page_handle(
+                &engine.bpm,
+                page_id,
+                AccessType::Unknown
+            )
+            .is_err()));
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_storage_engine_columnar() -> Result<()> {
+        let disk_manager = get_disk_manager();
+        let schema = Schema::new(&[
+            Column::new("a".to_string(), Type::Integer),
+            Column::new("b".to_string(), Type::Varchar),
+        ]);
+        let tuple = |i: i32, b: &str| {
+            let fields = [Field::Integer(i), Field::Varchar(b.to_string())];
+            Tuple::new(Serde::serialize(&fields).into())
+        };
+
+        let (directory_page_id, expected) = {
+            let engine = StorageEngine::new(get_bpm_arc(disk_manager.clone()));
+            engine.create_table_with_layout(0, "facts", TableLayout::Columnar(schema.clone()))?;
+            let tuples: Vec<_> = (0..5000)
+                .map(|i| tuple(i, ["x", "y"][i as usize % 2]))
+                .collect();
+            let rids = engine.insert_tuples(0, &tuples)?;
+            for &rid in rids.iter().step_by(4) {
+                engine.delete_tuple(0, rid)?;
+            }
+            engine.update_tuple(0, rids[1], &tuple(-1, "updated"))?;
+            assert_eq!(
+                tuple(-1, "updated").data(),
+                engine.get_tuple(0, rids[1])?.data()
+            );
+            assert!(engine.get_tuple(0, rids[0]).is_err());
+
+            // Tuples come out in insertion order.
+            let tuples: Vec<_> = engine.scan(0)?.collect::<Result<_>>()?;
+            assert_eq!(3750, tuples.len());
+            assert!(tuples.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+            // Batch scans only read the requested columns, and can be filtered on others.
+            let predicate = Predicate::compare(0, CompareOp::Lt, 100);
+            let batches: Vec<_> = engine
+                .scan_batches(0, vec![1], Some(predicate.clone()))?
+                .collect::<Result<_>>()?;
+            let values: Vec<_> = batches.iter().flat_map(|batch| batch.column(0)).collect();
+            assert_eq!(75, values.len());
+            assert_eq!(&Field::Varchar("updated".to_string()), values[0]);
+            assert!(engine.scan_batches(0, vec![2], None).is_err());
+            let filtered = engine.scan_filtered(0, &schema, &[1], Some(predicate))?;
+            assert_eq!(75, filtered.count());
+            let (_, first) = engine.scan_columns(0, vec![0])?.next().unwrap()?;
+            assert_eq!(Serde::serialize(&[Field::Integer(-1)]), first.data());
+
+            // Scans can be resumed from a cursor, and split into partitions.
+            let mut scan = engine.scan(0)?;
+            let mut paged = Vec::new();
+            loop {
+                paged.extend(scan.by_ref().take(1000).collect::<Result<Vec<_>>>()?);
+                let cursor = ScanCursor::from_bytes(&scan.cursor().to_bytes())?;
+                if cursor.is_exhausted() {
+                    break;
+                }
+                scan = engine.scan_from(0, cursor.record_id())?;
+            }
+            let rids: Vec<_> = tuples.iter().map(|(rid, _)| *rid).collect();
+            assert_eq!(rids, paged.iter().map(|(rid, _)| *rid).collect::<Vec<_>>());
+            let partitioned: Vec<_> = engine
+                .scan_partitions(0, 3)?
+                .into_iter()
+                .flatten()
+                .map(|item| item.map(|(rid, _)| rid))
+                .collect::<Result<_>>()?;
+            assert_eq!(rids, partitioned);
+
+            assert!(engine.vacuum(0, VacuumOptions::default()).is_err());
+            (engine.directory_page_id(), scan_all(&engine, 0)?)
+        };
+
+        // Dropping the engine wrote its pages out.
+        let engine = StorageEngine::open(get_bpm_arc(disk_manager), directory_page_id)?;
+        assert_eq!(expected, scan_all(&engine, 0)?);
+        assert_eq!(3750, engine.table_stats(0)?.row_count);
+        let rid = engine.insert_tuple(0, &tuple(5000, "after"))?;
+        assert_eq!(
+            tuple(5000, "after").data(),
+            engine.get_tuple(0, rid)?.data()
+        );
+
+        let page_ids = page_ids(&engine, 0)?;
+        engine.drop_table(0)?;
+        assert!(engine.scan(0).is_err());
+        assert!(page_ids
+            .iter()
+            .all(|&page_id| BufferPoolManager::fetch_page_handle(
+                &engine.bpm,
+                page_id,
+                AccessType::Unknown
+            )
+            .is_err()));
+
+        Ok(())
+    }
+
+    fn method_with(method: SampleMethod, probability: f64) -> SampleMethod {
+        match method {
+            SampleMethod::System(_) => SampleMethod::System(probability),
+            SampleMethod::Bernoulli(_) => SampleMethod::Bernoulli(probability),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! Fixtures shared by the crate's tests.
+
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::buffer_pool::BufferPoolManager;
+use crate::disk::disk_manager::DiskManager;
+use crate::replacer::lru_k_replacer::LrukReplacer;
+
+/// Opens a disk manager over a new temporary file, so that tests don't write to the database
+/// file checked in next to the sources. The file is unlinked right away and goes away with the
+/// disk manager, which keeps it open.
+pub(crate) fn get_disk_manager() -> Arc<Mutex<DiskManager>> {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let disk_manager = DiskManager::new(file.path().to_str().unwrap()).unwrap();
+    Arc::new(Mutex::new(disk_manager))
+}
+
+fn get_bpm(pool_size: usize, disk_manager: Arc<Mutex<DiskManager>>) -> BufferPoolManager {
+    let replacer = Box::new(LrukReplacer::new(5));
+    BufferPoolManager::new(pool_size, disk_manager, replacer)
+}
+
+/// Creates a buffer pool of 10 frames over `disk_manager`. Tests that reopen what they wrote
+/// pass the same disk manager to a second pool, which starts with nothing cached.
+pub(crate) fn get_bpm_arc(disk_manager: Arc<Mutex<DiskManager>>) -> Arc<RwLock<BufferPoolManager>> {
+    Arc::new(RwLock::new(get_bpm(10, disk_manager)))
+}
+
+pub(crate) fn get_bpm_with_pool_size(pool_size: usize) -> BufferPoolManager {
+    get_bpm(pool_size, get_disk_manager())
+}
+
+pub(crate) fn get_bpm_arc_with_pool_size(pool_size: usize) -> Arc<RwLock<BufferPoolManager>> {
+    Arc::new(RwLock::new(get_bpm_with_pool_size(pool_size)))
+}
@@ -0,0 +1,156 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use rustdb_error::{errdata, Error};
+
+use crate::replacer::replacer::AccessType;
+use crate::typedef::PageId;
+use crate::Result;
+
+/// A single buffer pool event in an access trace.
+///
+/// Traces are stored as text, one event per line: `pin <page_id> <access_type>` or
+/// `unpin <page_id>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// A page was pinned, i.e. created or fetched.
+    Pin {
+        page_id: PageId,
+        access_type: AccessType,
+    },
+    /// A page was unpinned.
+    Unpin { page_id: PageId },
+}
+
+impl std::fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TraceEvent::Pin {
+                page_id,
+                access_type,
+            } => write!(f, "pin {} {}", page_id, access_type_name(*access_type)),
+            TraceEvent::Unpin { page_id } => write!(f, "unpin {}", page_id),
+        }
+    }
+}
+
+impl FromStr for TraceEvent {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        match parts.as_slice() {
+            ["pin", page_id, access_type] => Ok(TraceEvent::Pin {
+                page_id: page_id.parse()?,
+                access_type: parse_access_type(access_type)?,
+            }),
+            ["unpin", page_id] => Ok(TraceEvent::Unpin {
+                page_id: page_id.parse()?,
+            }),
+            _ => errdata!("Invalid trace event: {}", line),
+        }
+    }
+}
+
+fn access_type_name(access_type: AccessType) -> &'static str {
+    match access_type {
+        AccessType::Unknown => "unknown",
+        AccessType::Lookup => "lookup",
+        AccessType::Scan => "scan",
+        AccessType::Index => "index",
+    }
+}
+
+fn parse_access_type(name: &str) -> Result<AccessType> {
+    match name {
+        "unknown" => Ok(AccessType::Unknown),
+        "lookup" => Ok(AccessType::Lookup),
+        "scan" => Ok(AccessType::Scan),
+        "index" => Ok(AccessType::Index),
+        _ => errdata!("Invalid access type: {}", name),
+    }
+}
+
+/// Reads all the events of the trace file at `path`.
+pub fn read_trace(path: impl AsRef<Path>) -> Result<Vec<TraceEvent>> {
+    let file = File::open(path)?;
+    BufReader::new(file)
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|line| line?.parse())
+        .collect()
+}
+
+/// Appends buffer pool events to a trace file.
+///
+/// Recording happens on the buffer pool's hot path, where there's no good way to surface an IO
+/// error, so the first error is kept and reported by [`TraceRecorder::finish`] instead.
+#[derive(Debug)]
+pub(crate) struct TraceRecorder {
+    writer: BufWriter<File>,
+    error: Option<std::io::Error>,
+}
+
+impl TraceRecorder {
+    /// Creates a recorder writing to the file at `path`, truncating it if it exists.
+    pub(crate) fn new(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .map_err(|e| Error::IO(format!("Unable to open file {}: {}", path.display(), e)))?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            error: None,
+        })
+    }
+
+    /// Appends an event to the trace.
+    pub(crate) fn record(&mut self, event: TraceEvent) {
+        if self.error.is_none() {
+            if let Err(e) = writeln!(self.writer, "{}", event) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// Flushes the trace to disk, reporting the first error hit while recording, if any.
+    pub(crate) fn finish(mut self) -> Result<()> {
+        if let Some(e) = self.error.take() {
+            return Err(e.into());
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_event_round_trip() {
+        let events = [
+            TraceEvent::Pin {
+                page_id: 1,
+                access_type: AccessType::Unknown,
+            },
+            TraceEvent::Pin {
+                page_id: 2,
+                access_type: AccessType::Scan,
+            },
+            TraceEvent::Unpin { page_id: 2 },
+            TraceEvent::Pin {
+                page_id: 3,
+                access_type: AccessType::Index,
+            },
+        ];
+        for event in events {
+            assert_eq!(Ok(event), event.to_string().parse());
+        }
+
+        assert!("pin 1".parse::<TraceEvent>().is_err());
+        assert!("pin 1 sideways".parse::<TraceEvent>().is_err());
+        assert!("unpin one".parse::<TraceEvent>().is_err());
+    }
+}
@@ -0,0 +1,5 @@
+//! Buffer pool access traces. The buffer pool can record every pin and unpin it serves to a
+//! trace file, which can later be replayed against different replacement policies and pool sizes
+//! to compare their hit ratios offline.
+pub mod access_trace;
+pub mod replay;
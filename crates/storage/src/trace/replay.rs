@@ -0,0 +1,308 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::str::FromStr;
+
+use rustdb_error::{errinput, Error};
+
+use super::access_trace::{read_trace, TraceEvent};
+use crate::replacer::arc_replacer::ArcReplacer;
+use crate::replacer::clock_replacer::ClockReplacer;
+use crate::replacer::lru_k_replacer::LrukReplacer;
+use crate::replacer::replacer::Replacer;
+use crate::replacer::two_queue_replacer::TwoQueueReplacer;
+use crate::typedef::{FrameId, PageId};
+use crate::Result;
+
+/// A replacement policy a trace can be replayed against.
+///
+/// Policies are named `lru-<k>` (e.g. `lru-2`), `clock`, `arc` and `2q`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    LruK(usize),
+    Clock,
+    Arc,
+    TwoQueue,
+}
+
+impl Policy {
+    /// Creates a replacer implementing the policy for a pool of `pool_size` frames.
+    pub(crate) fn replacer(self, pool_size: usize) -> Box<dyn Replacer> {
+        match self {
+            Policy::LruK(k) => Box::new(LrukReplacer::new(k)),
+            Policy::Clock => Box::new(ClockReplacer::new(pool_size)),
+            Policy::Arc => Box::new(ArcReplacer::new(pool_size)),
+            Policy::TwoQueue => Box::new(TwoQueueReplacer::new(pool_size)),
+        }
+    }
+}
+
+impl std::fmt::Display for Policy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Policy::LruK(k) => write!(f, "lru-{}", k),
+            Policy::Clock => write!(f, "clock"),
+            Policy::Arc => write!(f, "arc"),
+            Policy::TwoQueue => write!(f, "2q"),
+        }
+    }
+}
+
+impl FromStr for Policy {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "clock" => Ok(Policy::Clock),
+            "arc" => Ok(Policy::Arc),
+            "2q" => Ok(Policy::TwoQueue),
+            _ => match name.strip_prefix("lru-").map(str::parse::<usize>) {
+                Some(Ok(k)) if k > 0 => Ok(Policy::LruK(k)),
+                _ => errinput!("Unknown replacement policy: {}", name),
+            },
+        }
+    }
+}
+
+/// The outcome of replaying a trace with a given pool size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayStats {
+    pub pool_size: usize,
+    /// Pins of a page that was already in the pool.
+    pub hits: usize,
+    /// Pins that had to load the page into a frame.
+    pub misses: usize,
+    /// Pins that could not be served because every frame was pinned.
+    pub failed_pins: usize,
+}
+
+impl ReplayStats {
+    /// Returns the fraction of served pins that were hits.
+    pub fn hit_ratio(&self) -> f64 {
+        let served = self.hits + self.misses;
+        if served == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / served as f64
+    }
+}
+
+/// A buffer pool that only tracks which page each frame holds, without any page data.
+struct SimulatedPool {
+    replacer: Box<dyn Replacer>,
+    page_table: HashMap<PageId, FrameId>,
+    frame_pages: Vec<Option<PageId>>,
+    pin_counts: Vec<usize>,
+    free_list: VecDeque<FrameId>,
+    failed_pins: HashMap<PageId, usize>, // Failed pins whose unpins must be ignored
+    stats: ReplayStats,
+}
+
+impl SimulatedPool {
+    fn new(pool_size: usize, replacer: Box<dyn Replacer>) -> Self {
+        Self {
+            replacer,
+            page_table: HashMap::new(),
+            frame_pages: vec![None; pool_size],
+            pin_counts: vec![0; pool_size],
+            free_list: (0..pool_size).collect(),
+            failed_pins: HashMap::new(),
+            stats: ReplayStats {
+                pool_size,
+                hits: 0,
+                misses: 0,
+                failed_pins: 0,
+            },
+        }
+    }
+
+    fn apply(&mut self, event: TraceEvent) {
+        match event {
+            TraceEvent::Pin {
+                page_id,
+                access_type,
+            } => {
+                let frame_id = match self.page_table.get(&page_id) {
+                    Some(&frame_id) => {
+                        self.stats.hits += 1;
+                        frame_id
+                    }
+                    None => {
+                        let Some(frame_id) =
+                            self.free_list.pop_front().or_else(|| self.replacer.evict())
+                        else {
+                            self.stats.failed_pins += 1;
+                            *self.failed_pins.entry(page_id).or_default() += 1;
+                            return;
+                        };
+                        if let Some(evicted) = self.frame_pages[frame_id].replace(page_id) {
+                            self.page_table.remove(&evicted);
+                        }
+                        self.page_table.insert(page_id, frame_id);
+                        self.replacer.record_page(frame_id, page_id);
+                        self.stats.misses += 1;
+                        frame_id
+                    }
+                };
+                self.pin_counts[frame_id] += 1;
+                self.replacer.record_access(frame_id, access_type);
+                self.replacer.pin(frame_id);
+            }
+            TraceEvent::Unpin { page_id } => {
+                if let Some(count) = self.failed_pins.get_mut(&page_id) {
+                    *count -= 1;
+                    if *count == 0 {
+                        self.failed_pins.remove(&page_id);
+                    }
+                    return;
+                }
+                let Some(&frame_id) = self.page_table.get(&page_id) else {
+                    return;
+                };
+                if self.pin_counts[frame_id] > 0 {
+                    self.pin_counts[frame_id] -= 1;
+                    if self.pin_counts[frame_id] == 0 {
+                        self.replacer.unpin(frame_id);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Replays `trace` against `replacer` in a simulated pool of `pool_size` frames.
+pub(crate) fn replay(
+    trace: &[TraceEvent],
+    pool_size: usize,
+    replacer: Box<dyn Replacer>,
+) -> ReplayStats {
+    let mut pool = SimulatedPool::new(pool_size, replacer);
+    for &event in trace {
+        pool.apply(event);
+    }
+    pool.stats
+}
+
+/// Replays `trace` under `policy` once for each of the given pool sizes.
+pub fn replay_policy(
+    trace: &[TraceEvent],
+    policy: Policy,
+    pool_sizes: &[usize],
+) -> Vec<ReplayStats> {
+    pool_sizes
+        .iter()
+        .map(|&pool_size| replay(trace, pool_size, policy.replacer(pool_size)))
+        .collect()
+}
+
+/// Reads the trace file at `path` and replays it under `policy` for each of the given pool sizes.
+pub fn replay_file(
+    path: impl AsRef<Path>,
+    policy: Policy,
+    pool_sizes: &[usize],
+) -> Result<Vec<ReplayStats>> {
+    let trace = read_trace(path)?;
+    Ok(replay_policy(&trace, policy, pool_sizes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replacer::replacer::AccessType;
+
+    // Pins and unpins pages `first..=last` in order, `rounds` times.
+    fn looping_trace(first: PageId, last: PageId, rounds: usize) -> Vec<TraceEvent> {
+        let mut trace = Vec::new();
+        for _ in 0..rounds {
+            for page_id in first..=last {
+                trace.push(TraceEvent::Pin {
+                    page_id,
+                    access_type: AccessType::Scan,
+                });
+                trace.push(TraceEvent::Unpin { page_id });
+            }
+        }
+        trace
+    }
+
+    #[test]
+    fn test_replay_pool_sizes() {
+        let trace = looping_trace(1, 8, 3);
+        let stats = replay_policy(&trace, Policy::Clock, &[4, 8, 16]);
+
+        // A loop over more pages than frames defeats CLOCK entirely.
+        assert_eq!((0, 24), (stats[0].hits, stats[0].misses));
+        // Once the pool holds the whole loop, only the first round misses.
+        assert_eq!((16, 8), (stats[1].hits, stats[1].misses));
+        assert_eq!((16, 8), (stats[2].hits, stats[2].misses));
+        assert!((stats[1].hit_ratio() - 2.0 / 3.0).abs() < 1e-9);
+        assert!(stats.iter().all(|stats| stats.failed_pins == 0));
+    }
+
+    #[test]
+    fn test_replay_lru_k_resists_scans() {
+        // Pages 1 and 2 are each looked up twice per round, interleaved with a scan over pages
+        // 10 to 49 that streams two new pages through the pool each round.
+        let mut trace = Vec::new();
+        for round in 0..20 {
+            for page_id in [1, 2, 1, 2] {
+                trace.push(TraceEvent::Pin {
+                    page_id,
+                    access_type: AccessType::Lookup,
+                });
+                trace.push(TraceEvent::Unpin { page_id });
+            }
+            trace.extend(looping_trace(10 + 2 * round, 11 + 2 * round, 1));
+        }
+
+        let lru_1 = replay_policy(&trace, Policy::LruK(1), &[3])[0];
+        let lru_2 = replay_policy(&trace, Policy::LruK(2), &[3])[0];
+        assert_eq!(lru_1.hits + lru_1.misses, lru_2.hits + lru_2.misses);
+        assert!(lru_2.hits > lru_1.hits);
+    }
+
+    #[test]
+    fn test_replay_failed_pins() {
+        // Page 1 stays pinned, so a single frame can't serve page 2.
+        let trace = vec![
+            TraceEvent::Pin {
+                page_id: 1,
+                access_type: AccessType::Unknown,
+            },
+            TraceEvent::Pin {
+                page_id: 2,
+                access_type: AccessType::Unknown,
+            },
+            TraceEvent::Unpin { page_id: 2 },
+            TraceEvent::Pin {
+                page_id: 1,
+                access_type: AccessType::Unknown,
+            },
+            TraceEvent::Unpin { page_id: 1 },
+            TraceEvent::Unpin { page_id: 1 },
+        ];
+
+        let stats = replay_policy(&trace, Policy::Arc, &[1, 2]);
+        assert_eq!(
+            (1, 1, 1),
+            (stats[0].hits, stats[0].misses, stats[0].failed_pins)
+        );
+        assert_eq!(
+            (1, 2, 0),
+            (stats[1].hits, stats[1].misses, stats[1].failed_pins)
+        );
+    }
+
+    #[test]
+    fn test_policy_names() {
+        for policy in [
+            Policy::LruK(2),
+            Policy::Clock,
+            Policy::Arc,
+            Policy::TwoQueue,
+        ] {
+            assert_eq!(Ok(policy), policy.to_string().parse());
+        }
+        assert!("lru-0".parse::<Policy>().is_err());
+        assert!("mru".parse::<Policy>().is_err());
+    }
+}